@@ -0,0 +1,314 @@
+//! Maintenance operations behind `aptos-tracking-admin`, the things operators used to
+//! do with hand written SQL against `processed_block`, `wallet_tracked` and
+//! `token_info`.
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use log::warn;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use sea_query::Expr;
+use std::str::FromStr;
+
+use crate::entities::sea_orm_active_enums::{Place, Status};
+use crate::entities::{prelude::*, processed_block, token_info, wallet_tracked};
+use crate::error::Err;
+use crate::spam;
+use crate::tx::TxQueryClient;
+
+/// Rows listed as stuck, the oldest first.
+const STUCK_LIMIT: u64 = 20;
+/// Asset types per indexer metadata query.
+const METADATA_CHUNK: usize = 100;
+/// Rows per insert when marking a range skipped.
+const INSERT_CHUNK: usize = 1_000;
+
+/// `processed_block` as seen by one network's workers.
+#[derive(Debug)]
+pub struct NetworkStatus {
+    pub network: String,
+    pub bottom: Option<u64>,
+    pub highest: Option<u64>,
+    pub counts: Vec<(Status, i64)>,
+    /// Missing `from..=to` ranges above the bottom pointer.
+    pub gaps: Vec<(u64, u64)>,
+    /// Rows still `processing` or `error`, the ones a crashed worker leaves behind.
+    pub stuck: Vec<processed_block::Model>,
+}
+
+pub async fn status(db: &DatabaseConnection, network: &str) -> Result<NetworkStatus, Err> {
+    let bottom =
+        processed_block::Model::get_last_block(db, network, None, Some(Place::Bottom), true)
+            .await?;
+    let highest = processed_block::Model::get_last_block(db, network, None, None, false).await?;
+    let counts = ProcessedBlock::find()
+        .select_only()
+        .column(processed_block::Column::Status)
+        .column_as(Expr::col(processed_block::Column::Block).count(), "count")
+        .filter(processed_block::Column::Network.eq(network))
+        .group_by(processed_block::Column::Status)
+        .into_tuple::<(Status, i64)>()
+        .all(db)
+        .await?;
+    let mut gaps = vec![];
+    if let Some(bottom) = bottom {
+        let heights = ProcessedBlock::find()
+            .select_only()
+            .column(processed_block::Column::Block)
+            .filter(processed_block::Column::Network.eq(network))
+            .filter(processed_block::Column::Block.gte(i64::try_from(bottom)?))
+            .order_by_asc(processed_block::Column::Block)
+            .into_tuple::<i64>()
+            .all(db)
+            .await?;
+        let mut previous = bottom;
+        for height in heights {
+            let height = u64::try_from(height)?;
+            if height > previous + 1 {
+                gaps.push((previous + 1, height - 1));
+            }
+            previous = height;
+        }
+    }
+    let stuck = ProcessedBlock::find()
+        .filter(processed_block::Column::Network.eq(network))
+        .filter(processed_block::Column::Status.is_in([Status::Processing, Status::Error]))
+        .order_by_asc(processed_block::Column::Block)
+        .limit(STUCK_LIMIT)
+        .all(db)
+        .await?;
+    Ok(NetworkStatus {
+        network: network.to_string(),
+        bottom,
+        highest,
+        counts,
+        gaps,
+        stuck,
+    })
+}
+
+/// Deletes the rows of `from..=to` so the workers claim those blocks again, alerts
+/// included. A bottom pointer at or above `from` is moved to `from - 1`. Returns the
+/// number of rows deleted.
+pub async fn reprocess(
+    db: &DatabaseConnection,
+    network: &str,
+    from: u64,
+    to: u64,
+) -> Result<u64, Err> {
+    let below = match (from <= to, from.checked_sub(1)) {
+        (true, Some(below)) => i64::try_from(below)?,
+        _ => return Err(Err::InvalidRange),
+    };
+    let txn = db.begin().await?;
+    let deleted = ProcessedBlock::delete_many()
+        .filter(processed_block::Column::Network.eq(network))
+        .filter(processed_block::Column::Block.between(i64::try_from(from)?, i64::try_from(to)?))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+    let bottom = ProcessedBlock::find()
+        .filter(processed_block::Column::Network.eq(network))
+        .filter(processed_block::Column::Place.eq(Place::Bottom))
+        .order_by_asc(processed_block::Column::Block)
+        .one(&txn)
+        .await?;
+    if bottom.map_or(true, |bottom| bottom.block > below) {
+        move_bottom(&txn, network, below).await?;
+    }
+    txn.commit().await?;
+    Ok(deleted)
+}
+
+/// Makes `block` the only bottom row, inserting it as skipped when it is missing.
+async fn move_bottom<C: ConnectionTrait>(db: &C, network: &str, block: i64) -> Result<(), Err> {
+    ProcessedBlock::update_many()
+        .col_expr(processed_block::Column::Place, Expr::value(Place::Old))
+        .filter(processed_block::Column::Network.eq(network))
+        .filter(processed_block::Column::Place.eq(Place::Bottom))
+        .exec(db)
+        .await?;
+    match ProcessedBlock::find_by_id((network.to_string(), block))
+        .one(db)
+        .await?
+    {
+        Some(row) => {
+            let mut row = processed_block::ActiveModel::from(row);
+            row.place = Set(Place::Bottom);
+            row.update(db).await?;
+        }
+        None => {
+            processed_block::ActiveModel {
+                network: Set(network.to_string()),
+                block: Set(block),
+                status: Set(Status::Skipped),
+                place: Set(Place::Bottom),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Marks `from..=to` skipped: missing heights get a skipped row and `processing` or
+/// `error` rows are updated, so the bottom pointer can move past the range. Returns
+/// the number of rows inserted or updated.
+pub async fn skip(db: &DatabaseConnection, network: &str, from: u64, to: u64) -> Result<u64, Err> {
+    if from > to {
+        return Err(Err::InvalidRange);
+    }
+    let (from, to) = (i64::try_from(from)?, i64::try_from(to)?);
+    let txn = db.begin().await?;
+    let mut changed = ProcessedBlock::update_many()
+        .col_expr(
+            processed_block::Column::Status,
+            Expr::value(Status::Skipped),
+        )
+        .filter(processed_block::Column::Network.eq(network))
+        .filter(processed_block::Column::Block.between(from, to))
+        .filter(processed_block::Column::Status.is_in([Status::Processing, Status::Error]))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+    let existing = ProcessedBlock::find()
+        .select_only()
+        .column(processed_block::Column::Block)
+        .filter(processed_block::Column::Network.eq(network))
+        .filter(processed_block::Column::Block.between(from, to))
+        .into_tuple::<i64>()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .collect::<ahash::AHashSet<i64>>();
+    let missing = (from..=to)
+        .filter(|block| !existing.contains(block))
+        .map(|block| processed_block::ActiveModel {
+            network: Set(network.to_string()),
+            block: Set(block),
+            status: Set(Status::Skipped),
+            place: Set(Place::Head),
+            ..Default::default()
+        })
+        .collect::<Vec<processed_block::ActiveModel>>();
+    for chunk in missing.chunks(INSERT_CHUNK) {
+        ProcessedBlock::insert_many(chunk.to_vec())
+            .exec_without_returning(&txn)
+            .await?;
+        changed += u64::try_from(chunk.len())?;
+    }
+    txn.commit().await?;
+    Ok(changed)
+}
+
+/// Subscriptions, optionally narrowed to one network and one chat.
+pub async fn subscriptions(
+    db: &DatabaseConnection,
+    network: Option<&str>,
+    chat_id: Option<i64>,
+) -> Result<Vec<wallet_tracked::Model>, Err> {
+    let select = WalletTracked::find().order_by_asc(wallet_tracked::Column::WalletId);
+    let select = match network {
+        Some(network) => select.filter(wallet_tracked::Column::Network.eq(network)),
+        None => select,
+    };
+    let select = match chat_id {
+        Some(chat_id) => select.filter(wallet_tracked::Column::ChatId.eq(chat_id)),
+        None => select,
+    };
+    Ok(select.all(db).await?)
+}
+
+/// Refreshes the name and decimals of `mints` (every `token_info` row when empty)
+/// from the indexer, inserting unknown mints unpriced. Returns the number of rows
+/// written.
+pub async fn resync_tokens(
+    db: &DatabaseConnection,
+    client: &TxQueryClient,
+    mints: Vec<String>,
+) -> Result<usize, Err> {
+    let mints = match mints.is_empty() {
+        true => {
+            TokenInfo::find()
+                .select_only()
+                .column(token_info::Column::Mint)
+                .into_tuple::<String>()
+                .all(db)
+                .await?
+        }
+        false => mints,
+    };
+    let mut written = 0;
+    for chunk in mints.chunks(METADATA_CHUNK) {
+        let metadata = client
+            .get_token_metadata(chunk.to_vec())
+            .await?
+            .data
+            .ok_or(Err::IndexerQuery)?
+            .fungible_asset_metadata;
+        for mint in chunk {
+            if !metadata.iter().any(|m| &m.asset_type == mint) {
+                warn!("no metadata for {mint}");
+            }
+        }
+        for metadata in metadata {
+            let row = token_info::ActiveModel {
+                mint: Set(metadata.asset_type.clone()),
+                name: Set(Some(metadata.symbol)),
+                decimal: Set(i16::from(metadata.decimals)),
+                ..Default::default()
+            };
+            match TokenInfo::find_by_id(metadata.asset_type).one(db).await? {
+                Some(_) => row.update(db).await?,
+                None => row.insert(db).await?,
+            };
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// Sets `is_skipped` of `mints` to one of the [`spam`] values, inserting unknown mints
+/// unpriced. Plain addresses are normalized like the indexer prints them. Returns the
+/// number of rows written.
+pub async fn set_skipped(
+    db: &DatabaseConnection,
+    mints: &[String],
+    value: i16,
+) -> Result<usize, Err> {
+    for mint in mints {
+        let mint = match AccountAddress::from_str(mint) {
+            Ok(address) => address.to_string(),
+            Err(_) => mint.clone(),
+        };
+        match TokenInfo::find_by_id(mint.clone()).one(db).await? {
+            Some(row) => {
+                let mut update = row.into_active_model();
+                update.is_skipped = Set(value);
+                update.update(db).await?;
+            }
+            None => {
+                token_info::ActiveModel {
+                    mint: Set(mint),
+                    is_skipped: Set(value),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+    }
+    Ok(mints.len())
+}
+
+/// Tokens left out of alerts, skipped by an operator or flagged as spam.
+pub async fn skipped_tokens(db: &DatabaseConnection) -> Result<Vec<token_info::Model>, Err> {
+    Ok(TokenInfo::find()
+        .filter(token_info::Column::IsSkipped.gt(spam::UNFLAGGED))
+        .order_by_asc(token_info::Column::Mint)
+        .all(db)
+        .await?)
+}
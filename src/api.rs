@@ -0,0 +1,466 @@
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use axum::extract::{Path, Query, State};
+use axum::http::{Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::{future::Pending, stream::Abortable};
+use log::{error, info};
+use sea_orm::{
+    ActiveEnum, ActiveModelTrait, ActiveValue::*, ColumnTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::db::UniqueWallet;
+use crate::entities::sea_orm_active_enums::{Place, Status, TrackType};
+use crate::entities::{prelude::*, processed_block, token_info, user, wallet_tracked};
+use crate::error::Err;
+use crate::metrics::metrics_handler;
+use crate::network::{Network, Networks};
+use crate::probe::{probe_router, Liveness, ProbeState};
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+#[derive(Clone)]
+pub struct ApiState {
+    db: Arc<DatabaseConnection>,
+    networks: Networks,
+    /// SHA3-256 digest of the api key.
+    api_key: Arc<Vec<u8>>,
+}
+
+impl ApiState {
+    pub fn new(db: Arc<DatabaseConnection>, networks: Networks, api_key: &str) -> Self {
+        ApiState {
+            db,
+            networks,
+            api_key: Arc::new(HashValue::sha3_256_of(api_key.as_bytes()).to_vec()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionResponse {
+    pub wallet_id: i64,
+    pub chat_id: i64,
+    pub user_id: i64,
+    pub wallet_address: String,
+    pub nickname: Option<String>,
+    pub track_type: String,
+    pub minimum_value: f64,
+    pub token_mode: String,
+    pub tokens: Vec<String>,
+    /// Received and sent thresholds, counted in the `_token` asset or USD when unset.
+    pub min_received: Option<f64>,
+    pub min_received_token: Option<String>,
+    pub min_sent: Option<f64>,
+    pub min_sent_token: Option<String>,
+    pub filter_expr: Option<String>,
+    pub muted_until: Option<String>,
+    pub digest: String,
+    pub network: String,
+    pub create_time: String,
+}
+
+impl From<wallet_tracked::Model> for SubscriptionResponse {
+    fn from(model: wallet_tracked::Model) -> Self {
+        let tokens = model.token_list().into_iter().map(String::from).collect();
+        SubscriptionResponse {
+            wallet_id: model.wallet_id,
+            chat_id: model.chat_id,
+            user_id: model.user_id,
+            wallet_address: model.wallet_address,
+            nickname: model.nickname,
+            track_type: model.track_type.to_value(),
+            minimum_value: model.minimum_value,
+            token_mode: model.token_mode.to_value(),
+            tokens,
+            min_received: model.min_received,
+            min_received_token: model.min_received_token,
+            min_sent: model.min_sent,
+            min_sent_token: model.min_sent_token,
+            filter_expr: model.filter_expr,
+            muted_until: model.muted_until.map(|until| until.to_rfc3339()),
+            digest: model.digest.to_value(),
+            network: model.network,
+            create_time: model.create_time.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListQuery {
+    pub chat_id: Option<i64>,
+    pub wallet_address: Option<String>,
+    pub network: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSubscription {
+    pub chat_id: i64,
+    pub user_id: i64,
+    pub wallet_address: String,
+    pub nickname: Option<String>,
+    pub track_type: Option<String>,
+    pub minimum_value: Option<f64>,
+    /// Defaults to the first configured network.
+    pub network: Option<String>,
+}
+
+/// Only the fields present in the body are changed, an empty nickname clears it.
+#[derive(Deserialize)]
+pub struct UpdateSubscription {
+    pub nickname: Option<String>,
+    pub track_type: Option<String>,
+    pub minimum_value: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct StatusResponse {
+    pub unique_wallets: usize,
+    pub subscriptions: usize,
+    pub tokens: usize,
+    pub networks: Vec<NetworkStatus>,
+}
+
+#[derive(Serialize)]
+pub struct NetworkStatus {
+    pub network: String,
+    pub unique_wallets: usize,
+    pub current_block: u64,
+    pub last_processed_block: Option<u64>,
+    pub bottom_block: Option<u64>,
+    pub head_block: Option<u64>,
+    pub blocks_behind: Option<u64>,
+    pub processing_blocks: u64,
+    pub error_blocks: u64,
+}
+
+pub async fn api_loop(
+    abort: Abortable<Pending<()>>,
+    db: Arc<DatabaseConnection>,
+    bookkeeping: Arc<DatabaseConnection>,
+    networks: Networks,
+    liveness: Arc<Liveness>,
+    config: Arc<Config>,
+) {
+    let addr = match config.api.addr.clone() {
+        Some(addr) => addr,
+        None => {
+            info!("API_ADDR not set, api disabled");
+            return;
+        }
+    };
+    let addr = match SocketAddr::from_str(&addr) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("API_ADDR parse error: {:?}", e);
+            return;
+        }
+    };
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .merge(probe_router(ProbeState {
+            db: db.clone(),
+            bookkeeping,
+            networks: networks.clone(),
+            liveness,
+            config: config.health.clone(),
+        }));
+    let app = match config.api.key.clone() {
+        Some(api_key) => app.merge(subscription_router(ApiState::new(db, networks, &api_key))),
+        None => {
+            info!("API_KEY not set, subscription api disabled");
+            app
+        }
+    };
+    info!("api listening on {}", addr);
+    if let Err(e) = axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(async move {
+            let _ = abort.await;
+        })
+        .await
+    {
+        error!("api server error: {:?}", e);
+    }
+}
+
+pub fn subscription_router(state: ApiState) -> Router {
+    Router::new()
+        .route(
+            "/subscriptions",
+            get(list_subscriptions).post(create_subscription),
+        )
+        .route(
+            "/subscriptions/:id",
+            get(get_subscription)
+                .patch(update_subscription)
+                .delete(delete_subscription),
+        )
+        .route("/status", get(status))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth))
+        .with_state(state)
+}
+
+async fn auth<B>(
+    State(state): State<ApiState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response, Err> {
+    match req.headers().get(API_KEY_HEADER) {
+        Some(key) if key_matches(&state.api_key, key.as_bytes()) => Ok(next.run(req).await),
+        _ => Err(Err::Unauthorized),
+    }
+}
+
+/// Compares the digest of `given` with `expected` without stopping at the first
+/// differing byte, so the response time tells nothing about a guessed key.
+fn key_matches(expected: &[u8], given: &[u8]) -> bool {
+    let given = HashValue::sha3_256_of(given).to_vec();
+    expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given.iter())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn list_subscriptions(
+    State(state): State<ApiState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<SubscriptionResponse>>, Err> {
+    let select = WalletTracked::find();
+    let select = match query.chat_id {
+        Some(chat_id) => select.filter(wallet_tracked::Column::ChatId.eq(chat_id)),
+        None => select,
+    };
+    let select = match query.wallet_address {
+        Some(address) => select.filter(
+            wallet_tracked::Column::WalletAddress
+                .eq(AccountAddress::from_str(&address)?.to_string()),
+        ),
+        None => select,
+    };
+    let select = match query.network {
+        Some(network) => select.filter(wallet_tracked::Column::Network.eq(network)),
+        None => select,
+    };
+    Ok(Json(
+        select
+            .all(state.db.as_ref())
+            .await?
+            .into_iter()
+            .map(SubscriptionResponse::from)
+            .collect(),
+    ))
+}
+
+async fn get_subscription(
+    State(state): State<ApiState>,
+    Path(id): Path<i64>,
+) -> Result<Json<SubscriptionResponse>, Err> {
+    match WalletTracked::find_by_id(id).one(state.db.as_ref()).await? {
+        Some(entry) => Ok(Json(entry.into())),
+        None => Err(Err::NotFound),
+    }
+}
+
+async fn create_subscription(
+    State(state): State<ApiState>,
+    Json(req): Json<CreateSubscription>,
+) -> Result<(StatusCode, Json<SubscriptionResponse>), Err> {
+    let db = state.db.as_ref();
+    let address = AccountAddress::from_str(&req.wallet_address)?;
+    let nickname = parse_nickname(req.nickname)?;
+    let track_type = match req.track_type {
+        Some(s) => parse_track_type(s)?,
+        None => TrackType::Full,
+    };
+    let minimum_value = parse_minimum_value(req.minimum_value.unwrap_or(0.0))?;
+    let network = match req.network {
+        Some(name) => match state.networks.get(&name) {
+            Some(network) => network,
+            None => return Err(Err::UnknownNetwork),
+        },
+        None => state.networks.default_network(),
+    };
+    let track_exist = WalletTracked::find()
+        .filter(wallet_tracked::Column::WalletAddress.eq(address.to_string()))
+        .filter(wallet_tracked::Column::ChatId.eq(req.chat_id))
+        .filter(wallet_tracked::Column::Network.eq(network.name.clone()))
+        .one(db)
+        .await?
+        .is_some();
+    if track_exist {
+        return Err(Err::AlreadyTracked);
+    }
+    if User::find_by_id(req.user_id).one(db).await?.is_none() {
+        let new_user = user::ActiveModel {
+            user_id: Set(req.user_id),
+            ..Default::default()
+        };
+        User::insert(new_user).exec(db).await?;
+    }
+    let new_tracking = wallet_tracked::ActiveModel {
+        chat_id: Set(req.chat_id),
+        wallet_address: Set(address.to_string()),
+        user_id: Set(req.user_id),
+        nickname: Set(nickname),
+        track_type: Set(track_type),
+        minimum_value: Set(minimum_value),
+        network: Set(network.name.clone()),
+        ..Default::default()
+    };
+    let result = WalletTracked::insert(new_tracking).exec(db).await?;
+    match WalletTracked::find_by_id(result.last_insert_id)
+        .one(db)
+        .await?
+    {
+        Some(entry) => Ok((StatusCode::CREATED, Json(entry.into()))),
+        None => Err(Err::NotFound),
+    }
+}
+
+async fn update_subscription(
+    State(state): State<ApiState>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateSubscription>,
+) -> Result<Json<SubscriptionResponse>, Err> {
+    let db = state.db.as_ref();
+    let entry = match WalletTracked::find_by_id(id).one(db).await? {
+        Some(entry) => entry,
+        None => return Err(Err::NotFound),
+    };
+    let mut update: wallet_tracked::ActiveModel = entry.into();
+    if let Some(nickname) = req.nickname {
+        update.nickname = Set(parse_nickname(Some(nickname))?);
+    }
+    if let Some(track_type) = req.track_type {
+        update.track_type = Set(parse_track_type(track_type)?);
+    }
+    if let Some(value) = req.minimum_value {
+        update.minimum_value = Set(parse_minimum_value(value)?);
+    }
+    Ok(Json(update.update(db).await?.into()))
+}
+
+async fn delete_subscription(
+    State(state): State<ApiState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, Err> {
+    let result = wallet_tracked::Entity::delete_by_id(id)
+        .exec(state.db.as_ref())
+        .await?;
+    match result.rows_affected {
+        0 => Err(Err::NotFound),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+async fn status(State(state): State<ApiState>) -> Result<Json<StatusResponse>, Err> {
+    let db = state.db.as_ref();
+    let subscriptions = WalletTracked::find().count(db).await?;
+    let unique_wallets = UniqueWallet::fetch_all(db, None).await?.len();
+    let tokens = token_info::Model::get_token_hashmap(db).await?.len();
+    let mut networks = vec![];
+    for network in state.networks.iter() {
+        networks.push(network_status(db, network).await?);
+    }
+    Ok(Json(StatusResponse {
+        unique_wallets,
+        subscriptions: subscriptions.try_into().unwrap_or(usize::MAX),
+        tokens,
+        networks,
+    }))
+}
+
+async fn network_status(db: &DatabaseConnection, network: &Network) -> Result<NetworkStatus, Err> {
+    let name = network.name.as_str();
+    let unique_wallets = UniqueWallet::fetch_all(db, Some(name)).await?.len();
+    let current_block = network
+        .rpc
+        .get_ledger_information()
+        .await?
+        .inner()
+        .block_height;
+    let last_processed_block =
+        processed_block::Model::get_last_block(db, name, None, Some(Place::Old), false).await?;
+    let bottom_block =
+        processed_block::Model::get_last_block(db, name, None, Some(Place::Bottom), true).await?;
+    let head_block =
+        processed_block::Model::get_last_block(db, name, None, Some(Place::Head), false).await?;
+    let processing_blocks = ProcessedBlock::find()
+        .filter(processed_block::Column::Network.eq(name))
+        .filter(processed_block::Column::Status.eq(Status::Processing))
+        .count(db)
+        .await?;
+    let error_blocks = ProcessedBlock::find()
+        .filter(processed_block::Column::Network.eq(name))
+        .filter(processed_block::Column::Status.eq(Status::Error))
+        .count(db)
+        .await?;
+    Ok(NetworkStatus {
+        network: network.name.clone(),
+        unique_wallets,
+        current_block,
+        last_processed_block,
+        bottom_block,
+        head_block,
+        blocks_behind: last_processed_block.map(|block| current_block.saturating_sub(block)),
+        processing_blocks,
+        error_blocks,
+    })
+}
+
+fn parse_track_type(track_type: String) -> Result<TrackType, Err> {
+    TrackType::try_from_value(&track_type.to_lowercase()).map_err(|_| Err::InvalidTrackType)
+}
+
+/// A USD amount, finite and not negative.
+fn parse_minimum_value(value: f64) -> Result<f64, Err> {
+    match value.is_finite() && value >= 0.0 {
+        true => Ok(value),
+        false => Err(Err::InvalidMinimumValue),
+    }
+}
+
+fn parse_nickname(nickname: Option<String>) -> Result<Option<String>, Err> {
+    match nickname {
+        Some(nickname) if nickname.is_empty() => Ok(None),
+        Some(nickname) if nickname.len() > 20 => Err(Err::NicknameTooLong),
+        nickname => Ok(nickname),
+    }
+}
+
+impl IntoResponse for Err {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Err::Unauthorized => StatusCode::UNAUTHORIZED,
+            Err::NotFound => StatusCode::NOT_FOUND,
+            Err::AlreadyTracked => StatusCode::CONFLICT,
+            Err::InvalidTrackType
+            | Err::NicknameTooLong
+            | Err::InvalidMinimumValue
+            | Err::UnknownNetwork
+            | Err::ParseAddress(_) => StatusCode::BAD_REQUEST,
+            Err::Rpc(_) => StatusCode::BAD_GATEWAY,
+            _ => {
+                error!("api error: {:?}", self);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (
+            status,
+            Json(serde_json::json!({ "error": self.to_string() })),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,136 @@
+//! Current holdings of a wallet, read from the indexer's coin and fungible asset
+//! balances and priced with `token_info`.
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::db::TokenMap;
+use crate::entities::wallet_tracked;
+use crate::error::Err;
+use crate::filter_expr::Subject;
+use crate::network::Network;
+use crate::spam;
+use crate::tx::{BalanceChange, Token};
+
+/// Holdings listed by `/balance`, the total counts the rest too.
+pub const MAX_LISTED: usize = 30;
+
+/// Activity type [`BalanceChange::from_event`] reads as `+amount`.
+const DEPOSIT: &str = "0x1::fungible_asset::Deposit";
+
+/// Holdings of `wallet`, the most valuable first. Tokens skipped in `token_info` and
+/// priced holdings worth less than `spam.dust_usd` are left out, so are coin balances
+/// other than APT, which a migrated coin repeats under its fungible asset address.
+pub async fn holdings(
+    network: &Network,
+    token: &TokenMap,
+    wallet: &str,
+) -> Result<Vec<Subject>, Err> {
+    let owner = AccountAddress::from_str(wallet)?;
+    let data = match network.tx_client.get_balances(wallet).await?.data {
+        Some(data) => data,
+        None => return Ok(vec![]),
+    };
+    let mut rows: Vec<(String, i128, u8, String)> = data
+        .current_fungible_asset_balances
+        .into_iter()
+        .filter_map(|row| {
+            let metadata = row.metadata?;
+            Some((
+                row.asset_type,
+                row.amount,
+                metadata.decimals,
+                metadata.symbol,
+            ))
+        })
+        .collect();
+    rows.extend(data.current_coin_balances.into_iter().filter_map(|row| {
+        let info = row.coin_info?;
+        Some((
+            row.coin_type?,
+            row.amount?,
+            info.decimals?,
+            info.symbol.unwrap_or_default(),
+        ))
+    }));
+
+    let mut holdings: Vec<Subject> = vec![];
+    for (asset_type, amount, decimals, symbol) in rows.into_iter() {
+        if amount <= 0 {
+            continue;
+        }
+        let change = match BalanceChange::from_event(
+            DEPOSIT.to_string(),
+            amount,
+            decimals,
+            owner,
+            asset_type,
+        ) {
+            Some(change) => change,
+            None => continue,
+        };
+        if let Token::Coin(_) = change.token() {
+            continue;
+        }
+        let mut holding = Subject::from_change(&change, token);
+        // A migrated coin shows in both tables, the fungible asset row comes first.
+        if holdings
+            .iter()
+            .any(|other| other.asset_type == holding.asset_type)
+        {
+            continue;
+        }
+        if spam::is_skipped(token, &holding.asset_type)
+            || holding.usd.map_or(false, |usd| usd < network.spam.dust_usd)
+        {
+            continue;
+        }
+        let named = token
+            .get(&holding.asset_type)
+            .map_or(false, |entry| entry.name.is_some());
+        if !named && !symbol.is_empty() {
+            holding.symbol = symbol;
+        }
+        holdings.push(holding);
+    }
+    holdings.sort_by(|a, b| {
+        b.usd
+            .partial_cmp(&a.usd)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    Ok(holdings)
+}
+
+/// Sum of the priced holdings.
+pub fn total_usd(holdings: &[Subject]) -> f64 {
+    holdings.iter().filter_map(|holding| holding.usd).sum()
+}
+
+pub fn balance_text(subscription: &wallet_tracked::Model, holdings: &[Subject]) -> String {
+    let name = subscription.clone().display_name();
+    if holdings.is_empty() {
+        return format!("{name} holds nothing on {}", subscription.network);
+    }
+    let mut text = format!("Balance of {name} on {}\n", subscription.network);
+    for holding in holdings.iter().take(MAX_LISTED) {
+        let usd = match holding.usd {
+            Some(usd) => format!(" (${usd:.2})"),
+            None => String::new(),
+        };
+        text.push_str(&format!("\n{} {:.4}{usd}", holding.symbol, holding.amount));
+    }
+    if holdings.len() > MAX_LISTED {
+        text.push_str(&format!("\nand {} more", holdings.len() - MAX_LISTED));
+    }
+    let unpriced = holdings
+        .iter()
+        .filter(|holding| holding.usd.is_none())
+        .count();
+    text.push_str(&format!("\n\nTotal ${:.2}", total_usd(holdings)));
+    if unpriced > 0 {
+        text.push_str(&format!(", {unpriced} unpriced not counted"));
+    }
+    text
+}
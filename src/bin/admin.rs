@@ -0,0 +1,234 @@
+use aptos_tracking::admin;
+use aptos_tracking::config::{Config, NetworkConfig};
+use aptos_tracking::error::Err;
+use aptos_tracking::migration;
+use aptos_tracking::spam;
+use aptos_tracking::tx::TxQueryClient;
+use clap::{Parser, Subcommand};
+use log::error;
+use reqwest::Client as rqwClient;
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Maintenance tasks on the tracker database, with the same config as the bot.
+#[derive(Parser)]
+#[command(name = "aptos-tracking-admin")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Bottom pointer, rows by status, gaps and stuck rows of each network.
+    Status {
+        #[arg(long)]
+        network: Option<String>,
+    },
+    /// Delete the rows of FROM..=TO so the workers process them again, alerts included.
+    Reprocess {
+        #[arg(long)]
+        network: Option<String>,
+        from: u64,
+        to: u64,
+    },
+    /// Mark FROM..=TO skipped so the bottom pointer moves past it.
+    Skip {
+        #[arg(long)]
+        network: Option<String>,
+        from: u64,
+        to: u64,
+    },
+    /// List or export subscriptions.
+    #[command(subcommand)]
+    Subscriptions(Subscriptions),
+    /// Refresh token names and decimals from the indexer, every known token by default.
+    ResyncTokens {
+        #[arg(long)]
+        network: Option<String>,
+        mints: Vec<String>,
+    },
+    /// Leave tokens out of alerts and USD totals.
+    SkipTokens { mints: Vec<String> },
+    /// Alert on tokens again and keep the spam heuristics from flagging them.
+    AllowTokens { mints: Vec<String> },
+    /// Tokens skipped by an operator or flagged as spam.
+    SkippedTokens,
+    /// Apply pending migrations.
+    Migrate,
+}
+
+#[derive(Subcommand)]
+enum Subscriptions {
+    List {
+        #[arg(long)]
+        network: Option<String>,
+        #[arg(long)]
+        chat: Option<i64>,
+    },
+    /// One JSON object per line, to stdout unless --output is given.
+    Export {
+        #[arg(long)]
+        network: Option<String>,
+        #[arg(long)]
+        chat: Option<i64>,
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+    pretty_env_logger::init_timed();
+    let cli = Cli::parse();
+    let config = Config::load_admin().expect("config error");
+    let mut opt = ConnectOptions::new(config.database.url.clone());
+    opt.sqlx_logging(config.database.sqlx_logging);
+    let db = Database::connect(opt).await.expect("db connect error");
+    if let Err(e) = run(cli.command, &config, &db).await {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(command: Command, config: &Config, db: &DatabaseConnection) -> Result<(), Err> {
+    if let Command::Migrate = command {
+        migration::prepare(db, true).await?;
+        println!("schema up to date");
+        return Ok(());
+    }
+    migration::prepare(db, false).await?;
+    match command {
+        Command::Status { network } => {
+            let networks = match network {
+                Some(name) => vec![network_config(config, Some(name))?],
+                None => config.networks.iter().collect(),
+            };
+            for network in networks {
+                print_status(&admin::status(db, &network.name).await?);
+            }
+        }
+        Command::Reprocess { network, from, to } => {
+            let network = network_config(config, network)?;
+            let deleted = admin::reprocess(db, &network.name, from, to).await?;
+            println!(
+                "[{}] {deleted} rows deleted, {from}..={to} will be processed again",
+                network.name
+            );
+        }
+        Command::Skip { network, from, to } => {
+            let network = network_config(config, network)?;
+            let changed = admin::skip(db, &network.name, from, to).await?;
+            println!(
+                "[{}] {changed} rows marked skipped in {from}..={to}",
+                network.name
+            );
+        }
+        Command::Subscriptions(Subscriptions::List { network, chat }) => {
+            for s in admin::subscriptions(db, network.as_deref(), chat).await? {
+                println!(
+                    "{}\t[{}]\tchat {}\t{}\t{:?}\t{}\t{}",
+                    s.wallet_id,
+                    s.network,
+                    s.chat_id,
+                    s.wallet_address,
+                    s.track_type,
+                    s.minimum_value,
+                    s.nickname.unwrap_or_default()
+                );
+            }
+        }
+        Command::Subscriptions(Subscriptions::Export {
+            network,
+            chat,
+            output,
+        }) => {
+            let subscriptions = admin::subscriptions(db, network.as_deref(), chat).await?;
+            let mut out: Box<dyn Write> = match &output {
+                Some(path) => Box::new(std::fs::File::create(path)?),
+                None => Box::new(std::io::stdout()),
+            };
+            for subscription in subscriptions.iter() {
+                serde_json::to_writer(&mut out, subscription)?;
+                out.write_all(b"\n")?;
+            }
+            if let Some(path) = output {
+                println!(
+                    "{} subscriptions written to {}",
+                    subscriptions.len(),
+                    path.display()
+                );
+            }
+        }
+        Command::ResyncTokens { network, mints } => {
+            let network = network_config(config, network)?;
+            let client = TxQueryClient::from_config(Arc::new(rqwClient::new()), &network.indexer)?;
+            let written = admin::resync_tokens(db, &client, mints).await?;
+            println!("{written} tokens updated");
+        }
+        Command::SkipTokens { mints } => {
+            let written = admin::set_skipped(db, &mints, spam::SKIPPED).await?;
+            println!("{written} tokens skipped");
+        }
+        Command::AllowTokens { mints } => {
+            let written = admin::set_skipped(db, &mints, spam::ALLOWED).await?;
+            println!("{written} tokens allowed");
+        }
+        Command::SkippedTokens => {
+            for token in admin::skipped_tokens(db).await? {
+                println!(
+                    "{}\t{}\t{}",
+                    token.mint,
+                    match token.is_skipped {
+                        spam::SKIPPED => "skipped",
+                        _ => "spam",
+                    },
+                    token.name.unwrap_or_default()
+                );
+            }
+        }
+        Command::Migrate => {}
+    }
+    Ok(())
+}
+
+/// The named network, or the first configured one.
+fn network_config(config: &Config, name: Option<String>) -> Result<&NetworkConfig, Err> {
+    match name {
+        Some(name) => config
+            .networks
+            .iter()
+            .find(|network| network.name == name)
+            .ok_or(Err::UnknownNetwork),
+        None => Ok(&config.networks[0]),
+    }
+}
+
+fn print_status(status: &admin::NetworkStatus) {
+    let height = |block: Option<u64>| block.map_or("-".to_string(), |block| block.to_string());
+    println!("[{}]", status.network);
+    println!("  bottom   {}", height(status.bottom));
+    println!("  highest  {}", height(status.highest));
+    for (state, count) in status.counts.iter() {
+        println!("  {:<9}{}", format!("{:?}", state).to_lowercase(), count);
+    }
+    match status.gaps.is_empty() {
+        true => println!("  no gaps above the bottom pointer"),
+        false => {
+            let missing: u64 = status.gaps.iter().map(|(from, to)| to - from + 1).sum();
+            println!("  {} gaps, {} blocks missing", status.gaps.len(), missing);
+            for (from, to) in status.gaps.iter() {
+                println!("    {from}..={to}");
+            }
+        }
+    }
+    for row in status.stuck.iter() {
+        println!(
+            "  stuck {} {:?} since {} (worker {}/{})",
+            row.block, row.status, row.processed_time, row.remainder, row.modulo
+        );
+    }
+}
@@ -1,26 +1,27 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use aptos_sdk::rest_client::Client;
 use futures::{future::Pending, stream::Abortable};
 
-use tokio::sync::RwLock;
-pub async fn update_block_loop(
-    abort: Abortable<Pending<()>>,
-    slot: Arc<RwLock<u64>>,
-    rpc: Arc<Client>,
-) {
+use crate::metrics::{set_gauge, update_head_lag, CURRENT_BLOCK};
+use crate::network::Network;
+pub async fn update_block_loop(abort: Abortable<Pending<()>>, network: Arc<Network>) {
     loop {
         if abort.is_aborted() {
             break;
         }
-        if let Ok(info) = rpc.get_ledger_information().await {
-            let new_slot = info.inner().block_height;
-            let mut update = slot.write().await;
-            if new_slot > *update {
-                *update = new_slot;
+        match network.rpc.get_ledger_information().await {
+            Ok(info) => {
+                let new_slot = info.inner().block_height;
+                let mut update = network.current_block.write().await;
+                if new_slot > *update {
+                    *update = new_slot;
+                    set_gauge(&CURRENT_BLOCK.with_label_values(&[&network.name]), new_slot);
+                    update_head_lag(&network.name);
+                }
+                drop(update);
             }
-            drop(update);
+            Err(_) => {}
         }
         tokio::time::sleep(Duration::from_millis(100)).await;
     }
@@ -1,7 +1,7 @@
 type OptString = Option<String>;
 use ahash::AHashSet;
 use aptos_sdk::move_types::account_address::AccountAddress;
-use aptos_sdk::rest_client::Client;
+use chrono::{TimeDelta, Utc};
 use log::{error, warn};
 use reqwest::Client as rqwClient;
 use sea_orm::{ActiveModelTrait, ActiveValue::*};
@@ -17,18 +17,37 @@ use teloxide::{
     dispatching::UpdateFilterExt,
     dptree::{case, deps},
     prelude::{Requester, *},
-    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile},
     utils::command::{BotCommands, ParseError},
 };
 use tokio::sync::RwLock;
 
-use crate::db::UniqueWallet;
-use crate::entities::sea_orm_active_enums::{Place, TrackType};
+use crate::balance;
+use crate::db::{Threshold, TokenMap, UniqueWallet};
+use crate::digest;
+use crate::entities::sea_orm_active_enums::{DigestMode, Place, TokenMode, TrackType};
 use crate::entities::{prelude::*, processed_block, token_info, user, wallet_tracked};
 use crate::error::{Err, HandleErr};
-use crate::tx::{get_tx_detail, TxQueryClient};
+use crate::export;
+use crate::filter_expr::FilterExpr;
+use crate::history;
+use crate::network::{Network, Networks};
+use crate::quiet::{self, QuietHours};
+use crate::tx::get_tx_detail;
 use crate::{callback::CallbackCommand, db::WalletTrackedForChat};
 const SLEEP: Duration = Duration::from_secs(3);
+const THRESHOLDS_PROMPT: &str = "Send a USD value ex: 60.5, or thresholds ex: received 1000 APT, sent 50 usd\nA direction left out never alerts, clear removes the thresholds";
+const RULE_PROMPT: &str = "Send a rule ex: token in [APT, USDC] and usd >= 500 and direction == out\nFields: token, direction (in, out), usd, amount. Combine with and, or, not and parentheses, clear removes it";
+const MUTE_USAGE: &str = "Usage: /mute <address|nickname> <duration>, ex: /mute whale 8h\nDurations: 30m, 8h, 2d, 1w, off unmutes";
+const QUIET_USAGE: &str = "Usage: /quiet <start-end> [UTC offset], ex: /quiet 22:00-07:00 +2\nAlerts during quiet hours are sent as one summary when they end, /quiet off removes them";
+const DIGEST_USAGE: &str = "Usage: /digest <address|nickname> <daily|weekly|off>, ex: /digest whale daily\nAlerts of a wallet in digest mode are collected into one summary, /digest at 18:30 [weekday] [UTC offset] sets when it is sent";
+const HISTORY_USAGE: &str = "Usage: /history [network] <address|nickname> [count], ex: /history whale 20\nLists the stored balance changes of a tracked wallet, newest first";
+const EXPORT_USAGE: &str = "Usage: /export [network] <address|nickname> <from> <to>, ex: /export whale 2024-10-01 2024-10-31\nSends the balance changes of those days, UTC, as CSV and JSON";
+const BALANCE_USAGE: &str = "Usage: /balance [network] <address|nickname>, ex: /balance whale\nLists what a tracked wallet holds now with its USD value";
+const UNSUBSCRIBE_USAGE: &str = "Usage: /unsubscribe [network] <address|nickname>, ex: /unsubscribe testnet whale\nWithout arguments it lists the subscriptions of this chat";
+const UPDATE_USAGE: &str = "Usage: /update [network] <address|nickname>, ex: /update testnet whale\nWithout arguments it lists the subscriptions of this chat";
+const TOKENS_PROMPT: &str =
+    "Send the tokens, symbols like APT or asset types, separated by spaces or commas";
 
 #[derive(BotCommands, Clone, PartialEq, Eq, Debug)]
 #[command(rename_rule = "lowercase", parse_with = "split")]
@@ -36,7 +55,7 @@ enum Command {
     Start,
     #[command(parse_with = opt2)]
     Subscribe(ParsedSubscribe),
-    #[command(parse_with = opt)]
+    #[command(parse_with = rest)]
     Unsubscribe(OptString),
     #[command(parse_with = opt)]
     Test(OptString),
@@ -44,10 +63,22 @@ enum Command {
     Tx(OptString),
     Status,
     List,
-    #[command(parse_with = opt)]
+    #[command(parse_with = rest)]
     Update(OptString),
     #[command(parse_with = opt)]
     Token(OptString),
+    #[command(parse_with = opt_pair)]
+    Mute(OptString, OptString),
+    #[command(parse_with = rest)]
+    Quiet(OptString),
+    #[command(parse_with = rest)]
+    Digest(OptString),
+    #[command(parse_with = rest)]
+    History(OptString),
+    #[command(parse_with = rest)]
+    Export(OptString),
+    #[command(parse_with = rest)]
+    Balance(OptString),
 }
 
 type ChatState = Dialogue<State, InMemStorage<State>>;
@@ -56,23 +87,24 @@ type ChatState = Dialogue<State, InMemStorage<State>>;
 pub enum State {
     #[default]
     Idle,
-    UpdateNickname(u64),
-    UpdateFilter(u64),
+    UpdateNickname(i64),
+    UpdateFilter(i64),
+    UpdateTokens(i64, TokenMode),
+    UpdateRule(i64),
     // Subscribe,
 }
 pub async fn run(
     bot: Bot,
     db: Arc<DatabaseConnection>,
-    rpc: Arc<Client>,
-    tx_client: TxQueryClient,
+    networks: Networks,
     new_token: Arc<RwLock<AHashSet<AccountAddress>>>,
+    report_chat: Option<i64>,
 ) {
     let commands = |bot: Bot,
                     msg: Message,
                     cmd: Command,
                     db: Arc<DatabaseConnection>,
-                    rpc: Arc<Client>,
-                    tx_client: TxQueryClient,
+                    networks: Networks,
                     new_token: Arc<RwLock<AHashSet<AccountAddress>>>| async move {
         let chat_id = get_chat_id(bot.clone(), db.as_ref(), msg.chat.id).await?;
         let db = db.clone();
@@ -100,43 +132,54 @@ pub async fn run(
             }
             Some(user) => user,
         };
-        let user_check = User::find_by_id(from.id.0).one(db.as_ref()).await?;
+        let user_check = User::find_by_id(i64::try_from(from.id.0)?)
+            .one(db.as_ref())
+            .await?;
 
         match cmd {
             Command::Status => {
                 let all_chat = WalletTracked::find().all(db.as_ref()).await?;
-                let unique_wallet = UniqueWallet::fetch_all(db.as_ref()).await?;
-                let info = rpc.get_ledger_information().await?;
-                let current_block = info.inner().block_height;
+                let unique_wallet = UniqueWallet::fetch_all(db.as_ref(), None).await?;
                 let token = token_info::Model::get_token_hashmap(db.as_ref()).await?;
-
-                if let Some(last_slot) = processed_block::Model::get_last_block(
-                    db.as_ref(),
-                    None,
-                    Some(Place::Old),
-                    false,
-                )
-                .await?
-                {
-                    bot.send_message(
-                        chat_id,
-                        format!(
-                            "{} Wallet tracking, {} Chats, {} slot behind\n{} tokens",
-                            unique_wallet.len(),
-                            all_chat.len(),
-                            current_block.saturating_sub(last_slot),
-                            token.len()
-                        ),
+                let mut behind = vec![];
+                for network in networks.iter() {
+                    let info = network.rpc.get_ledger_information().await?;
+                    let current_block = info.inner().block_height;
+                    match processed_block::Model::get_last_block(
+                        db.as_ref(),
+                        &network.name,
+                        None,
+                        Some(Place::Old),
+                        false,
                     )
-                    .reply_to_message_id(msg.id)
-                    .await?;
-                } else {
-                    return Err(Err::SlotDatabaseEmpty);
+                    .await?
+                    {
+                        Some(last_slot) => behind.push(format!(
+                            "{}: {} slot behind",
+                            network.name,
+                            current_block.saturating_sub(last_slot)
+                        )),
+                        None => behind.push(format!("{}: no block processed", network.name)),
+                    }
                 }
+                bot.send_message(
+                    chat_id,
+                    format!(
+                        "{} Wallet tracking, {} Chats\n{}\n{} tokens",
+                        unique_wallet.len(),
+                        all_chat.len(),
+                        behind.join("\n"),
+                        token.len()
+                    ),
+                )
+                .reply_to_message_id(msg.id)
+                .await?;
             }
             Command::Tx(data) => {
                 if let Some(sig) = data {
-                    let result = get_tx_detail(&tx_client, u64::from_str(&sig)?).await?;
+                    let result =
+                        get_tx_detail(&networks.default_network().tx_client, u64::from_str(&sig)?)
+                            .await?;
                     match result {
                         Some(s) => {
                             let response = s
@@ -199,7 +242,7 @@ pub async fn run(
                 } else {
                     let new_user = user::ActiveModel {
                         user_name: sea_orm::ActiveValue::Set(from.username.clone()),
-                        user_id: sea_orm::ActiveValue::Set(from.id.0),
+                        user_id: sea_orm::ActiveValue::Set(i64::try_from(from.id.0)?),
                         ..Default::default()
                     };
                     User::insert(new_user).exec(db.as_ref()).await?;
@@ -235,10 +278,11 @@ pub async fn run(
                     }
                 };
 
+                let (network, req) = req.resolve(&networks);
                 match req.address {
                     None => {
                         let msg2 = bot
-                            .send_message(chat_id, "No address provided, try /subscribe <address>")
+                            .send_message(chat_id, "No address provided, try /subscribe [network] <address> [nickname]")
                             .reply_to_message_id(msg.id)
                             .await?;
                         tokio::time::sleep(SLEEP).await;
@@ -250,7 +294,11 @@ pub async fn run(
                                 let message = e.to_string();
                                 bot.send_message(
                                     chat_id,
-                                    format!("Address Invalid: {:?}", message),
+                                    format!(
+                                        "Address Invalid: {:?}\nNetworks: {}",
+                                        message,
+                                        networks.names()
+                                    ),
                                 )
                                 .reply_to_message_id(msg.id)
                                 .await?;
@@ -262,6 +310,9 @@ pub async fn run(
                                             .eq(address.to_string()),
                                     )
                                     .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
+                                    .filter(
+                                        wallet_tracked::Column::Network.eq(network.name.clone()),
+                                    )
                                     .one(db.as_ref())
                                     .await?
                                     .is_some();
@@ -280,21 +331,25 @@ pub async fn run(
                                         ),
                                         user_id: sea_orm::ActiveValue::set(user.user_id),
                                         nickname: sea_orm::ActiveValue::set(req.nickname),
+                                        network: sea_orm::ActiveValue::set(network.name.clone()),
                                         ..Default::default()
                                     };
                                     WalletTracked::insert(new_tracking)
                                         .exec(db.as_ref())
                                         .await?;
-                                    bot.send_message(chat_id, "Subscription added")
-                                        .reply_to_message_id(msg.id)
-                                        .await?;
+                                    bot.send_message(
+                                        chat_id,
+                                        format!("Subscription added on {}", network.name),
+                                    )
+                                    .reply_to_message_id(msg.id)
+                                    .await?;
                                 }
                             }
                         };
                     }
                 }
             }
-            Command::Unsubscribe(address) => {
+            Command::Unsubscribe(args) => {
                 if bot
                     .get_chat_member(chat_id, from.id)
                     .await?
@@ -310,8 +365,10 @@ pub async fn run(
                     bot.delete_message(chat_id, msg2.id).await?;
                     return Ok::<_, Err>(());
                 };
-                match address {
-                    None => {
+                let args = args.unwrap_or_default();
+                let words = args.split_whitespace().collect::<Vec<&str>>();
+                match split_network(&networks, &words) {
+                    (_, []) => {
                         let tracked =
                             WalletTrackedForChat::get_by_filter(db.as_ref(), Some(chat_id.0))
                                 .await?;
@@ -334,25 +391,38 @@ pub async fn run(
                                 .await?;
                         }
                     }
-                    Some(address) => {
-                        match WalletTracked::find()
-                            .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
-                            .filter(wallet_tracked::Column::WalletAddress.eq(address.clone()))
-                            .one(db.as_ref())
-                            .await?
-                        {
-                            Some(entry) => {
+                    (network, [wallet]) => {
+                        let found = find_wallet(db.as_ref(), chat_id.0, network, wallet).await?;
+                        match &found[..] {
+                            [] => {
+                                bot.send_message(chat_id, "Address Not found").await?;
+                            }
+                            [entry] => {
                                 wallet_tracked::Entity::delete_by_id(entry.wallet_id)
                                     .exec(db.as_ref())
                                     .await?;
-                                bot.send_message(chat_id, format!("{address} Unsubscribed"))
+                                bot.send_message(chat_id, format!("{wallet} Unsubscribed"))
                                     .await?;
                             }
-                            None => {
-                                bot.send_message(chat_id, "Address Not found").await?;
+                            _ => {
+                                bot.send_message(
+                                    chat_id,
+                                    "Tracked on several networks, select one",
+                                )
+                                .reply_to_message_id(msg.id)
+                                .reply_markup(network_buttons(
+                                    &found,
+                                    wallet_tracked::Model::to_unsub_callback_data,
+                                ))
+                                .await?;
                             }
                         }
                     }
+                    _ => {
+                        bot.send_message(chat_id, UNSUBSCRIBE_USAGE)
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                    }
                 }
             }
             Command::List => match user_check {
@@ -363,6 +433,10 @@ pub async fn run(
                 Some(_user) => {
                     let tracked =
                         WalletTrackedForChat::get_by_filter(db.as_ref(), Some(chat_id.0)).await?;
+                    let network_tag = |wallet: &WalletTrackedForChat| match networks.len() {
+                        1 => String::new(),
+                        _ => format!(" on {}", wallet.network),
+                    };
                     if tracked.is_empty() {
                         bot.send_message(
                             chat_id,
@@ -375,17 +449,22 @@ pub async fn run(
                             .iter()
                             .map(|wallet| {
                                 if msg.chat.is_private() {
-                                    return wallet
-                                        .nickname
-                                        .clone()
-                                        .unwrap_or(wallet.wallet_address.clone());
+                                    return format!(
+                                        "{}{}",
+                                        wallet
+                                            .nickname
+                                            .clone()
+                                            .unwrap_or(wallet.wallet_address.clone()),
+                                        network_tag(wallet)
+                                    );
                                 }
                                 format!(
-                                    "{} subscription added by @{}",
+                                    "{}{} subscription added by @{}",
                                     wallet
                                         .nickname
                                         .clone()
                                         .unwrap_or(wallet.wallet_address.clone()),
+                                    network_tag(wallet),
                                     wallet.user_name.clone().unwrap_or(format!(
                                         "[{0}](tg://user?id={0})",
                                         wallet.user_id.clone().to_string()
@@ -403,7 +482,7 @@ pub async fn run(
                     }
                 }
             },
-            Command::Update(address) => {
+            Command::Mute(wallet, duration) => {
                 if bot
                     .get_chat_member(chat_id, from.id)
                     .await?
@@ -419,8 +498,368 @@ pub async fn run(
                     bot.delete_message(chat_id, msg2.id).await?;
                     return Ok::<_, Err>(());
                 }
-                match address {
-                    None => {
+                let (wallet, duration) = match (wallet, duration) {
+                    (Some(wallet), Some(duration)) => (wallet, duration),
+                    _ => {
+                        bot.send_message(chat_id, MUTE_USAGE)
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let until = match duration.eq_ignore_ascii_case("off") {
+                    true => None,
+                    false => match quiet::parse_duration(&duration)
+                        .and_then(|duration| Utc::now().checked_add_signed(duration))
+                    {
+                        Some(until) => Some(until),
+                        None => {
+                            bot.send_message(
+                                chat_id,
+                                format!("Invalid duration {duration}\n{MUTE_USAGE}"),
+                            )
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                            return Ok(());
+                        }
+                    },
+                };
+                let found =
+                    wallet_tracked::Model::find_in_chat(db.as_ref(), chat_id.0, &wallet).await?;
+                if found.is_empty() {
+                    bot.send_message(chat_id, "Address Not found")
+                        .reply_to_message_id(msg.id)
+                        .await?;
+                    return Ok(());
+                }
+                let _update = WalletTracked::update_many()
+                    .col_expr(wallet_tracked::Column::MutedUntil, Expr::value(until))
+                    .filter(
+                        wallet_tracked::Column::WalletId
+                            .is_in(found.iter().map(|wallet| wallet.wallet_id)),
+                    )
+                    .exec(db.as_ref())
+                    .await?;
+                let reply = match until {
+                    Some(until) => format!(
+                        "{wallet} muted until {} UTC",
+                        until.format("%Y-%m-%d %H:%M")
+                    ),
+                    None => format!("{wallet} unmuted"),
+                };
+                bot.send_message(chat_id, reply)
+                    .reply_to_message_id(msg.id)
+                    .await?;
+            }
+            Command::Quiet(text) => {
+                if bot
+                    .get_chat_member(chat_id, from.id)
+                    .await?
+                    .can_manage_chat()
+                    .not()
+                    && !msg.chat.is_private()
+                {
+                    let msg2 = bot
+                        .send_message(chat_id, "Only group admin can do this")
+                        .reply_to_message_id(msg.id)
+                        .await?;
+                    tokio::time::sleep(SLEEP).await;
+                    bot.delete_message(chat_id, msg2.id).await?;
+                    return Ok::<_, Err>(());
+                }
+                let reply = match text.as_deref() {
+                    None => match ChatSetting::find_by_id(chat_id.0)
+                        .one(db.as_ref())
+                        .await?
+                        .and_then(|setting| setting.quiet_hours())
+                    {
+                        Some(quiet) => format!("Quiet hours {quiet}\n{QUIET_USAGE}"),
+                        None => format!("No quiet hours\n{QUIET_USAGE}"),
+                    },
+                    Some(text) if text.eq_ignore_ascii_case("off") => {
+                        quiet::set_quiet_hours(db.as_ref(), chat_id.0, None).await?;
+                        "Quiet hours removed, held alerts follow shortly".to_string()
+                    }
+                    Some(text) => match QuietHours::parse(text) {
+                        Ok(quiet) => {
+                            quiet::set_quiet_hours(db.as_ref(), chat_id.0, Some(quiet)).await?;
+                            format!("Quiet hours set to {quiet}")
+                        }
+                        Err(err) => format!("{err}\n{QUIET_USAGE}"),
+                    },
+                };
+                bot.send_message(chat_id, reply)
+                    .reply_to_message_id(msg.id)
+                    .await?;
+            }
+            Command::Digest(text) => {
+                if bot
+                    .get_chat_member(chat_id, from.id)
+                    .await?
+                    .can_manage_chat()
+                    .not()
+                    && !msg.chat.is_private()
+                {
+                    let msg2 = bot
+                        .send_message(chat_id, "Only group admin can do this")
+                        .reply_to_message_id(msg.id)
+                        .await?;
+                    tokio::time::sleep(SLEEP).await;
+                    bot.delete_message(chat_id, msg2.id).await?;
+                    return Ok::<_, Err>(());
+                }
+                let text = text.unwrap_or_default();
+                let mut words = text.split_whitespace();
+                let reply = match (words.next(), words.next(), words.next()) {
+                    (None, ..) => format!(
+                        "Digests at {}\n{DIGEST_USAGE}",
+                        digest::schedule(db.as_ref(), chat_id.0).await?
+                    ),
+                    (Some(at), ..) if at.eq_ignore_ascii_case("at") => {
+                        let current = digest::schedule(db.as_ref(), chat_id.0).await?;
+                        match current.parse(text[2..].trim()) {
+                            Ok(schedule) => {
+                                digest::set_schedule(db.as_ref(), chat_id.0, schedule).await?;
+                                format!("Digests at {schedule}")
+                            }
+                            Err(err) => format!("{err}\n{DIGEST_USAGE}"),
+                        }
+                    }
+                    (Some(wallet), Some(mode), None) => {
+                        let mode = match mode.to_lowercase().as_str() {
+                            "daily" => Some(DigestMode::Daily),
+                            "weekly" => Some(DigestMode::Weekly),
+                            "off" => Some(DigestMode::Off),
+                            _ => None,
+                        };
+                        let found =
+                            wallet_tracked::Model::find_in_chat(db.as_ref(), chat_id.0, wallet)
+                                .await?;
+                        match mode {
+                            None => DIGEST_USAGE.to_string(),
+                            Some(_) if found.is_empty() => "Address Not found".to_string(),
+                            Some(mode) => {
+                                let _update = WalletTracked::update_many()
+                                    .col_expr(
+                                        wallet_tracked::Column::Digest,
+                                        Expr::value(mode.clone()),
+                                    )
+                                    .col_expr(
+                                        wallet_tracked::Column::LastDigest,
+                                        Expr::value(Some(Utc::now())),
+                                    )
+                                    .filter(
+                                        wallet_tracked::Column::WalletId
+                                            .is_in(found.iter().map(|wallet| wallet.wallet_id)),
+                                    )
+                                    .exec(db.as_ref())
+                                    .await?;
+                                match mode {
+                                    DigestMode::Off => format!("{wallet} alerts are sent again"),
+                                    DigestMode::Daily => format!("{wallet} in daily digest"),
+                                    DigestMode::Weekly => format!("{wallet} in weekly digest"),
+                                }
+                            }
+                        }
+                    }
+                    _ => DIGEST_USAGE.to_string(),
+                };
+                bot.send_message(chat_id, reply)
+                    .reply_to_message_id(msg.id)
+                    .await?;
+            }
+            Command::History(args) => {
+                let args = args.unwrap_or_default();
+                let words = args.split_whitespace().collect::<Vec<&str>>();
+                let (network, wallet, count) = match split_network(&networks, &words) {
+                    (network, [wallet]) => (network, *wallet, None),
+                    (network, [wallet, count]) => (network, *wallet, Some(*count)),
+                    _ => {
+                        bot.send_message(chat_id, HISTORY_USAGE)
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let limit = match count.map(u64::from_str) {
+                    None => history::PAGE,
+                    Some(Ok(count)) if (1..=history::MAX_PAGE).contains(&count) => count,
+                    Some(_) => {
+                        bot.send_message(
+                            chat_id,
+                            format!("Count must be 1 to {}\n{HISTORY_USAGE}", history::MAX_PAGE),
+                        )
+                        .reply_to_message_id(msg.id)
+                        .await?;
+                        return Ok(());
+                    }
+                };
+                let found = find_wallet(db.as_ref(), chat_id.0, network, wallet).await?;
+                let subscription = match &found[..] {
+                    [] => {
+                        bot.send_message(chat_id, "Address Not found")
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                    [subscription] => subscription.clone(),
+                    _ => {
+                        bot.send_message(chat_id, "Tracked on several networks, select one")
+                            .reply_to_message_id(msg.id)
+                            .reply_markup(network_buttons(&found, |subscription| {
+                                CallbackCommand::History(subscription.wallet_id, 0, limit)
+                                    .to_callback_data()
+                            }))
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let (entries, more) = history::page(db.as_ref(), &subscription, 0, limit).await?;
+                bot.send_message(chat_id, history::history_text(&subscription, &entries, 0))
+                    .reply_markup(history_buttons(subscription.wallet_id, 0, limit, more))
+                    .reply_to_message_id(msg.id)
+                    .await?;
+            }
+            Command::Export(text) => {
+                let text = text.unwrap_or_default();
+                let words = text.split_whitespace().collect::<Vec<&str>>();
+                let (network, wallet, from, to) = match split_network(&networks, &words) {
+                    (network, [wallet, from, to]) => (network, *wallet, *from, *to),
+                    _ => {
+                        bot.send_message(chat_id, EXPORT_USAGE)
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let (start, end) = match export::parse_range(from, to) {
+                    Ok(range) => range,
+                    Err(err) => {
+                        bot.send_message(chat_id, format!("{err}\n{EXPORT_USAGE}"))
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let found = find_wallet(db.as_ref(), chat_id.0, network, wallet).await?;
+                let subscription = match &found[..] {
+                    [] => {
+                        bot.send_message(chat_id, "Address Not found")
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                    [subscription] => subscription.clone(),
+                    _ => {
+                        bot.send_message(chat_id, several_networks(&found, "export", wallet))
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let network = match networks.get(&subscription.network) {
+                    Some(network) => network.clone(),
+                    None => return Err(Err::UnknownNetwork),
+                };
+                let rows = export::rows(
+                    db.as_ref(),
+                    &network,
+                    &subscription.wallet_address,
+                    start,
+                    end,
+                )
+                .await?;
+                if rows.is_empty() {
+                    bot.send_message(chat_id, format!("No activity from {from} to {to}"))
+                        .reply_to_message_id(msg.id)
+                        .await?;
+                    return Ok(());
+                }
+                let name = format!("{}_{from}_{to}", subscription.wallet_address);
+                let caption = format!(
+                    "{} balance changes{}{}",
+                    rows.len(),
+                    match rows.len() >= export::MAX_ROWS {
+                        true => ", the export limit, later ones are left out",
+                        false => "",
+                    },
+                    match rows.iter().any(|row| row.usd_at == "current") {
+                        true => "\nUSD values of transactions the tracker did not process use current prices",
+                        false => "",
+                    }
+                );
+                bot.send_document(
+                    chat_id,
+                    InputFile::memory(export::to_csv(&rows)?).file_name(format!("{name}.csv")),
+                )
+                .caption(caption)
+                .reply_to_message_id(msg.id)
+                .await?;
+                bot.send_document(
+                    chat_id,
+                    InputFile::memory(export::to_json(&rows)?).file_name(format!("{name}.json")),
+                )
+                .await?;
+            }
+            Command::Balance(args) => {
+                let args = args.unwrap_or_default();
+                let words = args.split_whitespace().collect::<Vec<&str>>();
+                let (network, wallet) = match split_network(&networks, &words) {
+                    (network, [wallet]) => (network, *wallet),
+                    _ => {
+                        bot.send_message(chat_id, BALANCE_USAGE)
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let found = find_wallet(db.as_ref(), chat_id.0, network, wallet).await?;
+                let subscription = match &found[..] {
+                    [] => {
+                        bot.send_message(chat_id, "Address Not found")
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                    [subscription] => subscription.clone(),
+                    _ => {
+                        bot.send_message(chat_id, several_networks(&found, "balance", wallet))
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let network = match networks.get(&subscription.network) {
+                    Some(network) => network.clone(),
+                    None => return Err(Err::UnknownNetwork),
+                };
+                let token = token_info::Model::get_token_hashmap(db.as_ref()).await?;
+                let holdings =
+                    balance::holdings(&network, &token, &subscription.wallet_address).await?;
+                bot.send_message(chat_id, balance::balance_text(&subscription, &holdings))
+                    .reply_to_message_id(msg.id)
+                    .await?;
+            }
+            Command::Update(args) => {
+                if bot
+                    .get_chat_member(chat_id, from.id)
+                    .await?
+                    .can_manage_chat()
+                    .not()
+                    && !msg.chat.is_private()
+                {
+                    let msg2 = bot
+                        .send_message(chat_id, "Only group admin can do this")
+                        .reply_to_message_id(msg.id)
+                        .await?;
+                    tokio::time::sleep(SLEEP).await;
+                    bot.delete_message(chat_id, msg2.id).await?;
+                    return Ok::<_, Err>(());
+                }
+                let args = args.unwrap_or_default();
+                let words = args.split_whitespace().collect::<Vec<&str>>();
+                match split_network(&networks, &words) {
+                    (_, []) => {
                         let tracked =
                             WalletTrackedForChat::get_by_filter(db.as_ref(), Some(chat_id.0))
                                 .await?;
@@ -443,21 +882,35 @@ pub async fn run(
                                 .await?;
                         }
                     }
-                    Some(address) => {
-                        match WalletTracked::find()
-                            .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
-                            .filter(wallet_tracked::Column::WalletAddress.eq(address.clone()))
-                            .one(db.as_ref())
-                            .await?
-                        {
-                            Some(info) => {
-                                update_handler(bot, chat_id, info.wallet_id, address).await?;
-                            }
-                            None => {
+                    (network, [wallet]) => {
+                        let found = find_wallet(db.as_ref(), chat_id.0, network, wallet).await?;
+                        match &found[..] {
+                            [] => {
                                 bot.send_message(chat_id, "Address Not found").await?;
                             }
+                            [info] => {
+                                update_handler(bot, chat_id, info.wallet_id, wallet.to_string())
+                                    .await?;
+                            }
+                            _ => {
+                                bot.send_message(
+                                    chat_id,
+                                    "Tracked on several networks, select one",
+                                )
+                                .reply_to_message_id(msg.id)
+                                .reply_markup(network_buttons(
+                                    &found,
+                                    wallet_tracked::Model::to_update_callback_data,
+                                ))
+                                .await?;
+                            }
                         }
                     }
+                    _ => {
+                        bot.send_message(chat_id, UPDATE_USAGE)
+                            .reply_to_message_id(msg.id)
+                            .await?;
+                    }
                 }
             }
         }
@@ -529,7 +982,12 @@ pub async fn run(
                         bot.answer_callback_query(q.id).text("200").await?;
                     }
                     CallbackCommand::UpdateFilter(address) => {
-                        if let Some(msg_id) = q.message {
+                        let wallet = WalletTracked::find_by_id(address)
+                            .filter(wallet_tracked::Column::ChatId.eq(chat.0))
+                            .one(db.as_ref())
+                            .await?;
+                        if let (Some(wallet), Some(msg_id)) = (wallet, q.message) {
+                            let known = token_info::Model::get_token_hashmap(db.as_ref()).await?;
                             let buttons = vec![
                                 vec![
                                     InlineKeyboardButton::callback(
@@ -555,12 +1013,19 @@ pub async fn run(
                                             .to_callback_data(),
                                     ),
                                 ],
+                                vec![InlineKeyboardButton::callback(
+                                    "Thresholds",
+                                    CallbackCommand::Thresholds(address.clone()).to_callback_data(),
+                                )],
                             ];
 
                             let _msg = bot.edit_message_text(
                                 chat,
                                 msg_id.id,
-                                "Select your filter mode\nEvery Tx: No filter\nReceived: Filter with received value in USD\nSent: Filter with sent value in USD\n",
+                                format!(
+                                    "Select your filter mode\nEvery Tx: No filter\nReceived: Filter with received value in USD\nSent: Filter with sent value in USD\nThresholds: separate received and sent minimums, in USD or token units, replace the mode when set\n\nThresholds: {}",
+                                    describe_thresholds(&wallet, &known)
+                                ),
                             ).reply_markup(InlineKeyboardMarkup::new(buttons)).await;
                             bot.answer_callback_query(q.id).text("200").await?;
                         }
@@ -571,6 +1036,14 @@ pub async fn run(
                                 wallet_tracked::Column::TrackType,
                                 Expr::value(TrackType::Full),
                             )
+                            .col_expr(
+                                wallet_tracked::Column::MinReceived,
+                                Expr::value(Option::<f64>::None),
+                            )
+                            .col_expr(
+                                wallet_tracked::Column::MinSent,
+                                Expr::value(Option::<f64>::None),
+                            )
                             .filter(wallet_tracked::Column::ChatId.eq(chat.0))
                             .filter(wallet_tracked::Column::WalletId.eq(address))
                             .exec(db.as_ref())
@@ -585,6 +1058,14 @@ pub async fn run(
                                 wallet_tracked::Column::TrackType,
                                 Expr::value(TrackType::Balance),
                             )
+                            .col_expr(
+                                wallet_tracked::Column::MinReceived,
+                                Expr::value(Option::<f64>::None),
+                            )
+                            .col_expr(
+                                wallet_tracked::Column::MinSent,
+                                Expr::value(Option::<f64>::None),
+                            )
                             .filter(wallet_tracked::Column::ChatId.eq(chat.0))
                             .filter(wallet_tracked::Column::WalletId.eq(address.clone()))
                             .exec(db.as_ref())
@@ -600,6 +1081,14 @@ pub async fn run(
                                 wallet_tracked::Column::TrackType,
                                 Expr::value(TrackType::Receive),
                             )
+                            .col_expr(
+                                wallet_tracked::Column::MinReceived,
+                                Expr::value(Option::<f64>::None),
+                            )
+                            .col_expr(
+                                wallet_tracked::Column::MinSent,
+                                Expr::value(Option::<f64>::None),
+                            )
                             .filter(wallet_tracked::Column::ChatId.eq(chat.0))
                             .filter(wallet_tracked::Column::WalletId.eq(address.clone()))
                             .exec(db.as_ref())
@@ -615,6 +1104,14 @@ pub async fn run(
                                 wallet_tracked::Column::TrackType,
                                 Expr::value(TrackType::Sent),
                             )
+                            .col_expr(
+                                wallet_tracked::Column::MinReceived,
+                                Expr::value(Option::<f64>::None),
+                            )
+                            .col_expr(
+                                wallet_tracked::Column::MinSent,
+                                Expr::value(Option::<f64>::None),
+                            )
                             .filter(wallet_tracked::Column::ChatId.eq(chat.0))
                             .filter(wallet_tracked::Column::WalletId.eq(address.clone()))
                             .exec(db.as_ref())
@@ -624,6 +1121,151 @@ pub async fn run(
                         chat_state.update(State::UpdateFilter(address)).await?;
                         bot.answer_callback_query(q.id).text("200").await?;
                     }
+                    CallbackCommand::Thresholds(id) => {
+                        bot.send_message(chat, THRESHOLDS_PROMPT).await?;
+                        chat_state.update(State::UpdateFilter(id)).await?;
+                        bot.answer_callback_query(q.id).text("200").await?;
+                    }
+                    CallbackCommand::UpdateRule(id) => {
+                        let wallet = WalletTracked::find_by_id(id)
+                            .filter(wallet_tracked::Column::ChatId.eq(chat.0))
+                            .one(db.as_ref())
+                            .await?;
+                        match wallet {
+                            Some(wallet) => {
+                                bot.send_message(
+                                    chat,
+                                    format!(
+                                        "Current rule: {}\n{RULE_PROMPT}",
+                                        wallet.filter_expr.as_deref().unwrap_or("none")
+                                    ),
+                                )
+                                .await?;
+                                chat_state.update(State::UpdateRule(id)).await?;
+                                bot.answer_callback_query(q.id).text("200").await?;
+                            }
+                            None => {
+                                bot.answer_callback_query(q.id)
+                                    .text("Wallet not found")
+                                    .await?;
+                            }
+                        }
+                    }
+                    CallbackCommand::UpdateTokens(id) => {
+                        let wallet = WalletTracked::find_by_id(id)
+                            .filter(wallet_tracked::Column::ChatId.eq(chat.0))
+                            .one(db.as_ref())
+                            .await?;
+                        match (wallet, q.message) {
+                            (Some(wallet), Some(msg_id)) => {
+                                let buttons = vec![vec![
+                                    InlineKeyboardButton::callback(
+                                        "All tokens",
+                                        CallbackCommand::TokensAll(id).to_callback_data(),
+                                    ),
+                                    InlineKeyboardButton::callback(
+                                        "Only",
+                                        CallbackCommand::TokensInclude(id).to_callback_data(),
+                                    ),
+                                    InlineKeyboardButton::callback(
+                                        "All except",
+                                        CallbackCommand::TokensExclude(id).to_callback_data(),
+                                    ),
+                                ]];
+                                let _msg = bot
+                                    .edit_message_text(
+                                        chat,
+                                        msg_id.id,
+                                        format!(
+                                            "Tokens alerted: {}\nOnly: alert on the listed tokens\nAll except: never alert on the listed tokens",
+                                            describe_tokens(&wallet)
+                                        ),
+                                    )
+                                    .reply_markup(InlineKeyboardMarkup::new(buttons))
+                                    .await;
+                                bot.answer_callback_query(q.id).text("200").await?;
+                            }
+                            _ => {
+                                bot.answer_callback_query(q.id)
+                                    .text("Wallet not found")
+                                    .await?;
+                            }
+                        }
+                    }
+                    CallbackCommand::TokensAll(id) => {
+                        let _update = WalletTracked::update_many()
+                            .col_expr(
+                                wallet_tracked::Column::TokenMode,
+                                Expr::value(TokenMode::All),
+                            )
+                            .col_expr(
+                                wallet_tracked::Column::Tokens,
+                                Expr::value(Option::<String>::None),
+                            )
+                            .filter(wallet_tracked::Column::ChatId.eq(chat.0))
+                            .filter(wallet_tracked::Column::WalletId.eq(id))
+                            .exec(db.as_ref())
+                            .await?;
+                        bot.answer_callback_query(q.id)
+                            .text("Token filter cleared")
+                            .await?;
+                    }
+                    CallbackCommand::TokensInclude(id) => {
+                        bot.send_message(chat, TOKENS_PROMPT).await?;
+                        chat_state
+                            .update(State::UpdateTokens(id, TokenMode::Include))
+                            .await?;
+                        bot.answer_callback_query(q.id).text("200").await?;
+                    }
+                    CallbackCommand::Mute(id) => {
+                        let until = Utc::now() + TimeDelta::hours(1);
+                        let result = WalletTracked::update_many()
+                            .col_expr(wallet_tracked::Column::MutedUntil, Expr::value(Some(until)))
+                            .filter(wallet_tracked::Column::ChatId.eq(chat.0))
+                            .filter(wallet_tracked::Column::WalletId.eq(id))
+                            .exec(db.as_ref())
+                            .await?;
+                        bot.answer_callback_query(q.id)
+                            .text(match result.rows_affected {
+                                0 => "Wallet not found",
+                                _ => "Muted for 1h, /mute <wallet> off unmutes",
+                            })
+                            .await?;
+                    }
+                    CallbackCommand::History(id, offset, limit) => {
+                        let wallet = WalletTracked::find_by_id(id)
+                            .filter(wallet_tracked::Column::ChatId.eq(chat.0))
+                            .one(db.as_ref())
+                            .await?;
+                        match (wallet, q.message) {
+                            (Some(wallet), Some(msg_id)) => {
+                                let limit = limit.clamp(1, history::MAX_PAGE);
+                                let (entries, more) =
+                                    history::page(db.as_ref(), &wallet, offset, limit).await?;
+                                let _msg = bot
+                                    .edit_message_text(
+                                        chat,
+                                        msg_id.id,
+                                        history::history_text(&wallet, &entries, offset),
+                                    )
+                                    .reply_markup(history_buttons(id, offset, limit, more))
+                                    .await;
+                                bot.answer_callback_query(q.id).text("200").await?;
+                            }
+                            _ => {
+                                bot.answer_callback_query(q.id)
+                                    .text("Wallet not found")
+                                    .await?;
+                            }
+                        }
+                    }
+                    CallbackCommand::TokensExclude(id) => {
+                        bot.send_message(chat, TOKENS_PROMPT).await?;
+                        chat_state
+                            .update(State::UpdateTokens(id, TokenMode::Exclude))
+                            .await?;
+                        bot.answer_callback_query(q.id).text("200").await?;
+                    }
                 };
             }
             (Some(_chat), None) => {
@@ -641,7 +1283,7 @@ pub async fn run(
                                   msg: Message,
                                   chat_state: ChatState,
                                   db: Arc<DatabaseConnection>,
-                                  id: u64| async move {
+                                  id: i64| async move {
         let chat_id = get_chat_id(bot.clone(), db.as_ref(), msg.chat.id).await?;
         match WalletTracked::find()
             .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
@@ -679,32 +1321,137 @@ pub async fn run(
                                 msg: Message,
                                 chat_state: ChatState,
                                 db: Arc<DatabaseConnection>,
-                                id: u64| async move {
+                                id: i64| async move {
         let chat_id = get_chat_id(bot.clone(), db.as_ref(), msg.chat.id).await?;
-        match msg.text() {
-            Some(msg_text) => {
-                let parse = f64::from_str(msg_text);
-                match parse {
-                    Ok(value) => {
-                        let _update = WalletTracked::update_many()
-                            .col_expr(wallet_tracked::Column::MinimumValue, Expr::value(value))
-                            .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
-                            .filter(wallet_tracked::Column::WalletId.eq(id))
-                            .exec(db.as_ref())
-                            .await?;
-                        bot.send_message(chat_id, "Filter updated").await?;
-                        chat_state.update(State::Idle).await?;
-                    }
-                    Err(_err) => {
-                        bot.send_message(chat_id, "number parse error, plz send a valid value")
-                            .await?;
-                    }
-                }
+        let msg_text = match msg.text() {
+            Some(msg_text) => msg_text,
+            None => return Ok::<_, Err>(()),
+        };
+        if let Ok(value) = f64::from_str(msg_text) {
+            let _update = WalletTracked::update_many()
+                .col_expr(wallet_tracked::Column::MinimumValue, Expr::value(value))
+                .col_expr(
+                    wallet_tracked::Column::MinReceived,
+                    Expr::value(Option::<f64>::None),
+                )
+                .col_expr(
+                    wallet_tracked::Column::MinSent,
+                    Expr::value(Option::<f64>::None),
+                )
+                .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
+                .filter(wallet_tracked::Column::WalletId.eq(id))
+                .exec(db.as_ref())
+                .await?;
+            bot.send_message(chat_id, "Filter updated").await?;
+            chat_state.update(State::Idle).await?;
+            return Ok::<_, Err>(());
+        }
+        let known = token_info::Model::get_token_hashmap(db.as_ref()).await?;
+        match parse_thresholds(msg_text, &known) {
+            Ok((received, sent)) => {
+                let (min_received, min_received_token) = received.unzip();
+                let (min_sent, min_sent_token) = sent.unzip();
+                let _update = WalletTracked::update_many()
+                    .col_expr(
+                        wallet_tracked::Column::MinReceived,
+                        Expr::value(min_received),
+                    )
+                    .col_expr(
+                        wallet_tracked::Column::MinReceivedToken,
+                        Expr::value(min_received_token.flatten()),
+                    )
+                    .col_expr(wallet_tracked::Column::MinSent, Expr::value(min_sent))
+                    .col_expr(
+                        wallet_tracked::Column::MinSentToken,
+                        Expr::value(min_sent_token.flatten()),
+                    )
+                    .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
+                    .filter(wallet_tracked::Column::WalletId.eq(id))
+                    .exec(db.as_ref())
+                    .await?;
+                bot.send_message(chat_id, "Filter updated").await?;
+                chat_state.update(State::Idle).await?;
+            }
+            Err(err) => {
+                bot.send_message(chat_id, format!("{err}\n{THRESHOLDS_PROMPT}"))
+                    .await?;
             }
-            None => {}
         }
         Ok::<_, Err>(())
     };
+    let update_tokens_handle = |bot: Bot,
+                                msg: Message,
+                                chat_state: ChatState,
+                                db: Arc<DatabaseConnection>,
+                                (id, mode): (i64, TokenMode)| async move {
+        let chat_id = get_chat_id(bot.clone(), db.as_ref(), msg.chat.id).await?;
+        let text = match msg.text() {
+            Some(text) => text,
+            None => return Ok::<_, Err>(()),
+        };
+        let (tokens, unknown) = resolve_tokens(db.as_ref(), text).await?;
+        if !unknown.is_empty() || tokens.is_empty() {
+            bot.send_message(
+                chat_id,
+                format!(
+                    "Unknown tokens: {}\nSend symbols or asset types again",
+                    unknown.join(", ")
+                ),
+            )
+            .await?;
+            return Ok::<_, Err>(());
+        }
+        let result = WalletTracked::update_many()
+            .col_expr(wallet_tracked::Column::TokenMode, Expr::value(mode))
+            .col_expr(
+                wallet_tracked::Column::Tokens,
+                Expr::value(tokens.join("\n")),
+            )
+            .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
+            .filter(wallet_tracked::Column::WalletId.eq(id))
+            .exec(db.as_ref())
+            .await?;
+        match result.rows_affected {
+            0 => bot.send_message(chat_id, "Address Not found").await?,
+            _ => bot.send_message(chat_id, "Token filter updated").await?,
+        };
+        chat_state.update(State::Idle).await?;
+        Ok::<_, Err>(())
+    };
+    let update_rule_handle = |bot: Bot,
+                              msg: Message,
+                              chat_state: ChatState,
+                              db: Arc<DatabaseConnection>,
+                              id: i64| async move {
+        let chat_id = get_chat_id(bot.clone(), db.as_ref(), msg.chat.id).await?;
+        let text = match msg.text() {
+            Some(text) => text.trim(),
+            None => return Ok::<_, Err>(()),
+        };
+        let rule = match text.eq_ignore_ascii_case("clear") {
+            true => None,
+            false => match FilterExpr::parse(text) {
+                Ok(_rule) => Some(text.to_string()),
+                Err(err) => {
+                    bot.send_message(chat_id, format!("Rule error at {err}\nSend it again"))
+                        .await?;
+                    return Ok::<_, Err>(());
+                }
+            },
+        };
+        let result = WalletTracked::update_many()
+            .col_expr(wallet_tracked::Column::FilterExpr, Expr::value(rule))
+            .filter(wallet_tracked::Column::ChatId.eq(chat_id.0))
+            .filter(wallet_tracked::Column::WalletId.eq(id))
+            .exec(db.as_ref())
+            .await?;
+        match result.rows_affected {
+            0 => bot.send_message(chat_id, "Address Not found").await?,
+            _ => bot.send_message(chat_id, "Rule updated").await?,
+        };
+        chat_state.update(State::Idle).await?;
+        Ok::<_, Err>(())
+    };
     let default_handler = |bot: Bot, msg: Message, db: Arc<DatabaseConnection>| async move {
         let chat_id = get_chat_id(bot.clone(), db.as_ref(), msg.clone().chat.id).await?;
         if let Some(text) = msg.text() {
@@ -737,7 +1484,9 @@ pub async fn run(
             }
             Some(user) => user,
         };
-        let _user_check = User::find_by_id(from.id.0).one(db.as_ref()).await;
+        let _user_check = User::find_by_id(i64::try_from(from.id.0).unwrap_or(i64::MAX))
+            .one(db.as_ref())
+            .await;
         true
     };
 
@@ -759,14 +1508,15 @@ pub async fn run(
                 .enter_dialogue::<Message, InMemStorage<State>, State>()
                 .branch(case![State::Idle].endpoint(default_handler))
                 .branch(case![State::UpdateNickname(address)].endpoint(update_nickname_handle))
-                .branch(case![State::UpdateFilter(address)].endpoint(update_filter_handle)),
+                .branch(case![State::UpdateFilter(address)].endpoint(update_filter_handle))
+                .branch(case![State::UpdateTokens(id, mode)].endpoint(update_tokens_handle))
+                .branch(case![State::UpdateRule(id)].endpoint(update_rule_handle)),
         );
     Dispatcher::builder(bot.clone(), handler)
         .dependencies(deps![
             db.clone(),
-            rpc.clone(),
+            networks.clone(),
             new_token.clone(),
-            tx_client.clone(),
             InMemStorage::<State>::new()
         ])
         .default_handler(|_| async {})
@@ -774,6 +1524,7 @@ pub async fn run(
             db.clone(),
             bot.clone(),
             "bot_handle".to_string(),
+            report_chat,
         ))
         .enable_ctrlc_handler()
         .build()
@@ -793,7 +1544,100 @@ fn opt(input: String) -> Result<(Option<String>,), ParseError> {
     }
 }
 
-async fn update_handler(bot: Bot, chat_id: ChatId, id: u64, address: String) -> Result<(), Err> {
+fn opt_pair(input: String) -> Result<(OptString, OptString), ParseError> {
+    let mut all = input.split_whitespace();
+    match (all.next(), all.next(), all.count()) {
+        (first, second, 0) => Ok((first.map(str::to_string), second.map(str::to_string))),
+        (_, _, extra) => Err(ParseError::TooManyArguments {
+            expected: 2,
+            found: 2 + extra,
+            message: String::from("Wrong number of arguments"),
+        }),
+    }
+}
+
+/// Splits a leading network off command arguments. Like `/subscribe`, the first word
+/// is a network only when it names a configured one, and only when more words follow.
+fn split_network<'a, 'b>(
+    networks: &'b Networks,
+    words: &'a [&'b str],
+) -> (Option<&'b str>, &'a [&'b str]) {
+    match words {
+        [first, rest @ ..] if !rest.is_empty() && networks.get(first).is_some() => {
+            (Some(*first), rest)
+        }
+        _ => (None, words),
+    }
+}
+
+/// Subscriptions of `chat_id` to `wallet`, only those on `network` when one is given.
+async fn find_wallet(
+    db: &DatabaseConnection,
+    chat_id: i64,
+    network: Option<&str>,
+    wallet: &str,
+) -> Result<Vec<wallet_tracked::Model>, Err> {
+    let mut found = wallet_tracked::Model::find_in_chat(db, chat_id, wallet).await?;
+    found.retain(|subscription| network.map_or(true, |network| subscription.network == network));
+    Ok(found)
+}
+
+/// One button per subscription, labelled with its network, for a wallet tracked on
+/// several networks.
+fn network_buttons(
+    found: &[wallet_tracked::Model],
+    data: impl Fn(&wallet_tracked::Model) -> String,
+) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(found.iter().map(|subscription| {
+        vec![InlineKeyboardButton::callback(
+            format!(
+                "{} on {}",
+                subscription.clone().display_name(),
+                subscription.network
+            ),
+            data(subscription),
+        )]
+    }))
+}
+
+/// The reply to a command without buttons naming a wallet tracked on several networks.
+fn several_networks(found: &[wallet_tracked::Model], command: &str, wallet: &str) -> String {
+    let names = found
+        .iter()
+        .map(|subscription| subscription.network.as_str())
+        .collect::<Vec<&str>>();
+    format!(
+        "{wallet} is tracked on {}, put the network first, ex: /{command} {} {wallet}",
+        names.join(", "),
+        names[0]
+    )
+}
+
+/// The whole argument text, `None` when empty.
+fn rest(input: String) -> Result<(OptString,), ParseError> {
+    let input = input.trim();
+    Ok(((!input.is_empty()).then(|| input.to_string()),))
+}
+
+/// Newer and Older buttons around the page of `limit` entries after `offset`.
+fn history_buttons(id: i64, offset: u64, limit: u64, more: bool) -> InlineKeyboardMarkup {
+    let mut row = vec![];
+    if offset > 0 {
+        row.push(InlineKeyboardButton::callback(
+            "Newer",
+            CallbackCommand::History(id, offset.saturating_sub(limit), limit).to_callback_data(),
+        ));
+    }
+    if more {
+        row.push(InlineKeyboardButton::callback(
+            "Older",
+            CallbackCommand::History(id, offset + limit, limit).to_callback_data(),
+        ));
+    }
+    InlineKeyboardMarkup::new([row].into_iter().filter(|row| !row.is_empty()))
+}
+
+async fn update_handler(bot: Bot, chat_id: ChatId, id: i64, address: String) -> Result<(), Err> {
     let item = vec![
         InlineKeyboardButton::callback(
             "nickname",
@@ -803,6 +1647,14 @@ async fn update_handler(bot: Bot, chat_id: ChatId, id: u64, address: String) ->
             "filter",
             CallbackCommand::UpdateFilter(id.clone()).to_callback_data(),
         ),
+        InlineKeyboardButton::callback(
+            "tokens",
+            CallbackCommand::UpdateTokens(id.clone()).to_callback_data(),
+        ),
+        InlineKeyboardButton::callback(
+            "rule",
+            CallbackCommand::UpdateRule(id.clone()).to_callback_data(),
+        ),
     ];
     bot.send_message(chat_id, format!("Settings for {address}"))
         .reply_markup(InlineKeyboardMarkup::new([item]))
@@ -810,6 +1662,154 @@ async fn update_handler(bot: Bot, chat_id: ChatId, id: u64, address: String) ->
     Ok::<_, Err>(())
 }
 
+fn describe_tokens(wallet: &wallet_tracked::Model) -> String {
+    let list = wallet.token_list().join(", ");
+    match wallet.token_mode {
+        TokenMode::All => "all".to_string(),
+        TokenMode::Include => format!("only {list}"),
+        TokenMode::Exclude => format!("all except {list}"),
+    }
+}
+
+/// Asset types named in `text`. Anything with `::` or parsing as an address is taken
+/// as is, other words are looked up as `token_info` names. Returns the asset types and
+/// the words that matched nothing.
+async fn resolve_tokens(
+    db: &DatabaseConnection,
+    text: &str,
+) -> Result<(Vec<String>, Vec<String>), Err> {
+    let known = token_info::Model::get_token_hashmap(db).await?;
+    let mut tokens = vec![];
+    let mut unknown = vec![];
+    for word in split_tokens(text) {
+        match resolve_token(&word, &known) {
+            Some(token) => tokens.push(token),
+            None => unknown.push(word),
+        }
+    }
+    tokens.sort();
+    tokens.dedup();
+    Ok((tokens, unknown))
+}
+
+/// Asset type of one word: anything with `::` or parsing as an address is taken as
+/// is, other words are looked up as `token_info` names.
+fn resolve_token(word: &str, known: &TokenMap) -> Option<String> {
+    if word.contains("::") {
+        return Some(word.to_string());
+    }
+    if let Ok(address) = AccountAddress::from_str(word) {
+        return Some(address.to_string());
+    }
+    known
+        .values()
+        .find(|token| {
+            token
+                .name
+                .as_deref()
+                .map_or(false, |name| name.eq_ignore_ascii_case(word))
+        })
+        .map(|token| token.mint.clone())
+}
+
+/// A threshold typed in the filter dialog, the asset type is `None` for USD.
+type ThresholdInput = Option<(f64, Option<String>)>;
+
+/// Parses `received 1000 APT, sent 50 usd` into the received and sent thresholds.
+/// A direction left out is cleared, `clear` clears both.
+fn parse_thresholds(
+    text: &str,
+    known: &TokenMap,
+) -> Result<(ThresholdInput, ThresholdInput), String> {
+    let mut received = None;
+    let mut sent = None;
+    if text.trim().eq_ignore_ascii_case("clear") {
+        return Ok((received, sent));
+    }
+    let clauses = text
+        .split(|c| c == ',' || c == ';' || c == '\n')
+        .map(str::trim)
+        .filter(|clause| !clause.is_empty());
+    for clause in clauses {
+        let words: Vec<&str> = clause.split_whitespace().collect();
+        if words.len() > 3 {
+            return Err(format!(
+                "{clause}: expected received|sent <amount> [usd|token]"
+            ));
+        }
+        let slot = match words[0].to_lowercase().as_str() {
+            "received" | "receive" | "in" => &mut received,
+            "sent" | "send" | "out" => &mut sent,
+            _ => return Err(format!("{clause}: start with received or sent")),
+        };
+        let amount = words
+            .get(1)
+            .ok_or_else(|| format!("{clause}: amount missing"))?;
+        let (amount, dollar) = match amount.strip_prefix('$') {
+            Some(amount) => (amount, true),
+            None => (*amount, false),
+        };
+        let value = f64::from_str(amount)
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .ok_or_else(|| format!("{clause}: {amount} is not a positive number"))?;
+        let token = match words.get(2) {
+            None => None,
+            Some(unit) if unit.eq_ignore_ascii_case("usd") || *unit == "$" => None,
+            Some(unit) if dollar => return Err(format!("{clause}: $ and {unit} both given")),
+            Some(unit) => Some(
+                resolve_token(unit, known)
+                    .ok_or_else(|| format!("{clause}: unknown token {unit}"))?,
+            ),
+        };
+        *slot = Some((value, token));
+    }
+    Ok((received, sent))
+}
+
+fn describe_thresholds(wallet: &wallet_tracked::Model, known: &TokenMap) -> String {
+    let describe = |threshold: Option<Threshold>| match threshold {
+        None => "off".to_string(),
+        Some(Threshold { value, token: None }) => format!("{value} USD"),
+        Some(Threshold {
+            value,
+            token: Some(token),
+        }) => {
+            let name = known.get(token).and_then(|entry| entry.name.clone());
+            format!("{value} {}", name.as_deref().unwrap_or(token))
+        }
+    };
+    match (wallet.received_threshold(), wallet.sent_threshold()) {
+        (None, None) => "off".to_string(),
+        (received, sent) => format!("received {}, sent {}", describe(received), describe(sent)),
+    }
+}
+
+/// Splits on whitespace and commas outside the type arguments of an asset type.
+fn split_tokens(text: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut depth = 0_usize;
+    for c in text.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 && (c.is_whitespace() || c == ',') {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        } else if !(depth > 0 && c.is_whitespace()) {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
 async fn get_chat_id(bot: Bot, db: &DatabaseConnection, chat_id: ChatId) -> Result<ChatId, Err> {
     let result = bot.get_chat(chat_id).await;
     match result {
@@ -830,12 +1830,33 @@ async fn get_chat_id(bot: Bot, db: &DatabaseConnection, chat_id: ChatId) -> Resu
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 struct ParsedSubscribe {
+    network: OptString,
     address: OptString,
     nickname: OptString,
 }
+
+impl ParsedSubscribe {
+    /// The first argument is a network only when it names a configured one,
+    /// otherwise the arguments shift into address and nickname on the default network.
+    fn resolve(self, networks: &Networks) -> (Arc<Network>, Self) {
+        if let Some(network) = self.network.as_deref().and_then(|name| networks.get(name)) {
+            return (network.clone(), self);
+        }
+        (
+            networks.default_network().clone(),
+            ParsedSubscribe {
+                network: None,
+                address: self.network,
+                nickname: self.address,
+            },
+        )
+    }
+}
+
 fn opt2(input: String) -> Result<(ParsedSubscribe,), ParseError> {
     let mut all = input.split_whitespace();
     Ok((ParsedSubscribe {
+        network: all.next().map(str::to_string),
         address: all.next().map(str::to_string),
         nickname: all.next().map(str::to_string),
     },))
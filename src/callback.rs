@@ -1,13 +1,22 @@
 use std::str::FromStr;
 pub enum CallbackCommand {
-    Unsubscribe(u64),
-    Update(u64),
-    UpdateNickname(u64),
-    UpdateFilter(u64),
-    TrackBalance(u64),
-    TrackReceive(u64),
-    TrackSent(u64),
-    TrackFull(u64),
+    Unsubscribe(i64),
+    Update(i64),
+    UpdateNickname(i64),
+    UpdateFilter(i64),
+    TrackBalance(i64),
+    TrackReceive(i64),
+    TrackSent(i64),
+    TrackFull(i64),
+    UpdateTokens(i64),
+    TokensAll(i64),
+    TokensInclude(i64),
+    TokensExclude(i64),
+    Thresholds(i64),
+    UpdateRule(i64),
+    Mute(i64),
+    /// Subscription, entries skipped and page size.
+    History(i64, u64, u64),
 }
 
 #[derive(Debug, derive_more::Error, derive_more::Display)]
@@ -25,6 +34,14 @@ const BALANCE_INDEX: &str = "4";
 const RECEIVE_INDEX: &str = "5";
 const SENT_INDEX: &str = "6";
 const FULL_INDEX: &str = "7";
+const TOKENS_INDEX: &str = "8";
+const TOKENS_ALL_INDEX: &str = "9";
+const TOKENS_INCLUDE_INDEX: &str = "10";
+const TOKENS_EXCLUDE_INDEX: &str = "11";
+const THRESHOLDS_INDEX: &str = "12";
+const RULE_INDEX: &str = "13";
+const MUTE_INDEX: &str = "14";
+const HISTORY_INDEX: &str = "15";
 impl CallbackCommand {
     fn index(&self) -> &str {
         match self {
@@ -36,6 +53,14 @@ impl CallbackCommand {
             CallbackCommand::TrackReceive(..) => RECEIVE_INDEX,
             CallbackCommand::TrackSent(..) => SENT_INDEX,
             CallbackCommand::TrackFull(..) => FULL_INDEX,
+            CallbackCommand::UpdateTokens(..) => TOKENS_INDEX,
+            CallbackCommand::TokensAll(..) => TOKENS_ALL_INDEX,
+            CallbackCommand::TokensInclude(..) => TOKENS_INCLUDE_INDEX,
+            CallbackCommand::TokensExclude(..) => TOKENS_EXCLUDE_INDEX,
+            CallbackCommand::Thresholds(..) => THRESHOLDS_INDEX,
+            CallbackCommand::UpdateRule(..) => RULE_INDEX,
+            CallbackCommand::Mute(..) => MUTE_INDEX,
+            CallbackCommand::History(..) => HISTORY_INDEX,
         }
     }
     pub fn to_callback_data(&self) -> String {
@@ -65,6 +90,18 @@ impl CallbackCommand {
             CallbackCommand::TrackFull(id) => {
                 format!("{index} {id}")
             }
+            CallbackCommand::UpdateTokens(id)
+            | CallbackCommand::TokensAll(id)
+            | CallbackCommand::TokensInclude(id)
+            | CallbackCommand::TokensExclude(id)
+            | CallbackCommand::Thresholds(id)
+            | CallbackCommand::UpdateRule(id)
+            | CallbackCommand::Mute(id) => {
+                format!("{index} {id}")
+            }
+            CallbackCommand::History(id, offset, limit) => {
+                format!("{index} {id} {offset} {limit}")
+            }
         }
     }
     pub fn from_string(data: String) -> Result<CallbackCommand, ParseCallbackErr> {
@@ -73,52 +110,102 @@ impl CallbackCommand {
             Some(s) => match s {
                 UNSUB_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::Unsubscribe(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 UPDATE_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::Update(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 NICKNAME_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::UpdateNickname(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 FILTER_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::UpdateFilter(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 BALANCE_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::TrackBalance(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 RECEIVE_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::TrackReceive(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 SENT_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::TrackSent(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
                 FULL_INDEX => match data.next() {
                     Some(id) => Ok(CallbackCommand::TrackFull(
-                        u64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                TOKENS_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::UpdateTokens(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                TOKENS_ALL_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::TokensAll(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                TOKENS_INCLUDE_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::TokensInclude(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                TOKENS_EXCLUDE_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::TokensExclude(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
                     )),
                     None => Err(ParseCallbackErr::ArgNotFound),
                 },
+                THRESHOLDS_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::Thresholds(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                RULE_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::UpdateRule(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                MUTE_INDEX => match data.next() {
+                    Some(id) => Ok(CallbackCommand::Mute(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    None => Err(ParseCallbackErr::ArgNotFound),
+                },
+                HISTORY_INDEX => match (data.next(), data.next(), data.next()) {
+                    (Some(id), Some(offset), Some(limit)) => Ok(CallbackCommand::History(
+                        i64::from_str(id).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        u64::from_str(offset).map_err(|_| ParseCallbackErr::IdParseError)?,
+                        u64::from_str(limit).map_err(|_| ParseCallbackErr::IdParseError)?,
+                    )),
+                    _ => Err(ParseCallbackErr::ArgNotFound),
+                },
                 _ => Err(ParseCallbackErr::TypeNotFound),
             },
             None => Err(ParseCallbackErr::NoTypeProvided),
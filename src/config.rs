@@ -0,0 +1,742 @@
+use reqwest::header::{HeaderName, HeaderValue};
+use sea_orm::ActiveEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+
+use crate::entities::sea_orm_active_enums::TrackType;
+
+/// Path of the config file when `CONFIG` is not set, a missing file means defaults.
+const DEFAULT_PATH: &str = "config.toml";
+
+/// Network name used for the top level `rpc`, `indexer` and `explorer` tables.
+pub const DEFAULT_NETWORK: &str = "mainnet";
+
+/// Everything the tracker can be tuned with. Loaded from a TOML file, then the
+/// environment variables used before the file existed are applied on top.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub telegram: TelegramConfig,
+    pub rpc: RpcConfig,
+    pub indexer: IndexerConfig,
+    pub worker: WorkerConfig,
+    pub health: HealthConfig,
+    pub explorer: ExplorerConfig,
+    pub api: ApiConfig,
+    pub record: RecordConfig,
+    pub shadow: ShadowConfig,
+    pub webhook: WebhookConfig,
+    pub spam: SpamConfig,
+    /// `[[network]]` tables, the first one is the default for commands that take no
+    /// network. When empty the top level `rpc`, `indexer` and `explorer` are used as
+    /// a single `mainnet` network.
+    #[serde(rename = "network")]
+    pub networks: Vec<NetworkConfig>,
+    /// `[[subscription]]` tables, inserted or updated at startup. Removing one does not
+    /// delete the subscription.
+    #[serde(rename = "subscription")]
+    pub subscriptions: Vec<SubscriptionConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Stored on subscriptions and processed blocks, `[a-z0-9_]` up to 20 chars.
+    pub name: String,
+    #[serde(default)]
+    pub rpc: RpcConfig,
+    #[serde(default)]
+    pub indexer: IndexerConfig,
+    /// Defaults to the Aptos explorer with `?network=<name>`.
+    pub explorer: Option<ExplorerConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub sqlx_logging: bool,
+    /// Apply pending migrations at startup, otherwise only verify the schema.
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    /// Off runs the pipeline headless, alerts only reach `webhook` and `shadow.log`.
+    /// Always off in builds without the `telegram` feature.
+    pub enabled: bool,
+    pub token: String,
+    /// Chat receiving errors and health reports.
+    pub report_chat: Option<i64>,
+    /// Alerts of a subscription within this many seconds of the message starting a
+    /// burst are edited into that message instead of sent, 0, the default, sends each
+    /// alert.
+    pub burst_window_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    /// Fullnode REST urls, the first one is preferred while latencies are unknown.
+    pub urls: Vec<String>,
+    pub breaker_threshold: u32,
+    pub breaker_cooldown_secs: u64,
+    /// Nodes further than this behind the best known height are not routed to.
+    pub max_height_lag: u64,
+    pub refresh_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexerConfig {
+    pub endpoints: Vec<IndexerEndpointConfig>,
+    pub retries: u32,
+    pub backoff_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexerEndpointConfig {
+    pub url: String,
+    /// Sent as `Authorization: Bearer <api_key>`.
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkerConfig {
+    /// Block workers, each takes the heights with its own remainder.
+    pub threads: u8,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    pub report_interval_secs: u64,
+    /// Report when the processed block is this far behind the chain head.
+    pub max_lag_blocks: u64,
+    /// Report an RPC node this far behind the best node.
+    pub max_rpc_divergence: u64,
+    pub ready_max_indexer_lag: i64,
+    pub ready_max_block_age_secs: i64,
+    pub worker_stall_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExplorerConfig {
+    /// Transaction link, `{}` is replaced by the version.
+    pub tx_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    pub addr: Option<String>,
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordConfig {
+    /// Directory receiving a JSON fixture per processed block, unset disables recording.
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Receives every alert and health report as a JSON POST, unset disables it.
+    pub url: Option<String>,
+    pub headers: BTreeMap<String, String>,
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpamConfig {
+    /// Drop unsolicited deposits of unpriced and dust tokens. Tokens skipped in
+    /// `token_info` are dropped either way.
+    pub enabled: bool,
+    /// Unsolicited deposits of priced tokens worth less than this are dropped.
+    pub dust_usd: f64,
+    /// Mark unpriced tokens airdropped to tracked wallets by untracked senders as
+    /// skipped until they get a price. Off by default, operators opt in.
+    pub auto_flag: bool,
+    /// How often `token_info` is reloaded, so tokens skipped with the admin tool stop
+    /// alerting without a restart.
+    pub refresh_secs: u64,
+}
+
+/// A subscription declared in the config, for deployments without the bot.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscriptionConfig {
+    /// Defaults to the first network.
+    pub network: Option<String>,
+    /// Only a routing key for webhook consumers when headless.
+    pub chat_id: i64,
+    /// Defaults to `chat_id`.
+    pub user_id: Option<i64>,
+    pub wallet_address: String,
+    pub nickname: Option<String>,
+    /// `full`, `sent`, `receive` or `balance`.
+    #[serde(default = "default_track_type")]
+    pub track_type: String,
+    #[serde(default)]
+    pub minimum_value: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShadowConfig {
+    /// Run the pipeline without the Telegram bot, alerts only go to `log`.
+    pub enabled: bool,
+    /// JSON lines file receiving every alert. Set it in production too to diff the
+    /// two deployments with `aptos-tracking diff`.
+    pub log: Option<String>,
+    /// Database for `processed_block`, so a shadow doesn't claim production's
+    /// blocks. Subscriptions and prices are still read from `database.url`.
+    pub bookkeeping_url: Option<String>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: String::new(),
+            max_connections: 50,
+            sqlx_logging: false,
+            auto_migrate: true,
+        }
+    }
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        TelegramConfig {
+            enabled: true,
+            token: String::new(),
+            report_chat: None,
+            burst_window_secs: 0,
+        }
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        WebhookConfig {
+            url: None,
+            headers: BTreeMap::new(),
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        SpamConfig {
+            enabled: true,
+            dust_usd: 0.01,
+            auto_flag: false,
+            refresh_secs: 60,
+        }
+    }
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        RpcConfig {
+            urls: vec![],
+            breaker_threshold: 3,
+            breaker_cooldown_secs: 30,
+            max_height_lag: 10,
+            refresh_secs: 5,
+        }
+    }
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        IndexerConfig {
+            endpoints: vec![],
+            retries: 3,
+            backoff_ms: 500,
+        }
+    }
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        WorkerConfig { threads: 3 }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            report_interval_secs: 120,
+            max_lag_blocks: 1000,
+            max_rpc_divergence: 100,
+            ready_max_indexer_lag: 10_000,
+            ready_max_block_age_secs: 60,
+            worker_stall_secs: 120,
+        }
+    }
+}
+
+impl Default for ExplorerConfig {
+    fn default() -> Self {
+        ExplorerConfig {
+            tx_url: "https://explorer.aptoslabs.com/txn/{}?network=mainnet".to_string(),
+        }
+    }
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_track_type() -> String {
+    "full".to_string()
+}
+
+#[derive(Debug, derive_more::Display, derive_more::From, derive_more::Error)]
+pub enum ConfigErr {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    #[display("invalid config:\n{}", _0.join("\n"))]
+    #[from(ignore)]
+    Invalid(#[error(not(source))] Vec<String>),
+}
+
+impl Config {
+    /// Reads the file named by `CONFIG` (or `config.toml`), applies env overrides and
+    /// validates the result.
+    pub fn load() -> Result<Self, ConfigErr> {
+        let config = Self::read()?;
+        config.validate(true)?;
+        Ok(config)
+    }
+
+    /// Like [`Config::load`] without the checks that only matter to the bot, for the
+    /// admin binary.
+    pub fn load_admin() -> Result<Self, ConfigErr> {
+        let config = Self::read()?;
+        config.validate(false)?;
+        Ok(config)
+    }
+
+    fn read() -> Result<Self, ConfigErr> {
+        let path = std::env::var("CONFIG").ok();
+        let mut config = match std::fs::read_to_string(path.as_deref().unwrap_or(DEFAULT_PATH)) {
+            Ok(raw) => toml::from_str::<Config>(&raw)?,
+            Err(e) if path.is_none() && e.kind() == std::io::ErrorKind::NotFound => {
+                Config::default()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        config.apply_env();
+        if config.networks.is_empty() {
+            config.networks.push(NetworkConfig {
+                name: DEFAULT_NETWORK.to_string(),
+                rpc: config.rpc.clone(),
+                indexer: config.indexer.clone(),
+                explorer: Some(config.explorer.clone()),
+            });
+        }
+        Ok(config)
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(url) = std::env::var("DATABASE_URL") {
+            self.database.url = url;
+        }
+        if let Some(enabled) = env_parse("TELEGRAM_ENABLED") {
+            self.telegram.enabled = enabled;
+        }
+        if let Ok(token) = std::env::var("TELOXIDE_TOKEN") {
+            self.telegram.token = token;
+        }
+        if let Some(chat) = env_parse("REPORT_CHAT") {
+            self.telegram.report_chat = Some(chat);
+        }
+        if let Some(secs) = env_parse("BURST_WINDOW_SECS") {
+            self.telegram.burst_window_secs = secs;
+        }
+        let mut rpc_urls = vec![];
+        rpc_urls.extend(std::env::var("RPC_URL").ok());
+        rpc_urls.extend(std::env::var("BLOCK_RPC_URL").ok());
+        if let Ok(list) = std::env::var("RPC_URLS") {
+            rpc_urls.extend(
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        if !rpc_urls.is_empty() {
+            self.rpc.urls = rpc_urls;
+        }
+        let mut endpoints = vec![];
+        endpoints.extend(IndexerEndpointConfig::from_env(""));
+        for index in 1.. {
+            match IndexerEndpointConfig::from_env(&format!("_{index}")) {
+                Some(endpoint) => endpoints.push(endpoint),
+                None => break,
+            }
+        }
+        if !endpoints.is_empty() {
+            self.indexer.endpoints = endpoints;
+        }
+        if let Some(timeout) = env_parse("GRAPHQL_TIMEOUT_MS") {
+            for endpoint in self.indexer.endpoints.iter_mut() {
+                endpoint.timeout_ms = timeout;
+            }
+        }
+        if let Some(retries) = env_parse("GRAPHQL_RETRIES") {
+            self.indexer.retries = retries;
+        }
+        if let Some(threads) = env_parse("THREAD") {
+            self.worker.threads = threads;
+        }
+        if let Some(lag) = env_parse("READY_MAX_INDEXER_LAG") {
+            self.health.ready_max_indexer_lag = lag;
+        }
+        if let Some(age) = env_parse("READY_MAX_BLOCK_AGE_SECS") {
+            self.health.ready_max_block_age_secs = age;
+        }
+        if let Ok(addr) = std::env::var("API_ADDR") {
+            self.api.addr = Some(addr);
+        }
+        if let Ok(key) = std::env::var("API_KEY") {
+            self.api.key = Some(key);
+        }
+        if let Ok(dir) = std::env::var("RECORD_DIR") {
+            self.record.dir = Some(dir);
+        }
+        if let Some(enabled) = env_parse("SHADOW") {
+            self.shadow.enabled = enabled;
+        }
+        if let Ok(log) = std::env::var("SHADOW_LOG") {
+            self.shadow.log = Some(log);
+        }
+        if let Ok(url) = std::env::var("SHADOW_BOOKKEEPING_URL") {
+            self.shadow.bookkeeping_url = Some(url);
+        }
+        if let Ok(url) = std::env::var("WEBHOOK_URL") {
+            self.webhook.url = Some(url);
+        }
+    }
+
+    /// Whether the bot runs the Telegram dispatcher and sends alerts to Telegram.
+    pub fn telegram_enabled(&self) -> bool {
+        cfg!(feature = "telegram") && self.telegram.enabled && !self.shadow.enabled
+    }
+
+    fn validate(&self, bot: bool) -> Result<(), ConfigErr> {
+        let mut errors = vec![];
+        if self.database.url.is_empty() {
+            errors.push("database.url (DATABASE_URL) is required".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections must be positive".to_string());
+        }
+        if bot && self.telegram_enabled() && self.telegram.token.is_empty() {
+            errors.push("telegram.token (TELOXIDE_TOKEN) is required".to_string());
+        }
+        if bot
+            && !self.telegram_enabled()
+            && self.webhook.url.is_none()
+            && self.shadow.log.is_none()
+        {
+            errors.push("without telegram, webhook.url or shadow.log is required".to_string());
+        }
+        if let Some(url) = &self.webhook.url {
+            if Url::parse(url).is_err() {
+                errors.push(format!("webhook.url {url} is not a url"));
+            }
+        }
+        validate_headers("webhook", &self.webhook.headers, &mut errors);
+        for subscription in self.subscriptions.iter() {
+            let network = subscription.network.as_deref();
+            if network.map_or(false, |name| self.networks.iter().all(|n| n.name != name)) {
+                errors.push(format!(
+                    "subscription {} is on unknown network {}",
+                    subscription.wallet_address,
+                    network.unwrap_or_default()
+                ));
+            }
+            if TrackType::try_from_value(&subscription.track_type.to_lowercase()).is_err() {
+                errors.push(format!(
+                    "subscription {} track_type {} is not full, sent, receive or balance",
+                    subscription.wallet_address, subscription.track_type
+                ));
+            }
+        }
+        for (index, network) in self.networks.iter().enumerate() {
+            if self.networks[..index]
+                .iter()
+                .any(|other| other.name == network.name)
+            {
+                errors.push(format!("network {} is defined twice", network.name));
+            }
+            network.validate(&mut errors);
+        }
+        if self.worker.threads == 0 {
+            errors.push("worker.threads (THREAD) must be positive".to_string());
+        }
+        if self.health.report_interval_secs == 0 {
+            errors.push("health.report_interval_secs must be positive".to_string());
+        }
+        if self.spam.refresh_secs == 0 {
+            errors.push("spam.refresh_secs must be positive".to_string());
+        }
+        if self.shadow.enabled && self.shadow.log.is_none() {
+            errors.push("shadow.log (SHADOW_LOG) is required in shadow mode".to_string());
+        }
+        match (&self.api.addr, &self.api.key) {
+            (Some(addr), _) if std::net::SocketAddr::from_str(addr).is_err() => {
+                errors.push(format!("api.addr {addr} is not a socket address"));
+            }
+            (None, Some(_)) => errors.push("api.key is set but api.addr is not".to_string()),
+            _ => {}
+        }
+        if let Some(key) = &self.api.key {
+            if key.trim().is_empty() {
+                errors.push("api.key (API_KEY) must not be empty".to_string());
+            } else if HeaderValue::from_str(key).is_err() {
+                errors.push("api.key (API_KEY) is not a valid header value".to_string());
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigErr::Invalid(errors)),
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn explorer(&self) -> ExplorerConfig {
+        match &self.explorer {
+            Some(explorer) => explorer.clone(),
+            None => ExplorerConfig {
+                tx_url: format!(
+                    "https://explorer.aptoslabs.com/txn/{{}}?network={}",
+                    self.name
+                ),
+            },
+        }
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        let name = &self.name;
+        if name.is_empty()
+            || name.len() > 20
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            errors.push(format!(
+                "network name {name:?} must be [a-z0-9_], 1 to 20 chars"
+            ));
+        }
+        if self.rpc.urls.is_empty() {
+            errors.push(format!(
+                "network {name} rpc.urls (RPC_URL) needs at least one url"
+            ));
+        }
+        for url in self.rpc.urls.iter() {
+            if let Err(e) = Url::parse(url) {
+                errors.push(format!("network {name} rpc url {url}: {e}"));
+            }
+        }
+        if self.rpc.breaker_threshold == 0 {
+            errors.push(format!(
+                "network {name} rpc.breaker_threshold must be positive"
+            ));
+        }
+        if self.indexer.endpoints.is_empty() {
+            errors.push(format!(
+                "network {name} indexer.endpoints (GRAPHQL_URL) needs at least one url"
+            ));
+        }
+        for endpoint in self.indexer.endpoints.iter() {
+            if let Err(e) = Url::parse(&endpoint.url) {
+                errors.push(format!("network {name} indexer url {}: {e}", endpoint.url));
+            }
+            if endpoint.timeout_ms == 0 {
+                errors.push(format!(
+                    "network {name} indexer {} timeout_ms must be positive",
+                    endpoint.url
+                ));
+            }
+            match endpoint.api_key.as_deref() {
+                Some(key) if key.trim().is_empty() => errors.push(format!(
+                    "network {name} indexer {} api_key must not be empty",
+                    endpoint.url
+                )),
+                Some(key) if HeaderValue::from_str(&format!("Bearer {key}")).is_err() => errors
+                    .push(format!(
+                        "network {name} indexer {} api_key is not a valid header value",
+                        endpoint.url
+                    )),
+                _ => {}
+            }
+            validate_headers(
+                &format!("network {name} indexer {}", endpoint.url),
+                &endpoint.headers,
+                errors,
+            );
+        }
+        if !self.explorer().tx_url.contains("{}") {
+            errors.push(format!(
+                "network {name} explorer.tx_url needs a {{}} placeholder for the version"
+            ));
+        }
+    }
+}
+
+/// Reports the `headers` of `owner` that are not valid HTTP headers. Values are left
+/// out of the messages, they often hold credentials.
+fn validate_headers(owner: &str, headers: &BTreeMap<String, String>, errors: &mut Vec<String>) {
+    for (name, value) in headers.iter() {
+        if HeaderName::from_str(name).is_err() {
+            errors.push(format!("{owner} header name {name:?} is invalid"));
+        }
+        if HeaderValue::from_str(value).is_err() {
+            errors.push(format!("{owner} header {name} has an invalid value"));
+        }
+    }
+}
+
+impl IndexerEndpointConfig {
+    /// `GRAPHQL_URL{suffix}` with `GRAPHQL_API_KEY{suffix}` and `GRAPHQL_HEADERS{suffix}`
+    /// as `Name: value` pairs split by `;`.
+    fn from_env(suffix: &str) -> Option<Self> {
+        let url = std::env::var(format!("GRAPHQL_URL{suffix}")).ok()?;
+        let mut headers = BTreeMap::new();
+        if let Ok(raw) = std::env::var(format!("GRAPHQL_HEADERS{suffix}")) {
+            for pair in raw.split(';') {
+                if let Some((name, value)) = pair.split_once(':') {
+                    headers.insert(name.trim().to_string(), value.trim().to_string());
+                }
+            }
+        }
+        Some(IndexerEndpointConfig {
+            url,
+            api_key: std::env::var(format!("GRAPHQL_API_KEY{suffix}")).ok(),
+            headers,
+            timeout_ms: default_timeout_ms(),
+        })
+    }
+}
+
+impl ExplorerConfig {
+    pub fn tx_url(&self, version: impl std::fmt::Display) -> String {
+        self.tx_url.replacen("{}", &version.to_string(), 1)
+    }
+}
+
+impl HealthConfig {
+    pub fn worker_stall(&self) -> Duration {
+        Duration::from_secs(self.worker_stall_secs)
+    }
+}
+
+fn env_parse<T: FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|s| T::from_str(&s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+[database]
+url = "sqlite::memory:"
+
+[api]
+addr = "127.0.0.1:8080"
+key = "secret"
+
+[webhook]
+url = "https://hooks.test/alerts"
+headers = { "x-team" = "tracking" }
+
+[[network]]
+name = "mainnet"
+rpc = { urls = ["https://fullnode.test/v1"] }
+
+[[network.indexer.endpoints]]
+url = "https://indexer.test/v1/graphql"
+api_key = "indexer-key"
+headers = { "x-client" = "tracking" }
+"#;
+
+    fn errors(raw: &str) -> Vec<String> {
+        let config: Config = toml::from_str(raw).expect("parse config");
+        match config.validate(false) {
+            Ok(()) => vec![],
+            Err(ConfigErr::Invalid(errors)) => errors,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    #[test]
+    fn accepts_a_complete_config() {
+        assert_eq!(errors(VALID), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_headers_that_are_not_http_headers() {
+        let raw = VALID
+            .replace(r#""x-team" = "tracking""#, r#""x team" = "tracking""#)
+            .replace(
+                r#""x-client" = "tracking""#,
+                r#""x-client" = "line\nbreak""#,
+            );
+        let errors = errors(&raw);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].starts_with("webhook header name"), "{errors:?}");
+        assert!(
+            errors[1].ends_with("header x-client has an invalid value"),
+            "{errors:?}"
+        );
+        assert!(!errors.iter().any(|error| error.contains("break")));
+    }
+
+    #[test]
+    fn rejects_empty_or_unsendable_api_keys() {
+        let raw = VALID
+            .replace(r#"key = "secret""#, r#"key = " ""#)
+            .replace(r#"api_key = "indexer-key""#, r#"api_key = "bad\u0000key""#);
+        let errors = errors(&raw);
+        assert_eq!(
+            errors,
+            vec![
+                "network mainnet indexer https://indexer.test/v1/graphql api_key is not a valid header value"
+                    .to_string(),
+                "api.key (API_KEY) must not be empty".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_an_api_key_without_an_address() {
+        let errors = errors(&VALID.replace(r#"addr = "127.0.0.1:8080""#, ""));
+        assert_eq!(
+            errors,
+            vec!["api.key is set but api.addr is not".to_string()]
+        );
+    }
+}
@@ -1,26 +1,33 @@
 use crate::callback::CallbackCommand;
-use crate::entities::sea_orm_active_enums::{Place, TrackType};
+use crate::config::Config;
+use crate::entities::sea_orm_active_enums::{Place, TokenMode, TrackType};
+use crate::entities::{chat_setting, processed_block, token_info, user};
 use crate::entities::{
     prelude::*,
     wallet_tracked::{self},
 };
-use crate::entities::{processed_block, token_info, user};
 use crate::error::Err;
+use crate::spam;
+use crate::tx::BalanceChange;
 use ahash::AHashMap;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use std::str::FromStr;
 
 use sea_orm::entity::prelude::*;
-use sea_orm::{FromQueryResult, QueryOrder, QuerySelect};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{Condition, FromQueryResult, IntoActiveModel, QueryOrder, QuerySelect};
 
 #[derive(FromQueryResult, Debug, Clone)]
 pub struct WalletTrackedForChat {
-    pub wallet_id: u64,
+    pub wallet_id: i64,
     pub chat_id: i64,
-    pub user_id: u64,
+    pub user_id: i64,
     pub wallet_address: String,
     pub create_time: DateTimeUtc,
     pub track_type: TrackType,
     pub user_name: Option<String>,
     pub nickname: Option<String>,
+    pub network: String,
 }
 
 #[derive(FromQueryResult, Debug, Clone)]
@@ -80,11 +87,68 @@ impl wallet_tracked::Model {
         let data = CallbackCommand::Unsubscribe(self.wallet_id);
         data.to_callback_data()
     }
+    pub fn to_mute_callback_data(&self) -> String {
+        let data = CallbackCommand::Mute(self.wallet_id);
+        data.to_callback_data()
+    }
+    pub fn to_update_callback_data(&self) -> String {
+        let data = CallbackCommand::Update(self.wallet_id);
+        data.to_callback_data()
+    }
+    /// Subscriptions of `chat_id` to `wallet`, an address or a nickname, on any network.
+    pub async fn find_in_chat(
+        db: &DatabaseConnection,
+        chat_id: i64,
+        wallet: &str,
+    ) -> Result<Vec<Self>, Err> {
+        let by_name = match AccountAddress::from_str(wallet) {
+            Ok(address) => Condition::any()
+                .add(wallet_tracked::Column::WalletAddress.eq(address.to_string()))
+                .add(wallet_tracked::Column::Nickname.eq(wallet)),
+            Err(_) => Condition::any().add(wallet_tracked::Column::Nickname.eq(wallet)),
+        };
+        Ok(WalletTracked::find()
+            .filter(wallet_tracked::Column::ChatId.eq(chat_id))
+            .filter(by_name)
+            .all(db)
+            .await?)
+    }
+    /// Asset types of the token filter.
+    pub fn token_list(&self) -> Vec<&str> {
+        self.tokens.as_deref().map_or(vec![], |tokens| {
+            tokens.lines().filter(|t| !t.is_empty()).collect()
+        })
+    }
+    /// Whether alerts include moves of `token`, a [`BalanceChange::to_token_address`].
+    ///
+    /// [`BalanceChange::to_token_address`]: crate::tx::BalanceChange::to_token_address
+    pub fn allows_token(&self, token: &str) -> bool {
+        match self.token_mode {
+            TokenMode::All => true,
+            TokenMode::Include => self.token_list().contains(&token),
+            TokenMode::Exclude => !self.token_list().contains(&token),
+        }
+    }
+    /// `None` when no received threshold is set.
+    pub fn received_threshold(&self) -> Option<Threshold<'_>> {
+        self.min_received.map(|value| Threshold {
+            value,
+            token: self.min_received_token.as_deref(),
+        })
+    }
+    /// `None` when no sent threshold is set.
+    pub fn sent_threshold(&self) -> Option<Threshold<'_>> {
+        self.min_sent.map(|value| Threshold {
+            value,
+            token: self.min_sent_token.as_deref(),
+        })
+    }
     pub async fn get_filtered(
         db: &DatabaseConnection,
+        network: &str,
         wallet_address: Option<String>,
     ) -> Result<Vec<Self>, Err> {
-        let select = WalletTracked::find();
+        let select = WalletTracked::find().filter(wallet_tracked::Column::Network.eq(network));
         let select = match wallet_address {
             Some(wallet_address) => {
                 select.filter(wallet_tracked::Column::WalletAddress.eq(wallet_address))
@@ -94,11 +158,85 @@ impl wallet_tracked::Model {
 
         Ok(select.all(db).await?)
     }
+    /// Subscriptions of each of `wallet_addresses` on `network`.
+    pub async fn get_by_addresses(
+        db: &DatabaseConnection,
+        network: &str,
+        wallet_addresses: Vec<String>,
+    ) -> Result<AHashMap<String, Vec<Self>>, Err> {
+        let mut map: AHashMap<String, Vec<Self>> = AHashMap::new();
+        if wallet_addresses.is_empty() {
+            return Ok(map);
+        }
+        for chat in WalletTracked::find()
+            .filter(wallet_tracked::Column::Network.eq(network))
+            .filter(wallet_tracked::Column::WalletAddress.is_in(wallet_addresses))
+            .all(db)
+            .await?
+        {
+            map.entry(chat.wallet_address.clone())
+                .or_default()
+                .push(chat);
+        }
+        Ok(map)
+    }
+}
+
+/// Inserts the `[[subscription]]` tables of the config, or updates the matching
+/// subscription of the same chat, wallet and network.
+pub async fn seed_subscriptions(db: &DatabaseConnection, config: &Config) -> Result<(), Err> {
+    for entry in config.subscriptions.iter() {
+        let network = entry
+            .network
+            .clone()
+            .unwrap_or_else(|| config.networks[0].name.clone());
+        let address = AccountAddress::from_str(&entry.wallet_address)?.to_string();
+        let track_type = TrackType::try_from_value(&entry.track_type.to_lowercase())
+            .map_err(|_| Err::InvalidTrackType)?;
+        let user_id = entry.user_id.unwrap_or(entry.chat_id);
+        if User::find_by_id(user_id).one(db).await?.is_none() {
+            user::ActiveModel {
+                user_id: Set(user_id),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+        let existing = WalletTracked::find()
+            .filter(wallet_tracked::Column::Network.eq(&network))
+            .filter(wallet_tracked::Column::ChatId.eq(entry.chat_id))
+            .filter(wallet_tracked::Column::WalletAddress.eq(&address))
+            .one(db)
+            .await?;
+        let mut model = match existing {
+            Some(existing) => existing.into_active_model(),
+            None => wallet_tracked::ActiveModel {
+                chat_id: Set(entry.chat_id),
+                user_id: Set(user_id),
+                wallet_address: Set(address),
+                network: Set(network),
+                ..Default::default()
+            },
+        };
+        model.nickname = Set(entry.nickname.clone());
+        model.track_type = Set(track_type);
+        model.minimum_value = Set(entry.minimum_value);
+        model.save(db).await?;
+    }
+    Ok(())
 }
 
 impl UniqueWallet {
-    pub async fn fetch_all(db: &DatabaseConnection) -> Result<Vec<Self>, Err> {
-        Ok(WalletTracked::find()
+    pub async fn fetch_all(
+        db: &DatabaseConnection,
+        network: Option<&str>,
+    ) -> Result<Vec<Self>, Err> {
+        let select = WalletTracked::find();
+        let select = match network {
+            Some(network) => select.filter(wallet_tracked::Column::Network.eq(network)),
+            None => select,
+        };
+        Ok(select
             .select_only()
             .column(wallet_tracked::Column::WalletAddress)
             .group_by(wallet_tracked::Column::WalletAddress)
@@ -106,8 +244,11 @@ impl UniqueWallet {
             .all(db)
             .await?)
     }
-    pub async fn fetch_all_hash_map(db: &DatabaseConnection) -> Result<AHashMap<String, ()>, Err> {
-        Ok(Self::fetch_all(db)
+    pub async fn fetch_all_hash_map(
+        db: &DatabaseConnection,
+        network: &str,
+    ) -> Result<AHashMap<String, ()>, Err> {
+        Ok(Self::fetch_all(db, Some(network))
             .await?
             .into_iter()
             .map(|s| (s.wallet_address, ()))
@@ -116,17 +257,19 @@ impl UniqueWallet {
 }
 #[derive(FromQueryResult, Debug, Clone)]
 pub struct LastBlock {
-    pub block: u64,
+    pub block: i64,
 }
 
 impl processed_block::Model {
     pub async fn get_last_block(
         db: &DatabaseConnection,
+        network: &str,
         modulo: Option<(u8, u8)>,
         place: Option<Place>,
         last: bool,
     ) -> Result<Option<u64>, Err> {
         let last_entry_select = ProcessedBlock::find()
+            .filter(processed_block::Column::Network.eq(network))
             .select_only()
             .column(processed_block::Column::Block);
         let last_entry_select = match last {
@@ -146,28 +289,94 @@ impl processed_block::Model {
         };
 
         if let Some(entry) = last_entry_select.into_model::<LastBlock>().one(db).await? {
-            return Ok(Some(entry.block));
+            return Ok(Some(u64::try_from(entry.block)?));
         }
         Ok(None)
     }
-    pub async fn get_oldest_block(db: &DatabaseConnection) -> Result<Option<u64>, Err> {
+    pub async fn get_oldest_block(
+        db: &DatabaseConnection,
+        network: &str,
+    ) -> Result<Option<u64>, Err> {
         let last_entry_select = ProcessedBlock::find()
+            .filter(processed_block::Column::Network.eq(network))
             .select_only()
             .order_by_asc(processed_block::Column::Block)
             .filter(processed_block::Column::Place.eq(Place::Head))
             .column(processed_block::Column::Block);
         if let Some(entry) = last_entry_select.into_model::<LastBlock>().one(db).await? {
-            return Ok(Some(entry.block));
+            return Ok(Some(u64::try_from(entry.block)?));
         }
         Ok(None)
     }
-    pub async fn get_block(db: &DatabaseConnection, block: u64) -> Result<Option<Self>, Err> {
-        Ok(ProcessedBlock::find()
-            .filter(processed_block::Column::Block.eq(block))
-            .one(db)
-            .await?)
+    pub async fn get_block(
+        db: &DatabaseConnection,
+        network: &str,
+        block: u64,
+    ) -> Result<Option<Self>, Err> {
+        Ok(
+            ProcessedBlock::find_by_id((network.to_string(), i64::try_from(block)?))
+                .one(db)
+                .await?,
+        )
     }
 }
+/// A received or sent threshold of a subscription.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threshold<'a> {
+    pub value: f64,
+    /// Asset type the value is counted in, USD when `None`.
+    pub token: Option<&'a str>,
+}
+
+impl Threshold<'_> {
+    /// Whether `changes`, all in the threshold's direction, add up to at least the
+    /// value. Nothing moved never meets it, even at 0.
+    pub fn met(&self, changes: &[&BalanceChange], token_map: &TokenMap) -> bool {
+        let total = match self.token {
+            None => changes
+                .iter()
+                .map(|change| change.to_usd_change(token_map).unwrap_or(0.0).abs())
+                .sum::<f64>(),
+            Some(token) => changes
+                .iter()
+                .filter(|change| change.to_token_address() == token)
+                .map(|change| change.to_ui_f64().abs())
+                .sum::<f64>(),
+        };
+        let moved = changes.iter().any(|change| match self.token {
+            None => true,
+            Some(token) => change.to_token_address() == token,
+        });
+        moved && total >= self.value
+    }
+}
+
+impl chat_setting::Model {
+    /// Applies `change` to the settings of `chat_id`, created with the column defaults
+    /// when missing.
+    pub async fn upsert(
+        db: &DatabaseConnection,
+        chat_id: i64,
+        change: impl FnOnce(&mut chat_setting::ActiveModel),
+    ) -> Result<Self, Err> {
+        match ChatSetting::find_by_id(chat_id).one(db).await? {
+            Some(setting) => {
+                let mut model = setting.into_active_model();
+                change(&mut model);
+                Ok(model.update(db).await?)
+            }
+            None => {
+                let mut model = chat_setting::ActiveModel {
+                    chat_id: Set(chat_id),
+                    ..Default::default()
+                };
+                change(&mut model);
+                Ok(model.insert(db).await?)
+            }
+        }
+    }
+}
+
 pub type TokenMap = ahash::AHashMap<String, token_info::Model>;
 impl token_info::Model {
     pub async fn get_token_hashmap(db: &DatabaseConnection) -> Result<TokenMap, Err> {
@@ -178,4 +387,36 @@ impl token_info::Model {
             .collect();
         Ok(map)
     }
+    /// Flags `tokens` as spam, inserting the unknown ones unpriced with their symbol
+    /// and decimals. Tokens an operator vetted or skipped, or that got a price, are
+    /// left alone. Returns the rows flagged.
+    pub async fn flag_spam(
+        db: &DatabaseConnection,
+        tokens: &AHashMap<String, spam::SpamToken>,
+    ) -> Result<Vec<Self>, Err> {
+        let mut flagged = vec![];
+        for (mint, metadata) in tokens.iter() {
+            let row = match TokenInfo::find_by_id(mint.clone()).one(db).await? {
+                Some(row) if row.is_skipped != spam::UNFLAGGED || row.value > 0.0 => continue,
+                Some(row) => {
+                    let mut update = row.into_active_model();
+                    update.is_skipped = Set(spam::FLAGGED);
+                    update.update(db).await?
+                }
+                None => {
+                    token_info::ActiveModel {
+                        mint: Set(mint.clone()),
+                        name: Set(metadata.symbol.clone()),
+                        decimal: Set(i16::from(metadata.decimals)),
+                        is_skipped: Set(spam::FLAGGED),
+                        ..Default::default()
+                    }
+                    .insert(db)
+                    .await?
+                }
+            };
+            flagged.push(row);
+        }
+        Ok(flagged)
+    }
 }
@@ -7,15 +7,20 @@ use std::sync::Arc;
 
 use std::time::Duration;
 
+use tokio::sync::RwLock;
 use tokio::task;
 
+use crate::db::TokenMap;
 use crate::entities::processed_block;
 use crate::entities::sea_orm_active_enums::Place;
+use crate::entities::token_info;
+use crate::metrics::{set_gauge, update_head_lag, LAST_PROCESSED_BLOCK, TOKEN_MAP_SIZE};
 use crate::{entities::prelude::*, error::Err};
 
-pub async fn db_loop(abort: Abortable<Pending<()>>, db: Arc<DatabaseConnection>) {
+pub async fn db_loop(abort: Abortable<Pending<()>>, db: Arc<DatabaseConnection>, network: String) {
     loop {
         let db_new = db.clone();
+        let network_new = network.clone();
         task::spawn_blocking(|| async move {
             // match check_update_cleanup(&db_new).await {
             //     Ok(_) => {}
@@ -23,7 +28,7 @@ pub async fn db_loop(abort: Abortable<Pending<()>>, db: Arc<DatabaseConnection>)
             //         error!("{:?}", e)
             //     }
             // };
-            match check_slot(&db_new).await {
+            match check_slot(&db_new, &network_new).await {
                 Ok(processed) => {
                     let time: u64 = 500_usize
                         .checked_sub(processed * 50)
@@ -33,7 +38,7 @@ pub async fn db_loop(abort: Abortable<Pending<()>>, db: Arc<DatabaseConnection>)
                     tokio::time::sleep(Duration::from_millis(time)).await;
                 }
                 Err(e) => {
-                    error!("{:?}", e);
+                    error!("{} {:?}", network_new, e);
                     tokio::time::sleep(Duration::from_millis(10000)).await;
                 }
             }
@@ -48,9 +53,34 @@ pub async fn db_loop(abort: Abortable<Pending<()>>, db: Arc<DatabaseConnection>)
     }
 }
 
-async fn check_slot(db: &DatabaseConnection) -> Result<usize, Err> {
+/// Reloads `token_info` into `token` every `every`, so prices and skip flags written
+/// by other processes take effect.
+pub async fn token_refresh_loop(
+    abort: Abortable<Pending<()>>,
+    db: Arc<DatabaseConnection>,
+    token: Arc<RwLock<TokenMap>>,
+    every: Duration,
+) {
+    loop {
+        tokio::time::sleep(every).await;
+        if abort.is_aborted() {
+            info!("abort");
+            break;
+        }
+        match token_info::Model::get_token_hashmap(db.as_ref()).await {
+            Ok(fresh) => {
+                set_gauge(&TOKEN_MAP_SIZE, fresh.len());
+                *token.write().await = fresh;
+            }
+            Err(e) => error!("token refresh {:?}", e),
+        }
+    }
+}
+
+async fn check_slot(db: &DatabaseConnection, network: &str) -> Result<usize, Err> {
     let first_bottom = processed_block::Model::get_last_block(
         db,
+        network,
         None,
         Some(crate::entities::sea_orm_active_enums::Place::Bottom),
         true,
@@ -61,6 +91,7 @@ async fn check_slot(db: &DatabaseConnection) -> Result<usize, Err> {
         None => {
             let last_head = processed_block::Model::get_last_block(
                 db,
+                network,
                 None,
                 Some(crate::entities::sea_orm_active_enums::Place::Head),
                 true,
@@ -74,6 +105,7 @@ async fn check_slot(db: &DatabaseConnection) -> Result<usize, Err> {
         }
     };
     let head_slots = ProcessedBlock::find()
+        .filter(processed_block::Column::Network.eq(network))
         .filter(processed_block::Column::Block.gte(start_slot))
         .order_by_asc(processed_block::Column::Block)
         .all(db)
@@ -88,21 +120,25 @@ async fn check_slot(db: &DatabaseConnection) -> Result<usize, Err> {
                 return Err(Err::TooMuchSlotEntryInDb);
             }
         };
-        if slot.block != (start_slot + index_u64) {
+        let block = u64::try_from(slot.block)?;
+        if block != (start_slot + index_u64) {
             bottom = index - 1;
             end -= 1;
             break;
         } else {
-            end = slot.block;
+            end = block;
         }
     }
     if end != start_slot {
         ProcessedBlock::update_many()
             .col_expr(processed_block::Column::Place, Expr::value(Place::Old))
+            .filter(processed_block::Column::Network.eq(network))
             .filter(processed_block::Column::Block.gte(start_slot))
             .filter(processed_block::Column::Block.lte(end))
             .exec(db)
             .await?;
+        set_gauge(&LAST_PROCESSED_BLOCK.with_label_values(&[network]), end);
+        update_head_lag(network);
     }
 
     let mut update = processed_block::ActiveModel::from(head_slots[bottom].clone());
@@ -0,0 +1,343 @@
+//! Daily and weekly digests. Every balance change of a subscription in digest mode,
+//! whether or not it would alert, is stored in `digest_entry` and summarized at the
+//! digest time of its chat.
+
+use ahash::AHashMap;
+use chrono::{DateTime, Datelike, Days, FixedOffset, NaiveTime, TimeZone, Utc, Weekday};
+use futures::{future::Pending, stream::Abortable};
+use log::{error, info};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::entities::sea_orm_active_enums::DigestMode;
+use crate::entities::{chat_setting, digest_entry, prelude::*, wallet_tracked};
+use crate::error::Err;
+use crate::notifier::Notifier;
+use crate::process_new_block::DigestActivity;
+use crate::quiet::{format_clock, format_offset, parse_clock, parse_offset};
+
+const SEND_EVERY: Duration = Duration::from_secs(60);
+/// Net flows listed before the rest are only counted.
+const MAX_FLOWS: usize = 20;
+const MAX_LARGEST: usize = 3;
+
+/// When a chat gets its digests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    /// Local minutes after midnight.
+    pub time: u16,
+    /// Day of the weekly digests.
+    pub weekday: Weekday,
+    /// Minutes local time is ahead of UTC.
+    pub utc_offset: i16,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            time: 9 * 60,
+            weekday: Weekday::Mon,
+            utc_offset: 0,
+        }
+    }
+}
+
+impl Schedule {
+    /// Latest time at or before `now` a digest of `mode` was due, `None` when off.
+    pub fn last_due(&self, mode: &DigestMode, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if *mode == DigestMode::Off {
+            return None;
+        }
+        let offset = FixedOffset::east_opt(i32::from(self.utc_offset) * 60)?;
+        let local = now.with_timezone(&offset).naive_local();
+        let time =
+            NaiveTime::from_hms_opt(u32::from(self.time / 60), u32::from(self.time % 60), 0)?;
+        let mut due = local.date().and_time(time);
+        if due > local {
+            due = due.checked_sub_days(Days::new(1))?;
+        }
+        if *mode == DigestMode::Weekly {
+            let back = (7 + due.weekday().num_days_from_monday()
+                - self.weekday.num_days_from_monday())
+                % 7;
+            due = due.checked_sub_days(Days::new(u64::from(back)))?;
+        }
+        offset
+            .from_local_datetime(&due)
+            .single()
+            .map(|due| due.with_timezone(&Utc))
+    }
+    /// Parses `18:30`, optionally followed by the weekday of weekly digests and the UTC
+    /// offset, keeping those of `self` when left out.
+    pub fn parse(&self, text: &str) -> Result<Self, String> {
+        let mut words = text.split_whitespace();
+        let time = words.next().ok_or("time missing, ex: 18:30")?;
+        let mut schedule = Schedule {
+            time: parse_clock(time)?,
+            ..*self
+        };
+        for word in words {
+            match Weekday::from_str(word) {
+                Ok(weekday) => schedule.weekday = weekday,
+                Err(_) => schedule.utc_offset = parse_offset(word)?,
+            }
+        }
+        Ok(schedule)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, weekly digests on {}",
+            format_clock(self.time),
+            format_offset(self.utc_offset),
+            self.weekday
+        )
+    }
+}
+
+impl chat_setting::Model {
+    pub fn digest_schedule(&self) -> Schedule {
+        let default = Schedule::default();
+        Schedule {
+            time: u16::try_from(self.digest_time).unwrap_or(default.time),
+            weekday: u8::try_from(self.digest_weekday)
+                .ok()
+                .and_then(|day| Weekday::try_from(day).ok())
+                .unwrap_or(default.weekday),
+            utc_offset: self.utc_offset,
+        }
+    }
+}
+
+/// The digest schedule of `chat_id`, the default when it has no settings.
+pub async fn schedule(db: &DatabaseConnection, chat_id: i64) -> Result<Schedule, Err> {
+    Ok(ChatSetting::find_by_id(chat_id)
+        .one(db)
+        .await?
+        .map(|setting| setting.digest_schedule())
+        .unwrap_or_default())
+}
+
+pub async fn set_schedule(
+    db: &DatabaseConnection,
+    chat_id: i64,
+    schedule: Schedule,
+) -> Result<(), Err> {
+    chat_setting::Model::upsert(db, chat_id, |model| {
+        model.digest_time = Set(i16::try_from(schedule.time).unwrap_or(9 * 60));
+        model.digest_weekday =
+            Set(i16::try_from(schedule.weekday.num_days_from_monday()).unwrap_or(0));
+        model.utc_offset = Set(schedule.utc_offset);
+    })
+    .await?;
+    Ok(())
+}
+
+/// Stores the changes of `activity` for the next digest of its subscription.
+pub async fn record(
+    db: &DatabaseConnection,
+    activity: &DigestActivity,
+    now: DateTime<Utc>,
+) -> Result<(), Err> {
+    let version = i64::try_from(activity.version)?;
+    let entries: Vec<digest_entry::ActiveModel> = activity
+        .changes
+        .iter()
+        .map(|change| digest_entry::ActiveModel {
+            wallet_id: Set(activity.subscription.wallet_id),
+            version: Set(version),
+            asset_type: Set(change.asset_type.clone()),
+            symbol: Set(change.symbol.clone()),
+            amount: Set(change.amount),
+            usd: Set(change.usd),
+            create_time: Set(now),
+            ..Default::default()
+        })
+        .collect();
+    if entries.is_empty() {
+        return Ok(());
+    }
+    DigestEntry::insert_many(entries).exec(db).await?;
+    Ok(())
+}
+
+/// Sends the digest of every subscription whose digest time passed since its last one,
+/// skipping those without activity. Returns the number of digests sent.
+pub async fn send_due(
+    db: &DatabaseConnection,
+    notifier: &Notifier,
+    now: DateTime<Utc>,
+) -> Result<usize, Err> {
+    let subscriptions = WalletTracked::find()
+        .filter(wallet_tracked::Column::Digest.ne(DigestMode::Off))
+        .all(db)
+        .await?;
+    if subscriptions.is_empty() {
+        return Ok(0);
+    }
+    let mut chats: Vec<i64> = subscriptions.iter().map(|s| s.chat_id).collect();
+    chats.sort();
+    chats.dedup();
+    let schedules: AHashMap<i64, Schedule> = ChatSetting::find()
+        .filter(chat_setting::Column::ChatId.is_in(chats))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|setting| (setting.chat_id, setting.digest_schedule()))
+        .collect();
+    let mut sent = 0;
+    for subscription in subscriptions.into_iter() {
+        let schedule = schedules
+            .get(&subscription.chat_id)
+            .copied()
+            .unwrap_or_default();
+        let due = match schedule.last_due(&subscription.digest, now) {
+            Some(due) => due,
+            None => continue,
+        };
+        if subscription.last_digest.map_or(false, |last| last >= due) {
+            continue;
+        }
+        let entries = DigestEntry::find()
+            .filter(digest_entry::Column::WalletId.eq(subscription.wallet_id))
+            .filter(digest_entry::Column::CreateTime.lte(now))
+            .order_by_asc(digest_entry::Column::Id)
+            .all(db)
+            .await?;
+        if !entries.is_empty() {
+            // Entries and the digest time are kept for the next pass when sending fails.
+            if let Err(err) = notifier
+                .summary(subscription.chat_id, digest_text(&subscription, &entries))
+                .await
+            {
+                error!(
+                    "digest of subscription {} failed: {:?}",
+                    subscription.wallet_id, err
+                );
+                continue;
+            }
+            DigestEntry::delete_many()
+                .filter(digest_entry::Column::Id.is_in(entries.iter().map(|entry| entry.id)))
+                .exec(db)
+                .await?;
+            sent += 1;
+        }
+        let mut update: wallet_tracked::ActiveModel = subscription.into();
+        update.last_digest = Set(Some(now));
+        update.update(db).await?;
+    }
+    Ok(sent)
+}
+
+fn usd(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!(" (${value:.2})"),
+        None => String::new(),
+    }
+}
+
+fn digest_text(subscription: &wallet_tracked::Model, entries: &[digest_entry::Model]) -> String {
+    let title = match subscription.digest {
+        DigestMode::Weekly => "Weekly",
+        _ => "Daily",
+    };
+    let mut versions: Vec<i64> = entries.iter().map(|entry| entry.version).collect();
+    versions.sort();
+    versions.dedup();
+    let received = entries
+        .iter()
+        .filter_map(|entry| entry.usd)
+        .filter(|usd| *usd > 0.0)
+        .sum::<f64>();
+    let sent = entries
+        .iter()
+        .filter_map(|entry| entry.usd)
+        .filter(|usd| *usd < 0.0)
+        .map(f64::abs)
+        .sum::<f64>();
+    let mut flows: Vec<(&str, &str, f64, Option<f64>)> = vec![];
+    for entry in entries.iter() {
+        match flows
+            .iter_mut()
+            .find(|(asset_type, ..)| *asset_type == entry.asset_type)
+        {
+            Some(flow) => {
+                flow.2 += entry.amount;
+                flow.3 = match (flow.3, entry.usd) {
+                    (Some(total), Some(usd)) => Some(total + usd),
+                    (total, usd) => total.or(usd),
+                };
+            }
+            None => flows.push((
+                entry.asset_type.as_str(),
+                entry.symbol.as_str(),
+                entry.amount,
+                entry.usd,
+            )),
+        }
+    }
+    let magnitude = |usd: Option<f64>| usd.map_or(0.0, f64::abs);
+    flows.sort_by(|a, b| magnitude(b.3).total_cmp(&magnitude(a.3)));
+    let mut largest: Vec<&digest_entry::Model> = entries.iter().collect();
+    largest.sort_by(|a, b| magnitude(b.usd).total_cmp(&magnitude(a.usd)));
+
+    let mut text = format!(
+        "{title} digest for {} on {}\n{} transactions, received ${received:.2}, sent ${sent:.2}\nNet flow:\n",
+        subscription.clone().display_name(),
+        subscription.network,
+        versions.len()
+    );
+    for (_, symbol, amount, value) in flows.iter().take(MAX_FLOWS) {
+        text.push_str(&format!("{symbol} {amount:+.4}{}\n", usd(*value)));
+    }
+    if flows.len() > MAX_FLOWS {
+        text.push_str(&format!("...and {} more tokens\n", flows.len() - MAX_FLOWS));
+    }
+    text.push_str("Largest transfers:\n");
+    for entry in largest.into_iter().take(MAX_LARGEST) {
+        let direction = match entry.amount < 0.0 {
+            true => "Sent",
+            false => "Received",
+        };
+        text.push_str(&format!(
+            "{direction} {:.4} {}{} at version {}\n",
+            entry.amount.abs(),
+            entry.symbol,
+            usd(entry.usd.map(f64::abs)),
+            entry.version
+        ));
+    }
+    text
+}
+
+/// Sends the digests that came due, once a minute. Not run by a shadow, the entries
+/// and `last_digest` belong to production.
+pub async fn digest_loop(
+    abort: Abortable<Pending<()>>,
+    db: Arc<DatabaseConnection>,
+    notifier: Notifier,
+) {
+    if notifier.is_shadow() {
+        info!("shadow mode, digests are left to production");
+        return;
+    }
+    loop {
+        tokio::time::sleep(SEND_EVERY).await;
+        if abort.is_aborted() {
+            info!("abort");
+            break;
+        }
+        if let Err(e) = send_due(db.as_ref(), &notifier, Utc::now()).await {
+            error!("digest {:?}", e);
+        }
+    }
+}
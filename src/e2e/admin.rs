@@ -0,0 +1,34 @@
+use super::*;
+use crate::admin::{reprocess, skip, status};
+
+#[tokio::test]
+async fn skip_and_reprocess_move_the_bottom_pointer() {
+    let harness = Harness::start().await;
+    let db = harness.db.as_ref();
+    let network = harness.network.name.as_str();
+    harness.seed_bottom(99).await;
+
+    assert_eq!(skip(db, network, 100, 104).await.expect("skip"), 5);
+    let skipped = status(db, network).await.expect("status");
+    assert_eq!(skipped.bottom, Some(99));
+    assert_eq!(skipped.highest, Some(104));
+    assert!(skipped.gaps.is_empty(), "{:?}", skipped.gaps);
+    assert!(skipped.counts.contains(&(Status::Skipped, 5)));
+
+    assert_eq!(
+        reprocess(db, network, 102, 103).await.expect("reprocess"),
+        2
+    );
+    assert_eq!(status(db, network).await.unwrap().gaps, vec![(102, 103)]);
+
+    harness.seed_bottom(104).await;
+    reprocess(db, network, 101, 101).await.expect("reprocess");
+    let moved = status(db, network).await.expect("status");
+    assert_eq!(moved.bottom, Some(100));
+    assert_eq!(moved.gaps, vec![(101, 103)]);
+
+    assert!(matches!(
+        reprocess(db, network, 0, 1).await,
+        Err(Err::InvalidRange)
+    ));
+}
@@ -0,0 +1,176 @@
+use super::*;
+use crate::api::{subscription_router, ApiState};
+use crate::network::Networks;
+use fake::serve;
+use reqwest::{Method, StatusCode};
+
+const KEY: &str = "e2e-api-key";
+const ALICE: &str = "0x00000000000000000000000000000000000000000000000000000000000a11ce";
+
+/// Serves the subscription api of `harness` and returns its base url.
+async fn start_api(harness: &Harness) -> reqwest::Url {
+    let networks = Networks::from(vec![harness.network.clone()]);
+    serve(subscription_router(ApiState::new(
+        harness.db.clone(),
+        networks,
+        KEY,
+    )))
+    .await
+}
+
+async fn call(
+    url: &reqwest::Url,
+    method: Method,
+    path: &str,
+    key: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = rqwClient::new().request(method, url.join(path).unwrap());
+    if let Some(key) = key {
+        request = request.header("x-api-key", key);
+    }
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request.send().await.expect("api request");
+    let status = response.status();
+    let body = response.json().await.unwrap_or(Value::Null);
+    (status, body)
+}
+
+#[tokio::test]
+async fn requests_without_the_api_key_are_refused() {
+    let harness = Harness::start().await;
+    let url = start_api(&harness).await;
+
+    for key in [
+        None,
+        Some(""),
+        Some("e2e-api-kez"),
+        Some("e2e-api-key-and-more"),
+    ] {
+        let (status, body) = call(&url, Method::GET, "subscriptions", key, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{key:?}");
+        assert_eq!(body["error"], "Unauthorized");
+    }
+    let (status, _) = call(&url, Method::GET, "subscriptions", Some(KEY), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn subscriptions_are_created_read_updated_and_deleted() {
+    let harness = Harness::start().await;
+    let url = start_api(&harness).await;
+    let key = Some(KEY);
+    let create = json!({
+        "chat_id": 7,
+        "user_id": 70,
+        "wallet_address": ALICE,
+        "nickname": "alice",
+        "track_type": "receive",
+        "minimum_value": 5.0,
+    });
+
+    let (status, created) = call(
+        &url,
+        Method::POST,
+        "subscriptions",
+        key,
+        Some(create.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{created}");
+    assert_eq!(created["chat_id"], 7);
+    assert_eq!(created["nickname"], "alice");
+    assert_eq!(created["track_type"], "receive");
+    assert_eq!(created["network"], DEFAULT_NETWORK);
+    let id = created["wallet_id"].as_i64().expect("wallet_id");
+    let (status, _) = call(&url, Method::POST, "subscriptions", key, Some(create)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = call(
+        &url,
+        Method::POST,
+        "subscriptions",
+        key,
+        Some(json!({ "chat_id": 7, "user_id": 70, "wallet_address": "nope" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = call(
+        &url,
+        Method::POST,
+        "subscriptions",
+        key,
+        Some(
+            json!({ "chat_id": 8, "user_id": 80, "wallet_address": ALICE, "minimum_value": -1.0 }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "InvalidMinimumValue");
+
+    let (status, listed) = call(&url, Method::GET, "subscriptions?chat_id=7", key, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().map(Vec::len), Some(1), "{listed}");
+    let (_, listed) = call(&url, Method::GET, "subscriptions?chat_id=8", key, None).await;
+    assert_eq!(listed.as_array().map(Vec::len), Some(0), "{listed}");
+    let (status, fetched) =
+        call(&url, Method::GET, &format!("subscriptions/{id}"), key, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["wallet_address"], created["wallet_address"]);
+
+    let path = format!("subscriptions/{id}");
+    let (status, updated) = call(
+        &url,
+        Method::PATCH,
+        &path,
+        key,
+        Some(json!({ "nickname": "", "track_type": "SENT" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{updated}");
+    assert_eq!(updated["nickname"], Value::Null);
+    assert_eq!(updated["track_type"], "sent");
+    assert_eq!(updated["minimum_value"], 5.0);
+    let (status, _) = call(
+        &url,
+        Method::PATCH,
+        &path,
+        key,
+        Some(json!({ "track_type": "sideways" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = call(
+        &url,
+        Method::PATCH,
+        &path,
+        key,
+        Some(json!({ "minimum_value": -0.5 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, fetched) = call(&url, Method::GET, &path, key, None).await;
+    assert_eq!(fetched["minimum_value"], 5.0);
+
+    let (status, _) = call(&url, Method::DELETE, &path, key, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = call(&url, Method::GET, &path, key, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = call(&url, Method::DELETE, &path, key, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn status_counts_subscriptions_per_network() {
+    let harness = Harness::start().await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let url = start_api(&harness).await;
+
+    let (status, body) = call(&url, Method::GET, "status", Some(KEY), None).await;
+
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["subscriptions"], 1);
+    assert_eq!(body["networks"][0]["network"], DEFAULT_NETWORK);
+    assert_eq!(body["networks"][0]["unique_wallets"], 1);
+}
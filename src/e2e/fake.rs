@@ -0,0 +1,391 @@
+use aptos_sdk::bcs;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::rest_client::aptos_api_types::BcsBlock;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use crate::tx::FUNGIBLE_ASSET_PROCESSOR;
+
+const CHAIN_ID: u8 = 4;
+
+/// Serves `router` on a free local port and returns its base url.
+pub async fn serve(router: Router) -> Url {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("fake server bind");
+    let addr = listener.local_addr().expect("fake server addr");
+    let server = axum::Server::from_tcp(listener)
+        .expect("fake server")
+        .serve(router.into_make_service());
+    tokio::spawn(server);
+    Url::parse(&format!("http://{addr}/")).expect("fake server url")
+}
+
+#[derive(Debug, Default)]
+pub struct FullnodeState {
+    pub height: u64,
+    pub version: u64,
+    /// Height to `(first_version, last_version)`.
+    pub blocks: BTreeMap<u64, (u64, u64)>,
+}
+
+/// Aptos fullnode REST API answering ledger info and blocks by height in BCS, the
+/// encoding `aptos_sdk` asks for.
+#[derive(Clone)]
+pub struct FakeFullnode {
+    pub url: Url,
+    pub state: Arc<Mutex<FullnodeState>>,
+}
+
+/// Mirror of `IndexResponseBcs`, whose `RoleType` lives in a crate we don't depend on.
+#[derive(Serialize)]
+struct IndexBcs {
+    chain_id: u8,
+    epoch: u64,
+    ledger_version: u64,
+    oldest_ledger_version: u64,
+    ledger_timestamp: u64,
+    node_role: NodeRole,
+    oldest_block_height: u64,
+    block_height: u64,
+    git_hash: Option<String>,
+}
+
+#[derive(Serialize)]
+#[allow(dead_code)]
+enum NodeRole {
+    Validator,
+    FullNode,
+}
+
+impl FakeFullnode {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(FullnodeState::default()));
+        let router = Router::new()
+            .route("/v1", get(index))
+            .route("/v1/", get(index))
+            .route("/v1/blocks/by_height/:height", get(block_by_height))
+            .with_state(state.clone());
+        FakeFullnode {
+            url: serve(router).await,
+            state,
+        }
+    }
+    /// Adds a block and moves the head to it when it is newer.
+    pub fn add_block(&self, height: u64, first_version: u64, last_version: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.blocks.insert(height, (first_version, last_version));
+        state.height = state.height.max(height);
+        state.version = state.version.max(last_version);
+    }
+}
+
+fn ledger_headers(state: &FullnodeState) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in [
+        ("x-aptos-chain-id", u64::from(CHAIN_ID)),
+        ("x-aptos-epoch", 1),
+        ("x-aptos-ledger-version", state.version),
+        ("x-aptos-ledger-oldest-version", 0),
+        ("x-aptos-ledger-timestampusec", state.height * 1_000_000),
+        ("x-aptos-block-height", state.height),
+        ("x-aptos-oldest-block-height", 0),
+    ] {
+        headers.insert(name, HeaderValue::from(value));
+    }
+    headers.insert(
+        "content-type",
+        HeaderValue::from_static("application/x-bcs"),
+    );
+    headers
+}
+
+async fn index(State(state): State<Arc<Mutex<FullnodeState>>>) -> Response {
+    let state = state.lock().unwrap();
+    let body = bcs::to_bytes(&IndexBcs {
+        chain_id: CHAIN_ID,
+        epoch: 1,
+        ledger_version: state.version,
+        oldest_ledger_version: 0,
+        ledger_timestamp: state.height * 1_000_000,
+        node_role: NodeRole::FullNode,
+        oldest_block_height: 0,
+        block_height: state.height,
+        git_hash: None,
+    })
+    .expect("bcs index");
+    (ledger_headers(&state), body).into_response()
+}
+
+async fn block_by_height(
+    State(state): State<Arc<Mutex<FullnodeState>>>,
+    Path(height): Path<u64>,
+) -> Response {
+    let state = state.lock().unwrap();
+    match state.blocks.get(&height) {
+        Some((first_version, last_version)) => {
+            let body = bcs::to_bytes(&BcsBlock {
+                block_height: height,
+                block_hash: HashValue::sha3_256_of(&height.to_le_bytes()),
+                block_timestamp: height * 1_000_000,
+                first_version: *first_version,
+                last_version: *last_version,
+                transactions: None,
+            })
+            .expect("bcs block");
+            (ledger_headers(&state), body).into_response()
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "message": format!("block {height} not found"),
+                "error_code": "block_not_found",
+                "vm_error_code": null,
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IndexerState {
+    pub activities: Vec<Value>,
+    pub last_success_version: u64,
+    /// `current_fungible_asset_balances` rows.
+    pub balances: Vec<Value>,
+    /// `current_coin_balances` rows.
+    pub coin_balances: Vec<Value>,
+}
+
+/// Hasura endpoint serving canned `fungible_asset_activities`, balances and processor
+/// status.
+#[derive(Clone)]
+pub struct FakeIndexer {
+    pub url: Url,
+    pub state: Arc<Mutex<IndexerState>>,
+}
+
+impl FakeIndexer {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(IndexerState::default()));
+        let router = Router::new()
+            .route("/v1/graphql", post(graphql))
+            .with_state(state.clone());
+        let url = serve(router).await.join("v1/graphql").expect("indexer url");
+        FakeIndexer { url, state }
+    }
+}
+
+async fn graphql(
+    State(state): State<Arc<Mutex<IndexerState>>>,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    let state = state.lock().unwrap();
+    let variables = &body["variables"];
+    let in_range = |activity: &&Value| {
+        let version = activity["transaction_version"].as_i64().unwrap_or(-1);
+        match body["operationName"].as_str() {
+            Some("TransactionQuery") => variables["_eq"].as_i64() == Some(version),
+            Some("WalletActivityQuery") => {
+                let timestamp = activity["transaction_timestamp"]
+                    .as_str()
+                    .unwrap_or_default();
+                activity["owner_address"] == variables["owner"]
+                    && activity["is_transaction_success"] == true
+                    && variables["from"]
+                        .as_str()
+                        .map_or(true, |from| timestamp >= from)
+                    && variables["to"].as_str().map_or(true, |to| timestamp < to)
+                    && variables["after"]
+                        .as_i64()
+                        .map_or(true, |after| version > after)
+            }
+            Some("VersionsActivityQuery") => {
+                activity["is_transaction_success"] == true
+                    && variables["versions"]
+                        .as_array()
+                        .map_or(false, |versions| versions.contains(&json!(version)))
+            }
+            _ => {
+                variables["_gte"]
+                    .as_i64()
+                    .map_or(true, |gte| version >= gte)
+                    && variables["_lte"]
+                        .as_i64()
+                        .map_or(true, |lte| version <= lte)
+            }
+        }
+    };
+    // `WalletActivityQuery` pages like the indexer, the others return everything.
+    let limit = match body["operationName"].as_str() {
+        Some("WalletActivityQuery") => 500,
+        _ => usize::MAX,
+    };
+    Json(match body["operationName"].as_str() {
+        Some("IndexerStatusQuery") => json!({
+            "data": {
+                "processor_status": [{
+                    "processor": FUNGIBLE_ASSET_PROCESSOR,
+                    "last_success_version": state.last_success_version,
+                    "last_updated": "2024-10-19T00:00:00",
+                }]
+            }
+        }),
+        Some("TransactionsQuery")
+        | Some("TransactionQuery")
+        | Some("WalletActivityQuery")
+        | Some("VersionsActivityQuery") => json!({
+            "data": {
+                "fungible_asset_activities": state
+                    .activities
+                    .iter()
+                    .filter(in_range)
+                    .take(limit)
+                    .cloned()
+                    .collect::<Vec<Value>>(),
+            }
+        }),
+        Some("BalancesQuery") => {
+            let owned = |rows: &[Value]| {
+                rows.iter()
+                    .filter(|row| row["owner_address"] == variables["owner"])
+                    .cloned()
+                    .collect::<Vec<Value>>()
+            };
+            json!({
+                "data": {
+                    "current_fungible_asset_balances": owned(&state.balances),
+                    "current_coin_balances": owned(&state.coin_balances),
+                }
+            })
+        }
+        operation => json!({
+            "errors": [{ "message": format!("unknown operation {:?}", operation) }]
+        }),
+    })
+}
+
+/// A message the bot sent, with the inline keyboard flattened.
+#[derive(Debug, Clone)]
+pub struct SentMessage {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub text: String,
+    pub buttons: Vec<Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct TelegramState {
+    pub sent: Vec<SentMessage>,
+    /// `editMessageText` calls, with the new text and keyboard.
+    pub edited: Vec<SentMessage>,
+    /// Chats answering `sendMessage` with "bot was blocked by the user".
+    pub blocked: HashSet<i64>,
+}
+
+/// Telegram Bot API recording `sendMessage` and `editMessageText` calls, pointed at with `Bot::set_api_url`.
+#[derive(Clone)]
+pub struct FakeTelegram {
+    pub url: Url,
+    pub state: Arc<Mutex<TelegramState>>,
+}
+
+impl FakeTelegram {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(TelegramState::default()));
+        let router = Router::new()
+            .route("/:bot/:method", post(bot_method))
+            .with_state(state.clone());
+        FakeTelegram {
+            url: serve(router).await,
+            state,
+        }
+    }
+}
+
+async fn bot_method(
+    State(state): State<Arc<Mutex<TelegramState>>>,
+    Path((_bot, method)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let edit = method.eq_ignore_ascii_case("editMessageText");
+    if !edit && !method.eq_ignore_ascii_case("sendMessage") {
+        return Json(json!({ "ok": true, "result": true })).into_response();
+    }
+    let mut state = state.lock().unwrap();
+    let chat_id = body["chat_id"].as_i64().unwrap_or_default();
+    if state.blocked.contains(&chat_id) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "ok": false,
+                "error_code": 403,
+                "description": "Forbidden: bot was blocked by the user",
+            })),
+        )
+            .into_response();
+    }
+    let text = body["text"].as_str().unwrap_or_default().to_string();
+    let buttons = body["reply_markup"]["inline_keyboard"]
+        .as_array()
+        .map(|rows| {
+            rows.iter()
+                .filter_map(Value::as_array)
+                .flatten()
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let message = SentMessage {
+        chat_id,
+        message_id: match edit {
+            true => body["message_id"].as_i64().unwrap_or_default(),
+            false => state.sent.len() as i64 + 1,
+        },
+        text: text.clone(),
+        buttons,
+    };
+    let message_id = message.message_id;
+    match edit {
+        true => state.edited.push(message),
+        false => state.sent.push(message),
+    }
+    Json(json!({
+        "ok": true,
+        "result": {
+            "message_id": message_id,
+            "date": 0,
+            "chat": { "id": chat_id, "type": "private", "first_name": "e2e" },
+            "text": text,
+        }
+    }))
+    .into_response()
+}
+
+/// Webhook receiver recording every JSON body it is posted.
+#[derive(Clone)]
+pub struct FakeWebhook {
+    pub url: Url,
+    pub received: Arc<Mutex<Vec<Value>>>,
+}
+
+impl FakeWebhook {
+    pub async fn start() -> Self {
+        let received = Arc::new(Mutex::new(vec![]));
+        let router = Router::new()
+            .route("/hook", post(hook))
+            .with_state(received.clone());
+        let url = serve(router).await.join("hook").expect("webhook url");
+        FakeWebhook { url, received }
+    }
+}
+
+async fn hook(State(received): State<Arc<Mutex<Vec<Value>>>>, Json(body): Json<Value>) {
+    received.lock().unwrap().push(body);
+}
@@ -0,0 +1,372 @@
+//! End-to-end harness: the block pipeline against in-process fakes of the Aptos
+//! fullnode, the indexer and the Telegram Bot API, on a throwaway SQLite database.
+
+use ahash::AHashSet;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use reqwest::Client as rqwClient;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectOptions, Database, DatabaseConnection,
+    EntityTrait, QueryFilter,
+};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use teloxide::Bot;
+use tokio::sync::{mpsc, RwLock};
+
+use crate::config::DEFAULT_NETWORK;
+use crate::config::{IndexerConfig, IndexerEndpointConfig, NetworkConfig, RpcConfig, SpamConfig};
+use crate::db::{TokenMap, UniqueWallet};
+use crate::entities::sea_orm_active_enums::{Place, Status, TrackType};
+use crate::entities::{prelude::*, processed_block, token_info, wallet_tracked};
+use crate::error::Err;
+use crate::migration;
+use crate::network::Network;
+use crate::notifier::Notifier;
+use crate::process_new_block::handle_new_block;
+use crate::replay::Recorder;
+
+mod admin;
+mod api;
+mod fake;
+mod pipeline;
+
+pub use fake::{FakeFullnode, FakeIndexer, FakeTelegram, FakeWebhook, SentMessage};
+
+pub const APT: &str = "0x1::aptos_coin::AptosCoin";
+pub const WITHDRAW: &str = "0x1::coin::WithdrawEvent";
+pub const DEPOSIT: &str = "0x1::coin::DepositEvent";
+pub const GAS_FEE: &str = "0x1::aptos_coin::GasFeeEvent";
+
+static DB_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Harness {
+    pub db: Arc<DatabaseConnection>,
+    pub network: Arc<Network>,
+    pub bot: Bot,
+    pub fullnode: FakeFullnode,
+    pub indexer: FakeIndexer,
+    pub telegram: FakeTelegram,
+    db_path: PathBuf,
+    next_user: AtomicUsize,
+}
+
+impl Harness {
+    pub async fn start() -> Self {
+        Self::start_with(None).await
+    }
+
+    pub async fn start_with(recorder: Option<Recorder>) -> Self {
+        Self::start_configured(recorder, SpamConfig::default()).await
+    }
+
+    /// Starts with the spam heuristics configured as `spam`.
+    pub async fn start_with_spam(spam: SpamConfig) -> Self {
+        Self::start_configured(None, spam).await
+    }
+
+    async fn start_configured(recorder: Option<Recorder>, spam: SpamConfig) -> Self {
+        let fullnode = FakeFullnode::start().await;
+        let indexer = FakeIndexer::start().await;
+        let telegram = FakeTelegram::start().await;
+
+        let db_path = std::env::temp_dir().join(format!(
+            "aptos-tracking-e2e-{}-{}.db",
+            std::process::id(),
+            DB_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut opt = ConnectOptions::new(format!("sqlite://{}?mode=rwc", db_path.display()));
+        opt.max_connections(1).sqlx_logging(false);
+        let db = Database::connect(opt).await.expect("sqlite connect");
+        migration::prepare(&db, true).await.expect("migrate");
+
+        let config = NetworkConfig {
+            name: DEFAULT_NETWORK.to_string(),
+            rpc: RpcConfig {
+                urls: vec![fullnode.url.to_string()],
+                ..Default::default()
+            },
+            indexer: IndexerConfig {
+                endpoints: vec![IndexerEndpointConfig {
+                    url: indexer.url.to_string(),
+                    api_key: None,
+                    headers: Default::default(),
+                    timeout_ms: 5_000,
+                }],
+                retries: 0,
+                backoff_ms: 0,
+            },
+            explorer: None,
+        };
+        let network = Network::connect(&config, Arc::new(rqwClient::new()), recorder, spam)
+            .await
+            .expect("connect network");
+        Harness {
+            db: Arc::new(db),
+            network: Arc::new(network),
+            bot: Bot::new("1:e2e").set_api_url(telegram.url.clone()),
+            fullnode,
+            indexer,
+            telegram,
+            db_path,
+            next_user: AtomicUsize::new(1),
+        }
+    }
+
+    pub async fn subscribe(
+        &self,
+        chat_id: i64,
+        address: &str,
+        track_type: TrackType,
+        minimum_value: f64,
+    ) -> wallet_tracked::Model {
+        self.subscribe_on(DEFAULT_NETWORK, chat_id, address, track_type, minimum_value)
+            .await
+    }
+
+    pub async fn subscribe_on(
+        &self,
+        network: &str,
+        chat_id: i64,
+        address: &str,
+        track_type: TrackType,
+        minimum_value: f64,
+    ) -> wallet_tracked::Model {
+        let user_id = self.next_user.fetch_add(1, Ordering::Relaxed) as i64;
+        wallet_tracked::ActiveModel {
+            chat_id: Set(chat_id),
+            user_id: Set(user_id),
+            wallet_address: Set(address.to_string()),
+            track_type: Set(track_type),
+            minimum_value: Set(minimum_value),
+            network: Set(network.to_string()),
+            ..Default::default()
+        }
+        .insert(self.db.as_ref())
+        .await
+        .expect("insert subscription")
+    }
+
+    pub async fn set_price(&self, mint: &str, name: &str, value: f64) {
+        token_info::ActiveModel {
+            mint: Set(mint.to_string()),
+            name: Set(Some(name.to_string())),
+            value: Set(value),
+            ..Default::default()
+        }
+        .insert(self.db.as_ref())
+        .await
+        .expect("insert token");
+    }
+
+    /// Serves `block` and runs one `handle_new_block` pass over it.
+    pub async fn process_block(&self, block: &FakeBlock) -> Result<(), Err> {
+        self.serve_block(block).await;
+        self.run_once().await
+    }
+
+    /// Makes the fullnode and indexer serve `block` and points the bottom pointer
+    /// just below it, so it is the next block a worker claims.
+    pub async fn serve_block(&self, block: &FakeBlock) {
+        self.fullnode
+            .add_block(block.height, block.first_version, block.last_version);
+        {
+            let mut indexer = self.indexer.state.lock().unwrap();
+            indexer.activities.extend(block.activities.iter().cloned());
+            indexer.last_success_version = indexer.last_success_version.max(block.last_version);
+        }
+        {
+            let mut current = self.network.current_block.write().await;
+            *current = (*current).max(block.height);
+        }
+        self.seed_bottom(block.height - 1).await;
+    }
+
+    /// One pass of a single block worker.
+    pub async fn run_once(&self) -> Result<(), Err> {
+        self.run_with(Notifier::telegram(self.bot.clone())).await
+    }
+
+    pub async fn run_with(&self, notifier: Notifier) -> Result<(), Err> {
+        let all_wallet = RwLock::new(
+            UniqueWallet::fetch_all_hash_map(self.db.as_ref(), &self.network.name).await?,
+        );
+        let token: RwLock<TokenMap> =
+            RwLock::new(token_info::Model::get_token_hashmap(self.db.as_ref()).await?);
+        let new_token: RwLock<AHashSet<AccountAddress>> = RwLock::new(AHashSet::new());
+        let (new_price_update_tx, _new_price_update_rx) = mpsc::channel(16);
+        handle_new_block(
+            self.db.as_ref(),
+            self.db.as_ref(),
+            &self.network,
+            notifier,
+            &1,
+            &0,
+            &all_wallet,
+            &token,
+            &new_token,
+            new_price_update_tx,
+        )
+        .await
+    }
+
+    async fn seed_bottom(&self, block: u64) {
+        let block = i64::try_from(block).unwrap();
+        ProcessedBlock::delete_many()
+            .filter(processed_block::Column::Network.eq(&self.network.name))
+            .filter(
+                Condition::any()
+                    .add(processed_block::Column::Place.eq(Place::Bottom))
+                    .add(processed_block::Column::Block.eq(block)),
+            )
+            .exec(self.db.as_ref())
+            .await
+            .expect("clear bottom");
+        processed_block::ActiveModel {
+            network: Set(self.network.name.clone()),
+            block: Set(block),
+            status: Set(Status::Complete),
+            place: Set(Place::Bottom),
+            ..Default::default()
+        }
+        .insert(self.db.as_ref())
+        .await
+        .expect("seed bottom");
+    }
+
+    pub async fn processed(&self, block: u64) -> Option<processed_block::Model> {
+        processed_block::Model::get_block(self.db.as_ref(), &self.network.name, block)
+            .await
+            .expect("processed block")
+    }
+
+    pub fn block_chat(&self, chat_id: i64) {
+        self.telegram.state.lock().unwrap().blocked.insert(chat_id);
+    }
+
+    pub fn unblock_chat(&self, chat_id: i64) {
+        self.telegram.state.lock().unwrap().blocked.remove(&chat_id);
+    }
+
+    pub fn sent(&self) -> Vec<SentMessage> {
+        self.telegram.state.lock().unwrap().sent.clone()
+    }
+
+    pub fn edited(&self) -> Vec<SentMessage> {
+        self.telegram.state.lock().unwrap().edited.clone()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.db_path);
+    }
+}
+
+/// A block spanning `first_version..=last_version` and the indexer rows for it.
+pub struct FakeBlock {
+    pub height: u64,
+    pub first_version: u64,
+    pub last_version: u64,
+    pub activities: Vec<Value>,
+}
+
+/// Transactions are `version` seconds after the epoch.
+fn indexer_timestamp(version: u64) -> String {
+    chrono::DateTime::from_timestamp(i64::try_from(version).unwrap(), 0)
+        .unwrap()
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+/// One `fungible_asset_activities` row moving the fungible asset at `asset`.
+pub fn fa_activity(
+    version: u64,
+    event_index: u64,
+    owner: &str,
+    deposit: bool,
+    amount: u64,
+    asset: &str,
+) -> Value {
+    json!({
+        "amount": amount,
+        "asset_type": asset,
+        "metadata": {
+            "decimals": 6,
+            "name": "Free Airdrop",
+            "symbol": "CLAIM",
+            "token_standard": "v2",
+            "asset_type": asset,
+        },
+        "transaction_version": version,
+        "transaction_timestamp": indexer_timestamp(version),
+        "is_transaction_success": true,
+        "owner_address": owner,
+        "type": match deposit {
+            true => "0x1::fungible_asset::Deposit",
+            false => "0x1::fungible_asset::Withdraw",
+        },
+        "event_index": event_index,
+        "token_standard": "v2",
+    })
+}
+
+/// One successful `fungible_asset_activities` row moving the coin `coin_type`.
+pub fn coin_activity(
+    version: u64,
+    event_index: u64,
+    owner: &str,
+    event_type: &str,
+    amount: u64,
+    coin_type: &str,
+) -> Value {
+    json!({
+        "amount": amount,
+        "asset_type": coin_type,
+        "metadata": {
+            "decimals": 8,
+            "name": "Moon Coin",
+            "symbol": "MOON",
+            "token_standard": "v1",
+            "asset_type": coin_type,
+        },
+        "transaction_version": version,
+        "transaction_timestamp": indexer_timestamp(version),
+        "is_transaction_success": true,
+        "owner_address": owner,
+        "type": event_type,
+        "event_index": event_index,
+        "token_standard": "v1",
+    })
+}
+
+/// One `fungible_asset_activities` row moving APT.
+pub fn apt_activity(
+    version: u64,
+    event_index: u64,
+    owner: &str,
+    event_type: &str,
+    amount: u64,
+    success: bool,
+) -> Value {
+    json!({
+        "amount": amount,
+        "asset_type": APT,
+        "metadata": {
+            "decimals": 8,
+            "name": "Aptos Coin",
+            "symbol": "APT",
+            "token_standard": "v1",
+            "asset_type": APT,
+        },
+        "transaction_version": version,
+        "transaction_timestamp": indexer_timestamp(version),
+        "is_transaction_success": success,
+        "owner_address": owner,
+        "type": event_type,
+        "event_index": event_index,
+        "token_standard": "v1",
+    })
+}
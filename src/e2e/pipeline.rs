@@ -0,0 +1,1186 @@
+use super::*;
+use crate::balance;
+use crate::config::WebhookConfig;
+use crate::digest;
+use crate::entities::sea_orm_active_enums::{DigestMode, TokenMode};
+use crate::export;
+use crate::history;
+use crate::process_new_block::Notification;
+use crate::quiet::{self, QuietHours};
+use crate::replay::{fixture_path, BlockFixture};
+use crate::shadow::{diff, DiffSummary, NotificationLog};
+use crate::spam;
+use crate::tx::BalanceChange;
+use crate::webhook::Webhook;
+use chrono::{TimeDelta, Timelike, Utc};
+use sea_orm::IntoActiveModel;
+use std::str::FromStr;
+use std::time::Duration;
+
+const ALICE: &str = "0x00000000000000000000000000000000000000000000000000000000000a11ce";
+const BOB: &str = "0x0000000000000000000000000000000000000000000000000000000000000b0b";
+/// A coin other than APT.
+const MOON: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000b0b::moon::MoonCoin";
+
+/// Alice sends 5 APT to Bob at version 101 and pays gas, version 102 is a failed
+/// deposit to Alice that must be ignored.
+fn transfer_block(height: u64) -> FakeBlock {
+    FakeBlock {
+        height,
+        first_version: 100,
+        last_version: 102,
+        activities: vec![
+            apt_activity(101, 0, ALICE, WITHDRAW, 500_000_000, true),
+            apt_activity(101, 1, BOB, DEPOSIT, 500_000_000, true),
+            apt_activity(101, 2, ALICE, GAS_FEE, 100, true),
+            apt_activity(102, 0, ALICE, DEPOSIT, 700_000_000, false),
+        ],
+    }
+}
+
+#[tokio::test]
+async fn block_notifies_matching_subscriptions() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let alice_full = harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    harness.subscribe(2, ALICE, TrackType::Receive, 1.0).await;
+    let bob_receive = harness.subscribe(3, BOB, TrackType::Receive, 100.0).await;
+    harness.subscribe(4, BOB, TrackType::Sent, 1.0).await;
+    harness
+        .subscribe(5, ALICE, TrackType::Balance, 10_000.0)
+        .await;
+    harness
+        .subscribe_on("testnet", 6, ALICE, TrackType::Full, 0.0)
+        .await;
+
+    harness
+        .process_block(&transfer_block(1_000))
+        .await
+        .expect("process block");
+
+    let mut sent = harness.sent();
+    sent.sort_by_key(|message| message.chat_id);
+    assert_eq!(
+        sent.iter().map(|m| m.chat_id).collect::<Vec<i64>>(),
+        vec![1, 3],
+        "{:#?}",
+        sent
+    );
+
+    let alice = &sent[0];
+    assert!(alice.text.contains("Sent 5.00000000 APT"), "{}", alice.text);
+    assert!(alice.text.contains("Sent 0.00000100 APT"), "{}", alice.text);
+    assert!(!alice.text.contains("7.00000000"), "{}", alice.text);
+    assert_eq!(alice.buttons.len(), 3);
+    assert!(alice.buttons[0]["url"]
+        .as_str()
+        .unwrap()
+        .contains("/txn/101"));
+    assert_eq!(
+        alice.buttons[1]["callback_data"].as_str(),
+        Some(alice_full.to_unsub_callback_data().as_str())
+    );
+    assert_eq!(
+        alice.buttons[2]["callback_data"].as_str(),
+        Some(alice_full.to_mute_callback_data().as_str())
+    );
+
+    let bob = &sent[1];
+    assert_eq!(bob.text.lines().count(), 1, "{}", bob.text);
+    assert!(bob.text.contains("Receive 5.00000000 APT"), "{}", bob.text);
+    assert_eq!(
+        bob.buttons[1]["callback_data"].as_str(),
+        Some(bob_receive.to_unsub_callback_data().as_str())
+    );
+
+    let processed = harness.processed(1_000).await.expect("block row");
+    assert_eq!(processed.status, Status::Complete);
+    assert_eq!(processed.tx_count, 2);
+}
+
+#[tokio::test]
+async fn blocked_chat_is_unsubscribed() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(7, ALICE, TrackType::Full, 0.0).await;
+    harness.subscribe(8, ALICE, TrackType::Full, 0.0).await;
+    harness.block_chat(7);
+
+    harness
+        .process_block(&transfer_block(2_000))
+        .await
+        .expect("process block");
+
+    assert_eq!(
+        harness
+            .sent()
+            .iter()
+            .map(|m| m.chat_id)
+            .collect::<Vec<i64>>(),
+        vec![8]
+    );
+    let remaining = WalletTracked::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.chat_id)
+        .collect::<Vec<i64>>();
+    assert_eq!(remaining, vec![8]);
+}
+
+#[tokio::test]
+async fn token_filter_limits_alerts() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let usdc = "0x00000000000000000000000000000000000000000000000000000000000005dc";
+    harness.set_price(usdc, "USDC", 1.0).await;
+    let only_apt = harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let except_apt = harness.subscribe(2, ALICE, TrackType::Full, 0.0).await;
+    let only_usdc = harness.subscribe(3, ALICE, TrackType::Full, 0.0).await;
+    let only_other = harness.subscribe(4, ALICE, TrackType::Full, 0.0).await;
+    for (subscription, mode, tokens) in [
+        (only_apt, TokenMode::Include, APT),
+        (except_apt, TokenMode::Exclude, APT),
+        (only_usdc, TokenMode::Include, usdc),
+        (only_other, TokenMode::Include, "0x1::other::Coin"),
+    ] {
+        let mut update = subscription.into_active_model();
+        update.token_mode = Set(mode);
+        update.tokens = Set(Some(tokens.to_string()));
+        update.update(harness.db.as_ref()).await.unwrap();
+    }
+    let mut block = transfer_block(2_500);
+    block.last_version = 103;
+    block.activities.extend([
+        fa_activity(103, 0, BOB, false, 1_000_000_000, usdc),
+        fa_activity(103, 1, ALICE, true, 1_000_000_000, usdc),
+    ]);
+
+    harness.process_block(&block).await.expect("process block");
+
+    let mut sent = harness
+        .sent()
+        .iter()
+        .map(|m| {
+            let url = m.buttons[0]["url"].as_str().unwrap();
+            (m.chat_id, url.contains("/txn/103"))
+        })
+        .collect::<Vec<(i64, bool)>>();
+    sent.sort();
+    assert_eq!(sent, vec![(1, false), (2, true), (3, true)]);
+}
+
+#[tokio::test]
+async fn dust_deposits_are_suppressed() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let block = FakeBlock {
+        height: 2_600,
+        first_version: 100,
+        last_version: 103,
+        activities: vec![
+            apt_activity(102, 0, ALICE, DEPOSIT, 100, true),
+            apt_activity(103, 0, ALICE, WITHDRAW, 500_000_000, true),
+            apt_activity(103, 1, ALICE, GAS_FEE, 100, true),
+        ],
+    };
+
+    harness.process_block(&block).await.expect("process block");
+
+    let sent = harness.sent();
+    assert_eq!(sent.len(), 1, "{:#?}", sent);
+    assert!(sent[0].buttons[0]["url"]
+        .as_str()
+        .unwrap()
+        .contains("/txn/103"));
+}
+
+#[tokio::test]
+async fn fungible_assets_and_coins_alert_under_their_own_keys() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let usdc = "0x00000000000000000000000000000000000000000000000000000000000005dc";
+    harness.set_price(usdc, "USDC", 1.0).await;
+    harness.set_price(MOON, "MOON", 0.5).await;
+    let block = FakeBlock {
+        height: 2_650,
+        first_version: 100,
+        last_version: 102,
+        activities: vec![
+            fa_activity(101, 0, ALICE, true, 1_000_000_000, usdc),
+            fa_activity(101, 1, BOB, false, 1_000_000_000, usdc),
+            coin_activity(102, 0, BOB, WITHDRAW, 300_000_000, MOON),
+            coin_activity(102, 1, ALICE, DEPOSIT, 300_000_000, MOON),
+            apt_activity(102, 2, BOB, GAS_FEE, 100, true),
+        ],
+    };
+
+    harness.process_block(&block).await.expect("process block");
+
+    let sent = harness.sent();
+    assert_eq!(sent.len(), 2, "{:#?}", sent);
+    assert!(
+        sent[0].text.contains("Receive 1000.000000 USDC"),
+        "{}",
+        sent[0].text
+    );
+    assert!(
+        sent[1].text.contains("Receive 3.00000000 MOON"),
+        "{}",
+        sent[1].text
+    );
+    assert!(!sent[1].text.contains("APT"), "{}", sent[1].text);
+}
+
+#[tokio::test]
+async fn airdropped_spam_is_suppressed_and_flagged() {
+    let harness = Harness::start_with_spam(SpamConfig {
+        auto_flag: true,
+        ..Default::default()
+    })
+    .await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let scam = "0x00000000000000000000000000000000000000000000000000000000000c1a1a";
+    let block = FakeBlock {
+        height: 2_660,
+        first_version: 100,
+        last_version: 103,
+        activities: vec![
+            fa_activity(101, 0, ALICE, true, 1_000_000, scam),
+            coin_activity(102, 0, ALICE, DEPOSIT, 1_000_000_000_000, MOON),
+            apt_activity(103, 0, ALICE, WITHDRAW, 500_000_000, true),
+            apt_activity(103, 1, ALICE, GAS_FEE, 100, true),
+        ],
+    };
+
+    harness.process_block(&block).await.expect("process block");
+
+    let sent = harness.sent();
+    assert_eq!(sent.len(), 1, "{:#?}", sent);
+    assert!(sent[0].buttons[0]["url"]
+        .as_str()
+        .unwrap()
+        .contains("/txn/103"));
+    for (mint, decimal) in [(scam, 6), (MOON, 8)] {
+        let flagged = TokenInfo::find_by_id(mint.to_string())
+            .one(harness.db.as_ref())
+            .await
+            .unwrap()
+            .expect("flagged row");
+        assert_eq!(flagged.is_skipped, spam::FLAGGED, "{mint}");
+        assert_eq!(flagged.decimal, decimal, "{mint}");
+    }
+}
+
+/// An airdrop of `asset` to Alice.
+fn airdrop(asset: &str, amount: i128) -> BalanceChange {
+    BalanceChange::from_event(
+        "0x1::fungible_asset::Deposit".to_string(),
+        amount,
+        6,
+        AccountAddress::from_str(ALICE).unwrap(),
+        asset.to_string(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn flagged_tokens_keep_metadata_and_clear_once_priced() {
+    let harness = Harness::start().await;
+    let asset = "0x00000000000000000000000000000000000000000000000000000000000005dc";
+    let change = airdrop(asset, 1_000_000);
+    let mint = change.to_token_address();
+    let tokens = [(
+        mint.clone(),
+        spam::SpamToken {
+            symbol: Some("CLAIM".to_string()),
+            decimals: 6,
+        },
+    )]
+    .into_iter()
+    .collect();
+
+    let flagged = token_info::Model::flag_spam(harness.db.as_ref(), &tokens)
+        .await
+        .expect("flag spam");
+    assert_eq!(flagged.len(), 1);
+    let row = TokenInfo::find_by_id(mint.clone())
+        .one(harness.db.as_ref())
+        .await
+        .unwrap()
+        .expect("flagged row");
+    assert_eq!(row.name.as_deref(), Some("CLAIM"));
+    assert_eq!(row.decimal, 6);
+    assert_eq!(row.is_skipped, spam::FLAGGED);
+
+    let mut token = token_info::Model::get_token_hashmap(harness.db.as_ref())
+        .await
+        .unwrap();
+    let config = SpamConfig::default();
+    assert!(spam::is_skipped(&token, &mint));
+    assert_eq!(
+        spam::classify(&change, true, false, &token, &config),
+        spam::Verdict::Skipped
+    );
+
+    token.get_mut(&mint).unwrap().value = 1.0;
+    assert!(!spam::is_skipped(&token, &mint));
+    assert_eq!(
+        spam::classify(&change, true, false, &token, &config),
+        spam::Verdict::Keep
+    );
+}
+
+/// A threshold value and the asset type it is counted in, USD when `None`.
+type Limit = Option<(f64, Option<&'static str>)>;
+
+#[tokio::test]
+async fn thresholds_count_each_direction_in_usd_or_token_units() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let thresholds: [(i64, &str, Limit, Limit); 4] = [
+        (1, BOB, Some((10.0, Some(APT))), None),
+        (2, BOB, Some((4.0, Some(APT))), None),
+        (3, ALICE, None, Some((100.0, None))),
+        (4, ALICE, Some((0.0, None)), None),
+    ];
+    for (chat_id, address, received, sent) in thresholds {
+        let subscription = harness
+            .subscribe(chat_id, address, TrackType::Full, 0.0)
+            .await;
+        let mut update = subscription.into_active_model();
+        update.min_received = Set(received.map(|(value, _)| value));
+        update.min_received_token = Set(received.and_then(|(_, token)| token.map(String::from)));
+        update.min_sent = Set(sent.map(|(value, _)| value));
+        update.min_sent_token = Set(sent.and_then(|(_, token)| token.map(String::from)));
+        update.update(harness.db.as_ref()).await.unwrap();
+    }
+
+    harness
+        .process_block(&transfer_block(2_700))
+        .await
+        .expect("process block");
+
+    let mut sent = harness
+        .sent()
+        .iter()
+        .map(|m| m.chat_id)
+        .collect::<Vec<i64>>();
+    sent.sort();
+    assert_eq!(sent, vec![2, 3]);
+}
+
+#[tokio::test]
+async fn muted_subscriptions_skip_alerts_until_expiry() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let now = Utc::now();
+    for (chat_id, muted_until) in [
+        (1, Some(now + TimeDelta::hours(1))),
+        (2, Some(now - TimeDelta::minutes(1))),
+        (3, None),
+    ] {
+        let mut update = harness
+            .subscribe(chat_id, ALICE, TrackType::Full, 0.0)
+            .await
+            .into_active_model();
+        update.muted_until = Set(muted_until);
+        update.update(harness.db.as_ref()).await.unwrap();
+    }
+
+    harness
+        .process_block(&transfer_block(2_800))
+        .await
+        .expect("process block");
+
+    let mut sent = harness
+        .sent()
+        .iter()
+        .map(|m| m.chat_id)
+        .collect::<Vec<i64>>();
+    sent.sort();
+    assert_eq!(sent, vec![2, 3]);
+}
+
+#[tokio::test]
+async fn quiet_hours_hold_alerts_for_one_summary() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    harness.subscribe(1, BOB, TrackType::Full, 0.0).await;
+    harness.subscribe(2, ALICE, TrackType::Full, 0.0).await;
+    // Two hours around now in UTC+2, so the test does not depend on the clock.
+    let now = Utc::now();
+    let local = (now.hour() * 60 + now.minute() + 120) as u16;
+    let quiet = QuietHours {
+        start: (local + 24 * 60 - 60) % (24 * 60),
+        end: (local + 60) % (24 * 60),
+        utc_offset: 120,
+    };
+    assert!(quiet.contains(now));
+    quiet::set_quiet_hours(harness.db.as_ref(), 1, Some(quiet))
+        .await
+        .unwrap();
+
+    harness
+        .process_block(&transfer_block(2_900))
+        .await
+        .expect("process block");
+
+    let sent = harness.sent();
+    assert_eq!(
+        sent.iter().map(|m| m.chat_id).collect::<Vec<i64>>(),
+        vec![2]
+    );
+    let notifier = Notifier::telegram(harness.bot.clone());
+    assert_eq!(
+        quiet::flush_queued(harness.db.as_ref(), &notifier, now)
+            .await
+            .unwrap(),
+        0
+    );
+    harness.block_chat(1);
+    assert_eq!(
+        quiet::flush_queued(harness.db.as_ref(), &notifier, now + TimeDelta::hours(2))
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        QueuedAlert::find()
+            .all(harness.db.as_ref())
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    harness.unblock_chat(1);
+    assert_eq!(
+        quiet::flush_queued(harness.db.as_ref(), &notifier, now + TimeDelta::hours(2))
+            .await
+            .unwrap(),
+        1
+    );
+    let sent = harness.sent();
+    assert_eq!(sent.len(), 2);
+    let summary = &sent[1];
+    assert_eq!(summary.chat_id, 1);
+    assert!(
+        summary.text.starts_with("Quiet hours are over, 2 alerts"),
+        "{}",
+        summary.text
+    );
+    assert!(
+        summary.text.contains("Sent 5.00000000 APT"),
+        "{}",
+        summary.text
+    );
+    assert!(
+        summary.text.contains("Receive 5.00000000 APT"),
+        "{}",
+        summary.text
+    );
+    assert!(QueuedAlert::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn shadow_mode_holds_nothing_for_production() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let mut digest = harness
+        .subscribe(2, BOB, TrackType::Full, 0.0)
+        .await
+        .into_active_model();
+    digest.digest = Set(DigestMode::Daily);
+    digest.update(harness.db.as_ref()).await.unwrap();
+    let now = Utc::now();
+    let local = (now.hour() * 60 + now.minute()) as u16;
+    let quiet = QuietHours {
+        start: (local + 24 * 60 - 60) % (24 * 60),
+        end: (local + 60) % (24 * 60),
+        utc_offset: 0,
+    };
+    quiet::set_quiet_hours(harness.db.as_ref(), 1, Some(quiet))
+        .await
+        .unwrap();
+    harness.serve_block(&transfer_block(2_950)).await;
+
+    harness
+        .run_with(Notifier::new(None).in_shadow_mode())
+        .await
+        .expect("process block");
+
+    assert!(harness.sent().is_empty());
+    assert!(QueuedAlert::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(DigestEntry::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(Activity::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn digest_collects_alerts_until_the_digest_time() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let now = Utc::now();
+    let mut update = harness
+        .subscribe(1, ALICE, TrackType::Full, 0.0)
+        .await
+        .into_active_model();
+    update.digest = Set(DigestMode::Daily);
+    update.last_digest = Set(Some(now));
+    update.update(harness.db.as_ref()).await.unwrap();
+    harness.subscribe(2, ALICE, TrackType::Full, 0.0).await;
+
+    harness
+        .process_block(&transfer_block(2_950))
+        .await
+        .expect("process block");
+
+    let sent = harness.sent();
+    assert_eq!(
+        sent.iter().map(|m| m.chat_id).collect::<Vec<i64>>(),
+        vec![2]
+    );
+    let notifier = Notifier::telegram(harness.bot.clone());
+    assert_eq!(
+        digest::send_due(harness.db.as_ref(), &notifier, now)
+            .await
+            .unwrap(),
+        0
+    );
+    harness.block_chat(1);
+    assert_eq!(
+        digest::send_due(harness.db.as_ref(), &notifier, now + TimeDelta::days(1))
+            .await
+            .unwrap(),
+        0
+    );
+    assert!(!DigestEntry::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .is_empty());
+    harness.unblock_chat(1);
+    assert_eq!(
+        digest::send_due(harness.db.as_ref(), &notifier, now + TimeDelta::days(1))
+            .await
+            .unwrap(),
+        1
+    );
+    let sent = harness.sent();
+    assert_eq!(sent.len(), 2);
+    let summary = &sent[1];
+    assert_eq!(summary.chat_id, 1);
+    assert!(summary.text.starts_with("Daily digest"), "{}", summary.text);
+    assert!(summary.text.contains("1 transactions"), "{}", summary.text);
+    assert!(DigestEntry::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        digest::send_due(harness.db.as_ref(), &notifier, now + TimeDelta::days(1))
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn digest_counts_changes_below_the_thresholds() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let mut update = harness
+        .subscribe(1, ALICE, TrackType::Receive, 1_000.0)
+        .await
+        .into_active_model();
+    update.digest = Set(DigestMode::Daily);
+    update.update(harness.db.as_ref()).await.unwrap();
+
+    harness
+        .process_block(&transfer_block(2_960))
+        .await
+        .expect("process block");
+
+    assert!(harness.sent().is_empty());
+    let mut amounts: Vec<f64> = DigestEntry::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.amount)
+        .collect();
+    amounts.sort_by(f64::total_cmp);
+    // The transfer and its gas fee, neither reaches the receive threshold.
+    assert_eq!(amounts.len(), 2, "{:?}", amounts);
+    assert_eq!(amounts[0], -5.0);
+}
+
+#[tokio::test]
+async fn bursts_are_edited_into_one_message() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    harness.subscribe(2, BOB, TrackType::Full, 0.0).await;
+    let notifier =
+        Notifier::telegram(harness.bot.clone()).with_burst_window(Duration::from_secs(60));
+    let next = FakeBlock {
+        height: 3_101,
+        first_version: 103,
+        last_version: 104,
+        activities: vec![apt_activity(104, 0, ALICE, WITHDRAW, 200_000_000, true)],
+    };
+
+    for block in [transfer_block(3_100), next] {
+        harness.serve_block(&block).await;
+        harness
+            .run_with(notifier.clone())
+            .await
+            .expect("process block");
+    }
+
+    let mut sent = harness.sent();
+    sent.sort_by_key(|message| message.chat_id);
+    assert_eq!(
+        sent.iter().map(|m| m.chat_id).collect::<Vec<i64>>(),
+        vec![1, 2]
+    );
+    let edited = harness.edited();
+    assert_eq!(edited.len(), 1, "{:#?}", edited);
+    let burst = &edited[0];
+    assert_eq!(burst.message_id, sent[0].message_id);
+    assert!(
+        burst.text.starts_with("2 transactions for"),
+        "{}",
+        burst.text
+    );
+    assert!(burst.text.contains("/txn/101"), "{}", burst.text);
+    assert!(burst.text.contains("Sent 2.00000000 APT"), "{}", burst.text);
+    assert!(burst.buttons[0]["url"]
+        .as_str()
+        .unwrap()
+        .contains("/txn/104"));
+}
+
+#[tokio::test]
+async fn alerts_racing_the_first_of_a_burst_join_it() {
+    let harness = Harness::start().await;
+    let alice = harness.subscribe(1, ALICE, TrackType::Full, 0.0).await;
+    let notifier =
+        Notifier::telegram(harness.bot.clone()).with_burst_window(Duration::from_secs(60));
+    let alert = |version: i128| Notification {
+        subscription: alice.clone(),
+        version,
+        text: format!("alert {version}\n"),
+        tx_url: reqwest::Url::parse(&format!("https://explorer.test/txn/{version}")).unwrap(),
+        changes: vec![],
+    };
+
+    let (first, second) = tokio::join!(
+        notifier.send(harness.db.as_ref(), DEFAULT_NETWORK, alert(1)),
+        notifier.send(harness.db.as_ref(), DEFAULT_NETWORK, alert(2)),
+    );
+    first.expect("first alert");
+    second.expect("second alert");
+
+    let sent = harness.sent();
+    assert_eq!(sent.len(), 1, "{:#?}", sent);
+    let edited = harness.edited();
+    assert_eq!(edited.len(), 1, "{:#?}", edited);
+    assert_eq!(edited[0].message_id, sent[0].message_id);
+    assert!(
+        edited[0].text.starts_with("2 transactions for"),
+        "{}",
+        edited[0].text
+    );
+    assert!(edited[0].text.contains("alert 2"), "{}", edited[0].text);
+    assert!(edited[0].buttons[0]["url"]
+        .as_str()
+        .unwrap()
+        .contains("/txn/2"));
+}
+
+#[tokio::test]
+async fn unparseable_rules_alert_on_nothing() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    for (chat_id, rule) in [(1, "usd >="), (2, "direction == out")] {
+        let mut subscription = harness
+            .subscribe(chat_id, ALICE, TrackType::Full, 0.0)
+            .await
+            .into_active_model();
+        subscription.filter_expr = Set(Some(rule.to_string()));
+        subscription.update(harness.db.as_ref()).await.unwrap();
+    }
+
+    harness
+        .process_block(&transfer_block(3_150))
+        .await
+        .expect("process block");
+
+    let sent = harness.sent();
+    assert_eq!(
+        sent.iter().map(|m| m.chat_id).collect::<Vec<i64>>(),
+        vec![2],
+        "{:#?}",
+        sent
+    );
+}
+
+#[tokio::test]
+async fn rules_match_fungible_assets_by_symbol() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    let usdc = "0x00000000000000000000000000000000000000000000000000000000000005dc";
+    harness.set_price(usdc, "USDC", 1.0).await;
+    for (chat_id, rule) in [
+        (1, "token in [APT, USDC] and direction == in"),
+        (2, "token == usdc and usd >= 500"),
+        (3, "token in [APT] and direction == in"),
+    ] {
+        let mut subscription = harness
+            .subscribe(chat_id, ALICE, TrackType::Full, 0.0)
+            .await
+            .into_active_model();
+        subscription.filter_expr = Set(Some(rule.to_string()));
+        subscription.update(harness.db.as_ref()).await.unwrap();
+    }
+    let mut block = transfer_block(3_175);
+    block.last_version = 103;
+    block.activities.extend([
+        fa_activity(103, 0, BOB, false, 1_000_000_000, usdc),
+        fa_activity(103, 1, ALICE, true, 1_000_000_000, usdc),
+    ]);
+
+    harness.process_block(&block).await.expect("process block");
+
+    let sent = harness.sent();
+    let mut chats = sent.iter().map(|m| m.chat_id).collect::<Vec<i64>>();
+    chats.sort();
+    assert_eq!(chats, vec![1, 2], "{:#?}", sent);
+    assert!(
+        sent.iter()
+            .all(|m| m.buttons[0]["url"].as_str().unwrap().contains("/txn/103")),
+        "{:#?}",
+        sent
+    );
+}
+
+#[tokio::test]
+async fn activity_is_stored_once_per_block() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 2.0).await;
+    let alice = harness
+        .subscribe(1, ALICE, TrackType::Receive, 1_000.0)
+        .await;
+
+    for _ in 0..2 {
+        harness
+            .process_block(&transfer_block(3_200))
+            .await
+            .expect("process block");
+    }
+
+    assert!(harness.sent().is_empty());
+    let (entries, more) = history::page(harness.db.as_ref(), &alice, 0, history::PAGE)
+        .await
+        .unwrap();
+    assert!(!more);
+    assert_eq!(entries.len(), 2, "{:#?}", entries);
+    assert!(entries.iter().all(|entry| entry.version == 101));
+    assert!(entries.iter().all(|entry| entry.block == 3_200));
+    let sent = entries
+        .iter()
+        .find(|entry| entry.amount == -5.0)
+        .expect("transfer row");
+    assert_eq!(sent.symbol, "APT");
+    assert!(sent.usd.is_some());
+    assert_eq!(sent.timestamp.timestamp(), 3_200);
+
+    let (first, more) = history::page(harness.db.as_ref(), &alice, 0, 1)
+        .await
+        .unwrap();
+    assert!(more);
+    let text = history::history_text(&alice, &first, 0);
+    assert!(text.starts_with("History of"), "{}", text);
+    assert!(text.contains("\n1970-01-01 00:53 APT -"), "{}", text);
+    assert!(text.ends_with("at version 101"), "{}", text);
+}
+
+#[tokio::test]
+async fn export_lists_every_change_and_keeps_processed_prices() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 2.0).await;
+    harness
+        .subscribe(1, ALICE, TrackType::Receive, 1_000.0)
+        .await;
+    let asset = "0x00000000000000000000000000000000000000000000000000000000000005dc";
+    let block = FakeBlock {
+        height: 3_250,
+        first_version: 100,
+        last_version: 103,
+        activities: vec![
+            apt_activity(101, 0, ALICE, WITHDRAW, 500_000_000, true),
+            apt_activity(101, 1, BOB, DEPOSIT, 500_000_000, true),
+            apt_activity(101, 2, ALICE, GAS_FEE, 100, true),
+            // Dust, alerts leave it out and it is not stored.
+            apt_activity(102, 0, ALICE, DEPOSIT, 100, true),
+            // An airdrop the spam heuristics leave out of alerts and storage.
+            fa_activity(103, 0, ALICE, true, 1_000_000, asset),
+        ],
+    };
+    harness.process_block(&block).await.expect("process block");
+    let stored = Activity::find()
+        .all(harness.db.as_ref())
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|entry| entry.amount == -5.0)
+        .expect("stored transfer");
+    let mut apt = TokenInfo::find_by_id(APT.to_string())
+        .one(harness.db.as_ref())
+        .await
+        .unwrap()
+        .unwrap()
+        .into_active_model();
+    apt.value = Set(4.0);
+    apt.update(harness.db.as_ref()).await.unwrap();
+
+    // The fake indexer dates version 101 at 00:01:41.
+    let (from, to) = export::parse_range("1970-01-01", "1970-01-01").unwrap();
+    let rows = export::rows(harness.db.as_ref(), &harness.network, ALICE, from, to)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 4, "{:#?}", rows);
+    let sent = rows
+        .iter()
+        .find(|row| row.amount == 5.0)
+        .expect("transfer row");
+    assert_eq!(sent.direction, "out");
+    assert_eq!(sent.version, 101);
+    assert_eq!(sent.timestamp.timestamp(), 101);
+    assert_eq!(sent.token, "APT");
+    assert_eq!(sent.counterparty.as_deref(), Some(BOB));
+    assert_eq!(sent.usd_at, "processed");
+    assert_eq!(sent.usd, stored.usd.map(f64::abs));
+    let dust = rows
+        .iter()
+        .find(|row| row.version == 102)
+        .expect("dust row");
+    assert_eq!(dust.direction, "in");
+    assert_eq!(dust.usd_at, "current");
+    let airdrop = rows.iter().find(|row| row.version == 103).expect("airdrop");
+    assert_eq!(airdrop.asset_type, asset);
+    assert_eq!(airdrop.token, "CLAIM");
+    assert_eq!(airdrop.usd, None);
+    assert!(!airdrop.skipped);
+
+    let csv = String::from_utf8(export::to_csv(&rows).unwrap()).unwrap();
+    assert!(
+        csv.starts_with(
+            "timestamp,version,direction,token,asset_type,amount,usd,counterparty,usd_at,skipped\n"
+        ),
+        "{}",
+        csv
+    );
+    let json: Value = serde_json::from_slice(&export::to_json(&rows).unwrap()).unwrap();
+    assert_eq!(json.as_array().map(Vec::len), Some(4));
+}
+
+#[tokio::test]
+async fn export_prices_unprocessed_activity_now() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 2.0).await;
+    // Indexed but never processed, the fake indexer dates version 101 at 00:01:41.
+    harness.serve_block(&transfer_block(3_300)).await;
+
+    let (from, to) = export::parse_range("1970-01-01", "1970-01-01").unwrap();
+    let rows = export::rows(harness.db.as_ref(), &harness.network, ALICE, from, to)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 2, "{:#?}", rows);
+    assert!(rows.iter().all(|row| row.version == 101));
+    assert!(rows.iter().all(|row| row.timestamp.timestamp() == 101));
+    assert!(rows.iter().all(|row| row.usd_at == "current"));
+    let sent = rows
+        .iter()
+        .find(|row| row.amount == 5.0)
+        .expect("transfer row");
+    assert_eq!(sent.direction, "out");
+    assert_eq!(sent.token, "APT");
+    assert_eq!(sent.counterparty.as_deref(), Some(BOB));
+    assert!(sent.usd.is_some());
+
+    let (from, to) = export::parse_range("1970-01-03", "1970-01-04").unwrap();
+    let rows = export::rows(harness.db.as_ref(), &harness.network, ALICE, from, to)
+        .await
+        .unwrap();
+    assert!(rows.is_empty(), "{:#?}", rows);
+    assert!(export::parse_range("1970-01-04", "1970-01-03").is_err());
+}
+
+#[tokio::test]
+async fn export_keeps_every_row_of_a_version_filling_a_page() {
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 2.0).await;
+    // 600 withdrawals in one version, more than an indexer page, then one deposit.
+    let mut activities: Vec<Value> = (0..600)
+        .map(|index| apt_activity(101, index, ALICE, WITHDRAW, 1_000_000, true))
+        .collect();
+    activities.push(apt_activity(102, 0, ALICE, DEPOSIT, 100_000_000, true));
+    harness
+        .serve_block(&FakeBlock {
+            height: 3_350,
+            first_version: 100,
+            last_version: 102,
+            activities,
+        })
+        .await;
+
+    let (from, to) = export::parse_range("1970-01-01", "1970-01-01").unwrap();
+    let rows = export::rows(harness.db.as_ref(), &harness.network, ALICE, from, to)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.iter().filter(|row| row.version == 101).count(), 600);
+    assert!(rows
+        .iter()
+        .filter(|row| row.version == 101)
+        .all(|row| row.direction == "out"
+            && row.token == "APT"
+            && row.timestamp.timestamp() == 101));
+    assert_eq!(rows.last().map(|row| row.version), Some(102));
+    assert_eq!(rows.len(), 601);
+}
+
+#[tokio::test]
+async fn balance_lists_priced_holdings_without_dust_or_skipped_tokens() {
+    let harness = Harness::start().await;
+    let usdc = "0x00000000000000000000000000000000000000000000000000000000000005dc";
+    let dust = "0x000000000000000000000000000000000000000000000000000000000000d057";
+    let scam = "0x00000000000000000000000000000000000000000000000000000000000c1a1a";
+    let meme = "0x0000000000000000000000000000000000000000000000000000000000000be1";
+    harness.set_price(APT, "APT", 2.0).await;
+    harness.set_price(usdc, "USDC", 1.0).await;
+    harness.set_price(dust, "DUST", 1.0).await;
+    token_info::ActiveModel {
+        mint: Set(scam.to_string()),
+        value: Set(0.0),
+        is_skipped: Set(spam::SKIPPED),
+        ..Default::default()
+    }
+    .insert(harness.db.as_ref())
+    .await
+    .unwrap();
+    let alice = harness
+        .subscribe(1, ALICE, TrackType::Receive, 1_000.0)
+        .await;
+    let fa = |owner: &str, asset: &str, symbol: &str, decimals: u8, amount: u64| {
+        json!({
+            "owner_address": owner,
+            "amount": amount,
+            "asset_type": asset,
+            "metadata": { "decimals": decimals, "symbol": symbol },
+        })
+    };
+    {
+        let mut indexer = harness.indexer.state.lock().unwrap();
+        indexer.balances = vec![
+            fa(ALICE, "0xa", "APT", 8, 1_250_000_000),
+            fa(ALICE, usdc, "USDC", 6, 3_000_000),
+            fa(ALICE, dust, "DUST", 6, 1),
+            fa(ALICE, scam, "CLAIM", 6, 1_000_000_000),
+            fa(ALICE, meme, "MEME", 6, 7_000_000),
+            fa(BOB, usdc, "USDC", 6, 9_000_000),
+        ];
+        indexer.coin_balances = vec![
+            json!({
+                "owner_address": ALICE,
+                "amount": 1_250_000_000u64,
+                "coin_type": APT,
+                "coin_info": { "decimals": 8, "symbol": "APT" },
+            }),
+            json!({
+                "owner_address": ALICE,
+                "amount": 5u64,
+                "coin_type": "0x1234::moon::Moon",
+                "coin_info": { "decimals": 8, "symbol": "MOON" },
+            }),
+        ];
+    }
+
+    let token = token_info::Model::get_token_hashmap(harness.db.as_ref())
+        .await
+        .unwrap();
+    let holdings = balance::holdings(&harness.network, &token, ALICE)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        holdings
+            .iter()
+            .map(|holding| holding.symbol.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["APT", "USDC", "MEME"],
+        "{:#?}",
+        holdings
+    );
+    assert_eq!(holdings[0].amount, 12.5);
+    assert_eq!(holdings[1].usd, Some(3.0));
+    assert_eq!(holdings[2].usd, None);
+    let text = balance::balance_text(&alice, &holdings);
+    assert!(
+        text.contains("\nUSDC 3.0000 ($3.00)\nMEME 7.0000\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.ends_with(&format!(
+            "Total ${:.2}, 1 unpriced not counted",
+            balance::total_usd(&holdings)
+        )),
+        "{}",
+        text
+    );
+
+    let empty = balance::holdings(&harness.network, &token, "0x1")
+        .await
+        .unwrap();
+    assert!(empty.is_empty());
+    assert!(balance::balance_text(&alice, &empty).contains("holds nothing"));
+}
+
+#[tokio::test]
+async fn stale_indexer_sends_nothing() {
+    let harness = Harness::start().await;
+    harness.subscribe(9, ALICE, TrackType::Full, 0.0).await;
+    harness.serve_block(&transfer_block(3_000)).await;
+    harness.indexer.state.lock().unwrap().last_success_version = 50;
+
+    let result = harness.run_once().await;
+
+    assert!(matches!(result, Err(Err::IndexerStale)), "{:?}", result);
+    assert!(harness.sent().is_empty());
+    let processed = harness.processed(3_000).await.expect("block row");
+    assert_eq!(processed.status, Status::Processing);
+}
+
+#[tokio::test]
+async fn recorded_block_replays_the_same_messages() {
+    let dir = std::env::temp_dir().join(format!("aptos-tracking-fixtures-{}", std::process::id()));
+    let harness = Harness::start_with(Some(Recorder::new(&dir))).await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(10, ALICE, TrackType::Full, 0.0).await;
+    harness.subscribe(11, BOB, TrackType::Receive, 100.0).await;
+
+    harness
+        .process_block(&transfer_block(4_000))
+        .await
+        .expect("process block");
+
+    let raw = std::fs::read(fixture_path(&dir, &harness.network.name, 4_000)).expect("fixture");
+    let fixture: BlockFixture = serde_json::from_slice(&raw).expect("fixture json");
+    let replayed = fixture.notifications().expect("replay");
+    let mut replayed = replayed
+        .notifications
+        .into_iter()
+        .map(|n| (n.subscription.chat_id, n.text))
+        .collect::<Vec<(i64, String)>>();
+    replayed.sort();
+    let mut sent = harness
+        .sent()
+        .into_iter()
+        .map(|m| (m.chat_id, m.text))
+        .collect::<Vec<(i64, String)>>();
+    sent.sort();
+    assert_eq!(replayed, sent);
+    assert_eq!(sent.len(), 2);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn shadow_mode_logs_instead_of_sending() {
+    let path = std::env::temp_dir().join(format!(
+        "aptos-tracking-shadow-{}.jsonl",
+        std::process::id()
+    ));
+    let harness = Harness::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(12, ALICE, TrackType::Full, 0.0).await;
+    harness.subscribe(13, BOB, TrackType::Full, 0.0).await;
+    harness.serve_block(&transfer_block(5_000)).await;
+    let log = NotificationLog::open(path.to_str().unwrap()).expect("log");
+
+    harness
+        .run_with(Notifier::new(Some(Arc::new(log))))
+        .await
+        .expect("process block");
+
+    assert!(harness.sent().is_empty());
+    let logged = std::fs::read_to_string(&path).expect("log file");
+    assert_eq!(logged.lines().count(), 2, "{}", logged);
+    assert_eq!(
+        diff(&path, &path).expect("diff"),
+        DiffSummary {
+            same: 2,
+            ..Default::default()
+        }
+    );
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn headless_mode_posts_alerts_to_the_webhook() {
+    let harness = Harness::start().await;
+    let hook = FakeWebhook::start().await;
+    harness.set_price(APT, "APT", 1.0).await;
+    harness.subscribe(14, BOB, TrackType::Receive, 1.0).await;
+    harness.serve_block(&transfer_block(6_000)).await;
+    let webhook = Webhook::from_config(
+        Arc::new(rqwClient::new()),
+        &WebhookConfig {
+            url: Some(hook.url.to_string()),
+            ..Default::default()
+        },
+    )
+    .expect("webhook");
+
+    harness
+        .run_with(Notifier::new(None).with_webhook(webhook))
+        .await
+        .expect("process block");
+
+    assert!(harness.sent().is_empty());
+    let received = hook.received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1, "{:#?}", received);
+    assert_eq!(received[0]["kind"], "alert");
+    assert_eq!(received[0]["chat_id"], 14);
+    assert_eq!(received[0]["version"], 101);
+    assert!(received[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Receive 5.00000000 APT"));
+}
@@ -2,7 +2,11 @@
 
 pub mod prelude;
 
+pub mod activity;
+pub mod chat_setting;
+pub mod digest_entry;
 pub mod processed_block;
+pub mod queued_alert;
 pub mod sea_orm_active_enums;
 pub mod token_info;
 pub mod user;
@@ -1,6 +1,10 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0-rc.5
 
+pub use super::activity::Entity as Activity;
+pub use super::chat_setting::Entity as ChatSetting;
+pub use super::digest_entry::Entity as DigestEntry;
 pub use super::processed_block::Entity as ProcessedBlock;
+pub use super::queued_alert::Entity as QueuedAlert;
 pub use super::token_info::Entity as TokenInfo;
 pub use super::user::Entity as User;
 pub use super::wallet_tracked::Entity as WalletTracked;
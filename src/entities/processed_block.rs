@@ -8,14 +8,16 @@ use sea_orm::entity::prelude::*;
 #[sea_orm(table_name = "processed_block")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
-    pub block: u64,
+    pub network: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub block: i64,
     pub processed_time: DateTimeUtc,
     pub last_tx_sig: Option<String>,
-    pub modulo: u8,
-    pub remainder: u8,
+    pub modulo: i16,
+    pub remainder: i16,
     pub status: Status,
     pub place: Place,
-    pub tx_count: u32,
+    pub tx_count: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
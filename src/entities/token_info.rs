@@ -1,8 +1,9 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0-rc.5
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "token_info")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
@@ -10,14 +11,24 @@ pub struct Model {
     pub name: Option<String>,
     #[sea_orm(column_type = "Double")]
     pub value: f64,
-    pub decimal: u8,
-    pub is_skipped: u8,
+    pub decimal: i16,
+    pub is_skipped: i16,
     pub update_time: DateTimeUtc,
     pub creat_time: DateTime,
-    pub last_updated_block: u64,
+    pub last_updated_block: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Replaces MySQL's `ON UPDATE CURRENT_TIMESTAMP`, which the other backends lack.
+    async fn before_save<C>(mut self, _db: &C, _insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        self.update_time = sea_orm::ActiveValue::Set(chrono::Utc::now());
+        Ok(self)
+    }
+}
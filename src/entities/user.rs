@@ -6,7 +6,7 @@ use sea_orm::entity::prelude::*;
 #[sea_orm(table_name = "user")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
-    pub user_id: u64,
+    pub user_id: i64,
     pub user_name: Option<String>,
     pub create_time: DateTimeUtc,
 }
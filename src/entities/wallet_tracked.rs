@@ -1,21 +1,45 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0-rc.5
 
-use super::sea_orm_active_enums::TrackType;
+use super::sea_orm_active_enums::{DigestMode, TokenMode, TrackType};
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "wallet_tracked")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub wallet_id: u64,
+    pub wallet_id: i64,
     pub chat_id: i64,
-    pub user_id: u64,
+    pub user_id: i64,
     pub wallet_address: String,
     pub nickname: Option<String>,
     pub track_type: TrackType,
     pub create_time: DateTimeUtc,
     #[sea_orm(column_type = "Double")]
     pub minimum_value: f64,
+    pub network: String,
+    pub token_mode: TokenMode,
+    /// Asset types `token_mode` applies to, one per line.
+    #[sea_orm(column_type = "Text", nullable)]
+    pub tokens: Option<String>,
+    /// Received threshold, replaces `track_type` and `minimum_value` when either
+    /// threshold is set.
+    #[sea_orm(column_type = "Double", nullable)]
+    pub min_received: Option<f64>,
+    /// Asset type `min_received` is counted in, USD when unset.
+    pub min_received_token: Option<String>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub min_sent: Option<f64>,
+    pub min_sent_token: Option<String>,
+    /// Rule in the [`filter_expr`](crate::filter_expr) language, replaces the mode and
+    /// thresholds when set.
+    #[sea_orm(column_type = "Text", nullable)]
+    pub filter_expr: Option<String>,
+    /// Alerts are dropped until then.
+    pub muted_until: Option<DateTimeUtc>,
+    /// Alerts are collected into a digest instead of sent when not `Off`.
+    pub digest: DigestMode,
+    pub last_digest: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,31 +1,38 @@
 use crate::callback::ParseCallbackErr;
 use aptos_sdk::crypto::hash::HashValueParseError;
 use aptos_sdk::move_types::account_address::AccountAddressParseError;
-use log::error;
-use std::str::FromStr;
-use teloxide::requests::Requester;
-use teloxide::types::ChatId;
 
 use aptos_sdk::rest_client::error::RestError;
-use sea_orm::{DatabaseConnection, DbErr};
-use std::sync::Arc;
-use std::{fmt::Debug, num::ParseIntError};
-use teloxide::error_handlers::ErrorHandler;
-
-use teloxide::Bot;
+use sea_orm::DbErr;
+use std::{
+    fmt::Debug,
+    num::{ParseIntError, TryFromIntError},
+};
+#[cfg(feature = "telegram")]
 use teloxide::{dispatching::dialogue::InMemStorageError, RequestError};
 
+#[cfg(feature = "telegram")]
+pub use handle::HandleErr;
+
 #[derive(Debug, derive_more::Display, derive_more::From, derive_more::Error)]
 pub enum Err {
+    #[cfg(feature = "telegram")]
     Bot(RequestError),
     Db(DbErr),
     Callback(ParseCallbackErr),
+    #[cfg(feature = "telegram")]
     InMem(InMemStorageError),
     // Pubkey(ParsePubkeyError),
     Rqw(reqwest::Error),
+    HeaderName(reqwest::header::InvalidHeaderName),
+    HeaderValue(reqwest::header::InvalidHeaderValue),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
     Sig(HashValueParseError),
     Rpc(RestError),
     ParseInt(ParseIntError),
+    IntConversion(TryFromIntError),
     ParseAddress(AccountAddressParseError),
     ParseEventError,
     TxHasNoSig,
@@ -35,49 +42,85 @@ pub enum Err {
     WaitTillBottomUpdate,
     OverFlow,
     TxPending,
+    Unauthorized,
+    NotFound,
+    AlreadyTracked,
+    InvalidTrackType,
+    NicknameTooLong,
+    InvalidMinimumValue,
+    IndexerStale,
+    IndexerQuery,
+    UnknownNetwork,
+    InvalidRange,
+    #[display("schema does not match entities, missing: {}", _0.join(", "))]
+    #[from(ignore)]
+    SchemaMismatch(#[error(not(source))] Vec<String>),
 }
 
-pub struct HandleErr {
-    db: Arc<DatabaseConnection>,
-    bot: Bot,
-    from: String,
-}
+/// Reports dispatcher errors to the report chat.
+#[cfg(feature = "telegram")]
+mod handle {
+    use log::error;
+    use sea_orm::DatabaseConnection;
+    use std::sync::Arc;
+    use teloxide::error_handlers::ErrorHandler;
+    use teloxide::requests::Requester;
+    use teloxide::types::ChatId;
+    use teloxide::Bot;
 
-impl HandleErr {
-    pub fn new(db: Arc<DatabaseConnection>, bot: Bot, from: String) -> Arc<Self> {
-        Arc::new(Self { db, bot, from })
+    use super::Err;
+
+    pub struct HandleErr {
+        db: Arc<DatabaseConnection>,
+        bot: Bot,
+        from: String,
+        report_chat: Option<i64>,
     }
-}
-impl ErrorHandler<Err> for HandleErr {
-    fn handle_error(self: Arc<Self>, error: Err) -> futures::future::BoxFuture<'static, ()> {
-        let msg = match error {
-            Err::Db(err) => {
-                error!("Database ERROR {:?}", err);
-                Some(err.to_string())
-            }
-            Err::Rpc(err) => {
-                error!("RPC ERROR {:?}", err);
-                Some(err.to_string())
-            }
-            _ => {
-                error!("Error from {} err: {:?}", self.from, error);
-                Some(error.to_string())
-            }
-        };
-        Box::pin(async move {
-            match msg {
-                Some(msg) => {
-                    if let Ok(chat_id) = std::env::var("REPORT_CHAT") {
-                        if let Ok(id) = i64::from_str(&chat_id) {
+
+    impl HandleErr {
+        pub fn new(
+            db: Arc<DatabaseConnection>,
+            bot: Bot,
+            from: String,
+            report_chat: Option<i64>,
+        ) -> Arc<Self> {
+            Arc::new(Self {
+                db,
+                bot,
+                from,
+                report_chat,
+            })
+        }
+    }
+    impl ErrorHandler<Err> for HandleErr {
+        fn handle_error(self: Arc<Self>, error: Err) -> futures::future::BoxFuture<'static, ()> {
+            let msg = match error {
+                Err::Db(err) => {
+                    error!("Database ERROR {:?}", err);
+                    Some(err.to_string())
+                }
+                Err::Rpc(err) => {
+                    error!("RPC ERROR {:?}", err);
+                    Some(err.to_string())
+                }
+                _ => {
+                    error!("Error from {} err: {:?}", self.from, error);
+                    Some(error.to_string())
+                }
+            };
+            Box::pin(async move {
+                match msg {
+                    Some(msg) => {
+                        if let Some(id) = self.report_chat {
                             let _result = self
                                 .bot
                                 .send_message(ChatId(id), format!("Error: {}", msg))
                                 .await;
                         }
                     }
+                    None => {}
                 }
-                None => {}
-            }
-        })
+            })
+        }
     }
 }
@@ -0,0 +1,307 @@
+//! Activity exports for accounting. Rows always come from the indexer, so every
+//! balance change is listed, spam and dust the alerts leave out included. A change
+//! the tracker stored in `activity` keeps the USD value it had when processed, the
+//! others are priced with the current `token_info` values.
+
+use ahash::AHashMap;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::db::TokenMap;
+use crate::entities::{activity, prelude::*, token_info};
+use crate::error::Err;
+use crate::network::Network;
+use crate::query::wallet_activity_query::{
+    Variables, WalletActivityQueryFungibleAssetActivities,
+    WalletActivityQueryFungibleAssetActivitiesMetadata,
+};
+use crate::spam;
+use crate::tx::BalanceChange;
+
+/// Rows in one export, the rest of the range is left out.
+pub const MAX_ROWS: usize = 10_000;
+/// Rows the indexer returns per `WalletActivityQuery`.
+const INDEXER_PAGE: usize = 500;
+const INDEXER_TIME: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// One balance change of the exported wallet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRow {
+    pub timestamp: DateTime<Utc>,
+    pub version: i64,
+    /// `in` or `out`.
+    pub direction: &'static str,
+    pub token: String,
+    pub asset_type: String,
+    /// Whole tokens, unsigned.
+    pub amount: f64,
+    pub usd: Option<f64>,
+    pub counterparty: Option<String>,
+    /// `processed` when `usd` was stored as the tracker processed the transaction,
+    /// `current` when it is priced now.
+    pub usd_at: &'static str,
+    /// The token is skipped in `token_info`, alerts leave it out.
+    pub skipped: bool,
+}
+
+fn direction(amount: f64) -> &'static str {
+    match amount < 0.0 {
+        true => "out",
+        false => "in",
+    }
+}
+
+/// Parses the days `from` and `to`, both included, as `[start, end)` in UTC.
+pub fn parse_range(from: &str, to: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let day = |text: &str| {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map_err(|_| format!("{text} is not a date, ex: 2024-10-01"))
+    };
+    let (from, to) = (day(from)?, day(to)?);
+    if to < from {
+        return Err("the end is before the start".to_string());
+    }
+    let end = to
+        .checked_add_days(Days::new(1))
+        .ok_or_else(|| "date out of range".to_string())?;
+    Ok((
+        from.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+        end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+    ))
+}
+
+/// Balance changes of `wallet` on `network` in `[from, to)`, oldest first, at most
+/// [`MAX_ROWS`].
+pub async fn rows(
+    db: &DatabaseConnection,
+    network: &Network,
+    wallet: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<ExportRow>, Err> {
+    let mut rows = vec![];
+    let mut after: i128 = -1;
+    loop {
+        let mut page = network
+            .tx_client
+            .get_wallet_activity(Variables {
+                owner: Some(wallet.to_string()),
+                from: Some(from.format(INDEXER_TIME).to_string()),
+                to: Some(to.format(INDEXER_TIME).to_string()),
+                after: Some(after),
+            })
+            .await?
+            .data
+            .map(|data| data.fungible_asset_activities)
+            .unwrap_or_default();
+        let full = page.len() >= INDEXER_PAGE;
+        // The next page starts after the last version, so a version cut by the page
+        // limit is fetched again whole. A version filling the page alone is fetched
+        // by itself.
+        if let Some(last) = page.last().map(|row| row.transaction_version) {
+            if full && page.iter().any(|row| row.transaction_version != last) {
+                page.retain(|row| row.transaction_version != last);
+            } else if full {
+                page = whole_version(network, wallet, &page).await?;
+            }
+        }
+        let last = match page.last() {
+            Some(row) => row.transaction_version,
+            None => break,
+        };
+        rows.extend(decode_page(db, network, wallet, page).await?);
+        after = last;
+        if !full || rows.len() >= MAX_ROWS {
+            break;
+        }
+    }
+    rows.truncate(MAX_ROWS);
+    Ok(rows)
+}
+
+/// Every row of `wallet` in the single version of `page`, which the page limit cut.
+async fn whole_version(
+    network: &Network,
+    wallet: &str,
+    page: &[WalletActivityQueryFungibleAssetActivities],
+) -> Result<Vec<WalletActivityQueryFungibleAssetActivities>, Err> {
+    let first = match page.first() {
+        Some(row) => row,
+        None => return Ok(vec![]),
+    };
+    let symbol = |asset_type: &str| {
+        page.iter()
+            .filter_map(|row| row.metadata.as_ref())
+            .find(|metadata| metadata.asset_type == asset_type)
+            .map_or(asset_type.to_string(), |metadata| metadata.symbol.clone())
+    };
+    Ok(network
+        .tx_client
+        .get_versions_activity(vec![first.transaction_version])
+        .await?
+        .data
+        .map(|data| data.fungible_asset_activities)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|row| row.owner_address.as_deref() == Some(wallet))
+        .enumerate()
+        .map(|(index, row)| WalletActivityQueryFungibleAssetActivities {
+            amount: row.amount,
+            asset_type: row.asset_type,
+            metadata: row.metadata.map(|metadata| {
+                WalletActivityQueryFungibleAssetActivitiesMetadata {
+                    decimals: metadata.decimals,
+                    symbol: symbol(&metadata.asset_type),
+                    asset_type: metadata.asset_type,
+                }
+            }),
+            transaction_version: row.transaction_version,
+            transaction_timestamp: first.transaction_timestamp.clone(),
+            owner_address: row.owner_address,
+            type_: row.type_,
+            event_index: index as i128,
+        })
+        .collect())
+}
+
+/// Prices the rows of one page and finds their counterparties among every change of
+/// their transactions.
+async fn decode_page(
+    db: &DatabaseConnection,
+    network: &Network,
+    wallet: &str,
+    page: Vec<WalletActivityQueryFungibleAssetActivities>,
+) -> Result<Vec<ExportRow>, Err> {
+    let mut versions: Vec<i128> = page.iter().map(|row| row.transaction_version).collect();
+    versions.dedup();
+    let mut stored: AHashMap<(i64, String), Vec<activity::Model>> = AHashMap::new();
+    for entry in Activity::find()
+        .filter(activity::Column::Network.eq(&network.name))
+        .filter(activity::Column::WalletAddress.eq(wallet))
+        .filter(
+            activity::Column::Version.is_in(
+                versions
+                    .iter()
+                    .map(|version| i64::try_from(*version))
+                    .collect::<Result<Vec<i64>, _>>()?,
+            ),
+        )
+        .all(db)
+        .await?
+    {
+        stored
+            .entry((entry.version, entry.asset_type.clone()))
+            .or_default()
+            .push(entry);
+    }
+    let mut by_version: AHashMap<i128, Vec<BalanceChange>> = AHashMap::new();
+    for row in network
+        .tx_client
+        .get_versions_activity(versions)
+        .await?
+        .data
+        .map(|data| data.fungible_asset_activities)
+        .unwrap_or_default()
+    {
+        let change = match (row.metadata, row.owner_address, row.amount) {
+            (Some(metadata), Some(owner), Some(amount)) => BalanceChange::from_event(
+                row.type_,
+                amount,
+                metadata.decimals,
+                AccountAddress::from_str(&owner)?,
+                metadata.asset_type,
+            ),
+            _ => None,
+        };
+        if let Some(change) = change {
+            by_version
+                .entry(row.transaction_version)
+                .or_default()
+                .push(change);
+        }
+    }
+
+    let mut decoded = vec![];
+    for row in page.into_iter() {
+        let timestamp =
+            match NaiveDateTime::parse_from_str(&row.transaction_timestamp, INDEXER_TIME) {
+                Ok(timestamp) => timestamp.and_utc(),
+                Err(_) => continue,
+            };
+        let (metadata, owner, amount) = match (row.metadata, row.owner_address, row.amount) {
+            (Some(metadata), Some(owner), Some(amount)) => (metadata, owner, amount),
+            _ => continue,
+        };
+        if let Some(change) = BalanceChange::from_event(
+            row.type_,
+            amount,
+            metadata.decimals,
+            AccountAddress::from_str(&owner)?,
+            metadata.asset_type,
+        ) {
+            decoded.push((row.transaction_version, timestamp, metadata.symbol, change));
+        }
+    }
+    let mut assets: Vec<String> = decoded
+        .iter()
+        .map(|(.., change)| change.to_token_address())
+        .collect();
+    assets.sort();
+    assets.dedup();
+    let token: TokenMap = TokenInfo::find()
+        .filter(token_info::Column::Mint.is_in(assets))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|entry| (entry.mint.clone(), entry))
+        .collect();
+
+    let mut rows = vec![];
+    for (version, timestamp, symbol, change) in decoded.into_iter() {
+        let version = i64::try_from(version)?;
+        let asset_type = change.to_token_address();
+        let amount = change.to_ui_f64();
+        let all = by_version
+            .get(&i128::from(version))
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        let (usd, usd_at) = match stored
+            .get(&(version, asset_type.clone()))
+            .and_then(|entries| entries.iter().find(|entry| entry.amount == amount))
+        {
+            Some(entry) => (entry.usd, "processed"),
+            None => (change.to_usd_change(&token), "current"),
+        };
+        rows.push(ExportRow {
+            timestamp,
+            version,
+            direction: direction(amount),
+            token: token
+                .get(&asset_type)
+                .and_then(|entry| entry.name.clone())
+                .unwrap_or(symbol),
+            skipped: spam::is_skipped(&token, &asset_type),
+            asset_type,
+            amount: amount.abs(),
+            usd: usd.map(f64::abs),
+            counterparty: change.counterparty(all).map(|owner| format!("{owner:#x}")),
+            usd_at,
+        });
+    }
+    Ok(rows)
+}
+
+pub fn to_csv(rows: &[ExportRow]) -> Result<Vec<u8>, Err> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for row in rows.iter() {
+        writer.serialize(row)?;
+    }
+    writer.into_inner().map_err(|err| Err::Io(err.into_error()))
+}
+
+pub fn to_json(rows: &[ExportRow]) -> Result<Vec<u8>, Err> {
+    Ok(serde_json::to_vec_pretty(rows)?)
+}
@@ -0,0 +1,509 @@
+//! Subscription rules such as `token in [APT, USDC] and usd >= 500 and direction == out`,
+//! checked against every balance change of a transaction.
+//!
+//! Grammar, `and` binds tighter than `or`:
+//! ```text
+//! expr  = and ("or" and)*
+//! and   = not ("and" not)*
+//! not   = "not" not | "(" expr ")" | test
+//! test  = field op value | "token" ["not"] "in" "[" value ("," value)* "]"
+//! field = token | direction | usd | amount
+//! op    = == | != | > | >= | < | <=
+//! ```
+//! `token` is a symbol (any case) or an asset type, quoted when it has type arguments.
+//! `direction` is `in` or `out`. `usd` and `amount` are absolute values, `amount` in
+//! whole tokens, and `usd` tests are false for unpriced tokens. A transaction matches
+//! when any of the wallet's changes does.
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::db::TokenMap;
+use crate::tx::BalanceChange;
+
+/// Why a rule does not parse, `column` counts characters from 1.
+#[derive(Debug, Clone, PartialEq, derive_more::Display, derive_more::Error)]
+#[display("column {column}: {message}")]
+pub struct ExprErr {
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// A parsed rule.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+    /// The token is one of these symbols or asset types.
+    Token(Vec<String>),
+    Direction(Direction),
+    Usd(Cmp, f64),
+    Amount(Cmp, f64),
+}
+
+/// What a rule sees of one balance change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subject {
+    /// Key of the asset in `token_info`.
+    pub asset_type: String,
+    pub symbol: String,
+    /// Signed amount in whole tokens, positive when received.
+    pub amount: f64,
+    /// Signed USD value, `None` when unpriced.
+    pub usd: Option<f64>,
+}
+
+impl Subject {
+    pub fn from_change(change: &BalanceChange, token: &TokenMap) -> Self {
+        let asset_type = change.to_token_address();
+        let symbol = token
+            .get(&asset_type)
+            .and_then(|entry| entry.name.clone())
+            .unwrap_or_else(|| change.to_short_token_address());
+        Subject {
+            asset_type,
+            symbol,
+            amount: change.to_ui_f64(),
+            usd: change.to_usd_change(token),
+        }
+    }
+}
+
+impl Cmp {
+    fn holds(&self, left: f64, right: f64) -> bool {
+        match self {
+            Cmp::Eq => left == right,
+            Cmp::Ne => left != right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+        }
+    }
+}
+
+impl FilterExpr {
+    pub fn parse(text: &str) -> Result<Self, ExprErr> {
+        let mut parser = Parser {
+            tokens: lex(text)?,
+            pos: 0,
+            end: text.chars().count() + 1,
+        };
+        let expr = parser.or()?;
+        match parser.bump() {
+            None => Ok(expr),
+            Some((column, tok)) => Err(err(column, format!("unexpected {tok}"))),
+        }
+    }
+
+    pub fn matches(&self, subject: &Subject) -> bool {
+        match self {
+            FilterExpr::And(left, right) => left.matches(subject) && right.matches(subject),
+            FilterExpr::Or(left, right) => left.matches(subject) || right.matches(subject),
+            FilterExpr::Not(expr) => !expr.matches(subject),
+            FilterExpr::Token(values) => values.iter().any(|value| {
+                value.eq_ignore_ascii_case(&subject.symbol) || *value == subject.asset_type
+            }),
+            FilterExpr::Direction(Direction::In) => subject.amount > 0.0,
+            FilterExpr::Direction(Direction::Out) => subject.amount < 0.0,
+            FilterExpr::Usd(cmp, value) => subject
+                .usd
+                .map_or(false, |usd| cmp.holds(usd.abs(), *value)),
+            FilterExpr::Amount(cmp, value) => cmp.holds(subject.amount.abs(), *value),
+        }
+    }
+
+    /// Whether any of the changes of a transaction matches.
+    pub fn matches_any(&self, changes: &[&BalanceChange], token: &TokenMap) -> bool {
+        changes
+            .iter()
+            .any(|change| self.matches(&Subject::from_change(change, token)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Word(String),
+    Text(String),
+    Num(f64),
+    Op(Cmp),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Word(word) => write!(f, "{word}"),
+            Tok::Text(text) => write!(f, "\"{text}\""),
+            Tok::Num(num) => write!(f, "{num}"),
+            Tok::Op(cmp) => write!(
+                f,
+                "{}",
+                match cmp {
+                    Cmp::Eq => "==",
+                    Cmp::Ne => "!=",
+                    Cmp::Gt => ">",
+                    Cmp::Ge => ">=",
+                    Cmp::Lt => "<",
+                    Cmp::Le => "<=",
+                }
+            ),
+            Tok::LParen => write!(f, "("),
+            Tok::RParen => write!(f, ")"),
+            Tok::LBracket => write!(f, "["),
+            Tok::RBracket => write!(f, "]"),
+            Tok::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn err(column: usize, message: impl Into<String>) -> ExprErr {
+    ExprErr {
+        column,
+        message: message.into(),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '.' | '$')
+}
+
+/// Tokens with the column they start at.
+fn lex(text: &str) -> Result<Vec<(usize, Tok)>, ExprErr> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (tok, len) = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => (Tok::LParen, 1),
+            ')' => (Tok::RParen, 1),
+            '[' => (Tok::LBracket, 1),
+            ']' => (Tok::RBracket, 1),
+            ',' => (Tok::Comma, 1),
+            '=' if next == Some('=') => (Tok::Op(Cmp::Eq), 2),
+            '!' if next == Some('=') => (Tok::Op(Cmp::Ne), 2),
+            '>' if next == Some('=') => (Tok::Op(Cmp::Ge), 2),
+            '<' if next == Some('=') => (Tok::Op(Cmp::Le), 2),
+            '>' => (Tok::Op(Cmp::Gt), 1),
+            '<' => (Tok::Op(Cmp::Lt), 1),
+            '"' | '\'' => {
+                let len = match chars[i + 1..].iter().position(|d| *d == c) {
+                    Some(len) => len,
+                    None => return Err(err(column, "unterminated string")),
+                };
+                let text: String = chars[i + 1..i + 1 + len].iter().collect();
+                (Tok::Text(text), len + 2)
+            }
+            c if is_word_char(c) => {
+                let len = chars[i..].iter().take_while(|d| is_word_char(**d)).count();
+                let word: String = chars[i..i + len].iter().collect();
+                let number = word.strip_prefix('$').unwrap_or(&word).replace('_', "");
+                let numeric = c.is_ascii_digit() || c == '.' || c == '$';
+                match f64::from_str(&number) {
+                    Ok(num) if numeric && num.is_finite() => (Tok::Num(num), len),
+                    _ if numeric && !word.starts_with("0x") => {
+                        return Err(err(column, format!("{word} is not a number")))
+                    }
+                    _ => (Tok::Word(word), len),
+                }
+            }
+            '=' => return Err(err(column, "use == to compare")),
+            c => return Err(err(column, format!("unexpected {c}"))),
+        };
+        tokens.push((column, tok));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Tok)>,
+    pos: usize,
+    /// Column reported for errors at the end of the input.
+    end: usize,
+}
+
+impl Parser {
+    fn bump(&mut self) -> Option<(usize, Tok)> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|(_, tok)| tok)
+    }
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(column, _)| *column)
+    }
+    fn eat(&mut self, tok: &Tok) -> bool {
+        match self.peek() == Some(tok) {
+            true => {
+                self.pos += 1;
+                true
+            }
+            false => false,
+        }
+    }
+    fn eat_word(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Some(Tok::Word(w)) if w.eq_ignore_ascii_case(word) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+    fn expect(&mut self, tok: &Tok) -> Result<(), ExprErr> {
+        match self.eat(tok) {
+            true => Ok(()),
+            false => Err(err(self.column(), format!("expected {tok}"))),
+        }
+    }
+
+    fn or(&mut self) -> Result<FilterExpr, ExprErr> {
+        let mut left = self.and()?;
+        while self.eat_word("or") {
+            left = FilterExpr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+    fn and(&mut self) -> Result<FilterExpr, ExprErr> {
+        let mut left = self.not()?;
+        while self.eat_word("and") {
+            left = FilterExpr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+    fn not(&mut self) -> Result<FilterExpr, ExprErr> {
+        if self.eat_word("not") {
+            return Ok(FilterExpr::Not(Box::new(self.not()?)));
+        }
+        if self.eat(&Tok::LParen) {
+            let expr = self.or()?;
+            self.expect(&Tok::RParen)?;
+            return Ok(expr);
+        }
+        self.test()
+    }
+    fn test(&mut self) -> Result<FilterExpr, ExprErr> {
+        let (column, field) = match self.bump() {
+            Some((column, Tok::Word(word))) => (column, word.to_lowercase()),
+            Some((column, tok)) => {
+                return Err(err(column, format!("expected a field, found {tok}")))
+            }
+            None => return Err(err(self.end, "expected a field")),
+        };
+        match field.as_str() {
+            "token" => {
+                if self.eat_word("in") {
+                    return Ok(FilterExpr::Token(self.list()?));
+                }
+                if self.eat_word("not") {
+                    if !self.eat_word("in") {
+                        return Err(err(self.column(), "expected in"));
+                    }
+                    return Ok(FilterExpr::Not(Box::new(FilterExpr::Token(self.list()?))));
+                }
+                let (op_column, cmp) = self.cmp()?;
+                let token = FilterExpr::Token(vec![self.token()?]);
+                match cmp {
+                    Cmp::Eq => Ok(token),
+                    Cmp::Ne => Ok(FilterExpr::Not(Box::new(token))),
+                    _ => Err(err(op_column, "token only takes ==, != and in")),
+                }
+            }
+            "direction" => {
+                let (op_column, cmp) = self.cmp()?;
+                let value_column = self.column();
+                let direction = match self.bump() {
+                    Some((_, Tok::Word(word))) if word.eq_ignore_ascii_case("in") => Direction::In,
+                    Some((_, Tok::Word(word))) if word.eq_ignore_ascii_case("out") => {
+                        Direction::Out
+                    }
+                    _ => return Err(err(value_column, "direction is in or out")),
+                };
+                match cmp {
+                    Cmp::Eq => Ok(FilterExpr::Direction(direction)),
+                    Cmp::Ne => Ok(FilterExpr::Not(Box::new(FilterExpr::Direction(direction)))),
+                    _ => Err(err(op_column, "direction only takes == and !=")),
+                }
+            }
+            "usd" | "amount" => {
+                let (_, cmp) = self.cmp()?;
+                let value = match self.bump() {
+                    Some((_, Tok::Num(num))) => num,
+                    Some((column, tok)) => {
+                        return Err(err(column, format!("{field} needs a number, found {tok}")))
+                    }
+                    None => return Err(err(self.end, format!("{field} needs a number"))),
+                };
+                match field.as_str() {
+                    "usd" => Ok(FilterExpr::Usd(cmp, value)),
+                    _ => Ok(FilterExpr::Amount(cmp, value)),
+                }
+            }
+            _ => Err(err(
+                column,
+                format!("unknown field {field}, expected token, direction, usd or amount"),
+            )),
+        }
+    }
+    fn cmp(&mut self) -> Result<(usize, Cmp), ExprErr> {
+        match self.bump() {
+            Some((column, Tok::Op(cmp))) => Ok((column, cmp)),
+            Some((column, tok)) => Err(err(column, format!("expected a comparison, found {tok}"))),
+            None => Err(err(self.end, "expected a comparison")),
+        }
+    }
+    /// A symbol or asset type, plain addresses in the form the indexer prints.
+    fn token(&mut self) -> Result<String, ExprErr> {
+        match self.bump() {
+            Some((_, Tok::Word(word) | Tok::Text(word))) => {
+                Ok(match AccountAddress::from_str(&word) {
+                    Ok(address) => address.to_string(),
+                    Err(_) => word,
+                })
+            }
+            Some((column, tok)) => Err(err(column, format!("expected a token, found {tok}"))),
+            None => Err(err(self.end, "expected a token")),
+        }
+    }
+    fn list(&mut self) -> Result<Vec<String>, ExprErr> {
+        self.expect(&Tok::LBracket)?;
+        let mut values = vec![self.token()?];
+        while self.eat(&Tok::Comma) {
+            values.push(self.token()?);
+        }
+        self.expect(&Tok::RBracket)?;
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cmp, Direction, ExprErr, FilterExpr, Subject};
+
+    const APT: &str = "0x1::aptos_coin::AptosCoin";
+
+    fn subject(symbol: &str, amount: f64, usd: Option<f64>) -> Subject {
+        Subject {
+            asset_type: match symbol {
+                "APT" => APT.to_string(),
+                _ => format!("0x1::test::{symbol}"),
+            },
+            symbol: symbol.to_string(),
+            amount,
+            usd,
+        }
+    }
+
+    fn column(text: &str) -> usize {
+        match FilterExpr::parse(text) {
+            Ok(expr) => panic!("{text} parsed as {expr:?}"),
+            Err(ExprErr { column, .. }) => column,
+        }
+    }
+
+    #[test]
+    fn parses_the_example_rule() {
+        let expr = FilterExpr::parse("token in [APT, USDC] and usd >= 500 and direction == out")
+            .expect("parse");
+        assert_eq!(
+            expr,
+            FilterExpr::And(
+                Box::new(FilterExpr::And(
+                    Box::new(FilterExpr::Token(vec!["APT".into(), "USDC".into()])),
+                    Box::new(FilterExpr::Usd(Cmp::Ge, 500.0)),
+                )),
+                Box::new(FilterExpr::Direction(Direction::Out)),
+            )
+        );
+        assert!(expr.matches(&subject("usdc", -600.0, Some(-600.0))));
+        assert!(!expr.matches(&subject("USDC", 600.0, Some(600.0))));
+        assert!(!expr.matches(&subject("APT", -1.0, Some(-499.0))));
+        assert!(!expr.matches(&subject("WETH", -10.0, Some(-30_000.0))));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let expr = FilterExpr::parse("token == APT or amount > 10 and direction == in").unwrap();
+        assert!(expr.matches(&subject("APT", -1.0, None)));
+        assert!(expr.matches(&subject("USDC", 11.0, None)));
+        assert!(!expr.matches(&subject("USDC", -11.0, None)));
+
+        let grouped =
+            FilterExpr::parse("(token == APT or amount > 10) and direction == in").unwrap();
+        assert!(!grouped.matches(&subject("APT", -1.0, None)));
+    }
+
+    #[test]
+    fn negations_and_asset_types() {
+        let expr = FilterExpr::parse(&format!("not token in [{APT}] and token != usdt")).unwrap();
+        assert!(!expr.matches(&subject("APT", 1.0, None)));
+        assert!(!expr.matches(&subject("USDT", 1.0, None)));
+        assert!(expr.matches(&subject("USDC", 1.0, None)));
+
+        let expr = FilterExpr::parse("token not in ['0x1::lp::LP<0x1::a::A, 0x1::b::B>']").unwrap();
+        assert!(expr.matches(&subject("APT", 1.0, None)));
+    }
+
+    #[test]
+    fn usd_tests_fail_on_unpriced_tokens() {
+        let expr = FilterExpr::parse("usd < 1").unwrap();
+        assert!(!expr.matches(&subject("SCAM", 1_000.0, None)));
+        assert!(expr.matches(&subject("APT", -0.01, Some(-0.5))));
+        let expr = FilterExpr::parse("amount >= $1_000").unwrap();
+        assert!(expr.matches(&subject("APT", -1_000.0, None)));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_column() {
+        assert_eq!(column(""), 1);
+        assert_eq!(column("price > 5"), 1);
+        assert_eq!(column("usd >= lots"), 8);
+        assert_eq!(column("usd = 5"), 5);
+        assert_eq!(column("token > APT"), 7);
+        assert_eq!(column("direction == sideways"), 14);
+        assert_eq!(column("token in [APT, USDC"), 20);
+        assert_eq!(column("(usd > 5"), 9);
+        assert_eq!(column("usd > 5 usd"), 9);
+        assert_eq!(column("token == 'APT"), 10);
+
+        let err = FilterExpr::parse("price > 5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "column 1: unknown field price, expected token, direction, usd or amount"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Stored activity of tracked wallets. Every balance change of a tracked wallet is kept
+//! in `activity` with its USD value when processed, `/history` pages through it.
+
+use chrono::{DateTime, Utc};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use crate::entities::{activity, prelude::*, wallet_tracked};
+use crate::error::Err;
+use crate::process_new_block::WalletActivity;
+
+/// Entries per page when `/history` is not given a count.
+pub const PAGE: u64 = 10;
+pub const MAX_PAGE: u64 = 50;
+
+/// Stores the activity of block `block`, replacing the rows of an earlier pass over
+/// the same block.
+pub async fn record(
+    db: &DatabaseConnection,
+    network: &str,
+    block: u64,
+    timestamp: DateTime<Utc>,
+    activities: &[WalletActivity],
+) -> Result<(), Err> {
+    if activities.is_empty() {
+        return Ok(());
+    }
+    let block = i64::try_from(block)?;
+    Activity::delete_many()
+        .filter(activity::Column::Network.eq(network))
+        .filter(activity::Column::Block.eq(block))
+        .exec(db)
+        .await?;
+    let mut rows = Vec::with_capacity(activities.len());
+    for activity in activities.iter() {
+        rows.push(activity::ActiveModel {
+            network: Set(network.to_string()),
+            wallet_address: Set(activity.wallet.clone()),
+            block: Set(block),
+            version: Set(i64::try_from(activity.version)?),
+            timestamp: Set(timestamp),
+            asset_type: Set(activity.change.asset_type.clone()),
+            symbol: Set(activity.change.symbol.clone()),
+            amount: Set(activity.change.amount),
+            usd: Set(activity.change.usd),
+            counterparty: Set(activity.counterparty.clone()),
+            ..Default::default()
+        });
+    }
+    Activity::insert_many(rows).exec(db).await?;
+    Ok(())
+}
+
+/// Up to `limit` entries of the wallet of `subscription`, newest first, skipping the
+/// `offset` newest. The flag tells whether older entries remain.
+pub async fn page(
+    db: &DatabaseConnection,
+    subscription: &wallet_tracked::Model,
+    offset: u64,
+    limit: u64,
+) -> Result<(Vec<activity::Model>, bool), Err> {
+    let mut entries = Activity::find()
+        .filter(activity::Column::Network.eq(&subscription.network))
+        .filter(activity::Column::WalletAddress.eq(&subscription.wallet_address))
+        .order_by_desc(activity::Column::Version)
+        .order_by_asc(activity::Column::Id)
+        .offset(offset)
+        .limit(limit + 1)
+        .all(db)
+        .await?;
+    let more = entries.len() as u64 > limit;
+    entries.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+    Ok((entries, more))
+}
+
+pub fn history_text(
+    subscription: &wallet_tracked::Model,
+    entries: &[activity::Model],
+    offset: u64,
+) -> String {
+    let name = subscription.clone().display_name();
+    if entries.is_empty() {
+        return match offset {
+            0 => format!(
+                "No activity stored for {name} on {} yet",
+                subscription.network
+            ),
+            _ => format!("No older activity for {name}"),
+        };
+    }
+    let mut text = format!(
+        "History of {name} on {}, {} to {}\n",
+        subscription.network,
+        offset + 1,
+        offset + entries.len() as u64
+    );
+    for entry in entries.iter() {
+        let usd = match entry.usd {
+            Some(usd) => format!(" (${usd:.2})"),
+            None => String::new(),
+        };
+        text.push_str(&format!(
+            "\n{} {} {:+.4}{usd} at version {}",
+            entry.timestamp.format("%Y-%m-%d %H:%M"),
+            entry.symbol,
+            entry.amount,
+            entry.version
+        ));
+    }
+    text
+}
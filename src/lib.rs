@@ -0,0 +1,73 @@
+//! Wallet activity tracking for Aptos.
+//!
+//! The pieces other services reuse:
+//! - fetching: [`TxQueryClient`] queries `fungible_asset_activities` from one or more
+//!   indexers, [`rpc::RpcPool`] reads blocks from a pool of fullnodes;
+//! - decoding: [`BalanceChange::from_events`] turns the indexer rows of a transaction
+//!   into signed balance changes, [`Decimal`] prints them;
+//! - filtering: [`Filter`] narrows changes down to one wallet and
+//!   [`process_new_block::build_notifications`] matches a block against subscriptions;
+//! - block claiming: [`process_new_block::get_block_to_process`] hands out heights to
+//!   workers sharing one `processed_block` table.
+//!
+//! The Telegram bot in `main.rs` is a binary on top of this crate. Without the
+//! `telegram` feature it runs headless and delivers alerts through [`webhook`].
+
+/// Maintenance operations of the admin binary.
+pub mod admin;
+/// HTTP API over subscriptions and tracked wallets.
+pub mod api;
+/// Current holdings of a wallet and `/balance`.
+pub mod balance;
+/// Chain head polling.
+pub mod block;
+/// Telegram dialogue and commands.
+#[cfg(feature = "telegram")]
+pub mod bot;
+pub mod callback;
+pub mod config;
+/// Queries over the entities.
+pub mod db;
+pub mod db_update_loop;
+/// Daily and weekly digests.
+pub mod digest;
+#[cfg(all(test, feature = "telegram"))]
+mod e2e;
+pub mod entities;
+pub mod error;
+/// CSV and JSON exports of wallet activity.
+pub mod export;
+/// Subscription rule language.
+pub mod filter_expr;
+pub mod health_check_loop;
+/// Stored wallet activity and `/history`.
+pub mod history;
+pub mod metrics;
+pub mod migration;
+/// Per network clients and loops.
+pub mod network;
+/// Delivery of alerts to Telegram and the notification log.
+pub mod notifier;
+pub mod probe;
+/// Block claiming and alert building.
+pub mod process_new_block;
+mod process_new_tx;
+/// Generated indexer GraphQL types.
+pub mod query;
+/// Muted subscriptions and quiet hours.
+pub mod quiet;
+/// Block fixtures recorded from production and replayed offline.
+pub mod replay;
+/// Fullnode pool with failover.
+pub mod rpc;
+pub mod shadow;
+/// Skipped, spam and dust token suppression.
+pub mod spam;
+// mod token;
+/// Indexer client and balance change decoding.
+pub mod tx;
+/// Alerts and health reports as JSON POSTs.
+pub mod webhook;
+
+pub use error::Err;
+pub use tx::{BalanceChange, Decimal, Filter, Token, TxQueryClient};
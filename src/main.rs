@@ -1,126 +1,152 @@
-use crate::db_update_loop::db_loop;
-
-use crate::block::update_block_loop;
-use crate::entities::token_info;
-use crate::health_check_loop::health_check_loop;
-use crate::process_new_block::handle_new_block_loop;
-use health_check_loop::db_check;
+use aptos_tracking::api::api_loop;
+#[cfg(feature = "telegram")]
+use aptos_tracking::bot;
+use aptos_tracking::config::Config;
+use aptos_tracking::db::{seed_subscriptions, TokenMap};
+use aptos_tracking::db_update_loop::token_refresh_loop;
+use aptos_tracking::digest::digest_loop;
+use aptos_tracking::entities::token_info;
+use aptos_tracking::network::{network_loop, Networks};
+use aptos_tracking::notifier::Notifier;
+use aptos_tracking::probe::Liveness;
+use aptos_tracking::quiet::quiet_hours_loop;
+use aptos_tracking::webhook::Webhook;
+use aptos_tracking::{metrics, migration, replay, shadow};
 use reqwest::Client as rqwClient;
-use std::{sync::Arc, time::Duration};
-use tx::TxQueryClient;
-// use crate::token::{passive_update_price_feed, update_token_list};
+use std::sync::Arc;
+use std::time::Duration;
+// use aptos_tracking::token::{passive_update_price_feed, update_token_list};
 use ahash::{AHashMap, AHashSet};
 use aptos_sdk::move_types::account_address::AccountAddress;
-use aptos_sdk::rest_client::Client;
-use db::TokenMap;
 use futures::future::{self, pending};
 use log::*;
 use sea_orm::{ConnectOptions, Database};
-use std::str::FromStr;
-use teloxide::prelude::*;
+#[cfg(feature = "telegram")]
+use teloxide::Bot;
 use tokio::sync::{mpsc, RwLock};
-use url::Url;
-mod block;
-mod bot;
-mod callback;
-mod db;
-mod db_update_loop;
-mod entities;
-mod error;
-mod fetch_loop;
-mod health_check_loop;
-mod process_new_block;
-mod process_new_tx;
-mod query;
-mod rpc;
-// mod token;
-mod tx;
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
     pretty_env_logger::init_timed();
     info!("start");
-    let mut opt = ConnectOptions::new(
-        std::env::var("DATABASE_URL")
-            .expect("no DATABASE_URL in .env")
-            .to_owned(),
-    );
-    opt.sqlx_logging(false);
-    opt.max_connections(50);
+    if std::env::args().nth(1).as_deref() == Some("replay") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        replay::run(&args).expect("replay error");
+        return;
+    }
+    if std::env::args().nth(1).as_deref() == Some("diff") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        shadow::run(&args).expect("diff error");
+        return;
+    }
+    let mut config = Config::load().expect("config error");
+    if config.shadow.enabled {
+        // Health reports would reach the production report chat too.
+        config.telegram.report_chat = None;
+    }
+    let config = Arc::new(config);
+    let mut opt = ConnectOptions::new(config.database.url.clone());
+    opt.sqlx_logging(config.database.sqlx_logging);
+    opt.max_connections(config.database.max_connections);
     let db = Arc::new(Database::connect(opt).await.expect("db connect error"));
-    let bot = Bot::from_env();
-    let rpc_url = std::env::var("RPC_URL")
-        .expect("no RPC_URL in .env")
-        .to_owned()
-        .to_string();
-    let rpc_connection = Arc::new(Client::new(
-        Url::parse(&rpc_url).expect("RPC url parse error"),
-    ));
-
-    let back_up_rpc = std::env::var("BLOCK_RPC_URL")
-        .unwrap_or(rpc_url)
-        .to_owned()
-        .to_string();
-    let back_up_rpc_connection = Arc::new(Client::new(
-        Url::parse(&back_up_rpc).expect("RPC url parse error"),
-    ));
-    let gq_url = std::env::var("GRAPHQL_URL")
-        .expect("Graphql url needed")
-        .to_owned()
-        .to_string();
-    let rqw_client = Arc::new(rqwClient::new());
-    let tx_client = TxQueryClient {
-        client: rqw_client,
-        url: gq_url,
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        migration::prepare(db.as_ref(), true)
+            .await
+            .expect("migration error");
+        info!("schema up to date");
+        return;
+    }
+    migration::prepare(db.as_ref(), config.database.auto_migrate)
+        .await
+        .expect("schema check error");
+    let bookkeeping = match config.shadow.bookkeeping_url.clone() {
+        Some(url) => {
+            let mut opt = ConnectOptions::new(url);
+            opt.sqlx_logging(config.database.sqlx_logging);
+            let bookkeeping = Database::connect(opt)
+                .await
+                .expect("bookkeeping db connect error");
+            migration::prepare(&bookkeeping, true)
+                .await
+                .expect("bookkeeping migration error");
+            Arc::new(bookkeeping)
+        }
+        None => db.clone(),
     };
+    // A shadow shares production's subscriptions, it must not add to them.
+    if !config.shadow.enabled {
+        seed_subscriptions(db.as_ref(), &config)
+            .await
+            .expect("config subscriptions error");
+    }
+    let rqw_client = Arc::new(rqwClient::new());
+    let log = config.shadow.log.as_deref().map(|path| {
+        Arc::new(shadow::NotificationLog::open(path).expect("notification log open error"))
+    });
+    let mut notifier = Notifier::new(log);
+    match Webhook::from_config(rqw_client.clone(), &config.webhook) {
+        _ if config.shadow.enabled => {
+            info!("shadow mode, alerts are only logged");
+            notifier = notifier.in_shadow_mode();
+        }
+        Some(webhook) => notifier = notifier.with_webhook(webhook),
+        None => {}
+    }
+    #[cfg(feature = "telegram")]
+    let bot = Bot::new(config.telegram.token.clone());
+    #[cfg(feature = "telegram")]
+    if config.telegram_enabled() {
+        notifier = notifier
+            .with_telegram(bot.clone(), config.telegram.report_chat)
+            .with_burst_window(Duration::from_secs(config.telegram.burst_window_secs));
+    }
+    let networks = Networks::connect(&config, rqw_client)
+        .await
+        .expect("network config error");
+    #[cfg_attr(not(feature = "telegram"), allow(unused_variables))]
     let (abortable, abort_handle) = future::abortable(pending::<()>());
     let new_token: Arc<RwLock<AHashSet<AccountAddress>>> = Arc::new(RwLock::new(AHashSet::new()));
+    let liveness = Liveness::new();
     let tg_loop = async {
-        bot::run(
-            bot.clone(),
-            db.clone(),
-            rpc_connection.clone(),
-            tx_client.clone(),
-            new_token.clone(),
-        )
-        .await;
-        abort_handle.abort();
+        #[cfg(feature = "telegram")]
+        if config.telegram_enabled() {
+            bot::run(
+                bot.clone(),
+                db.clone(),
+                networks.clone(),
+                new_token.clone(),
+                config.telegram.report_chat,
+            )
+            .await;
+            liveness.stop("telegram").await;
+            abort_handle.abort();
+            return;
+        }
+        info!("telegram disabled, running headless");
+        future::pending::<()>().await
     };
-    let now_block = rpc_connection
-        .get_ledger_information()
-        .await
-        .expect("RPC get slot error")
-        .inner()
-        .block_height;
-    let current_block = Arc::new(RwLock::new(now_block));
     let token_map = Arc::new(RwLock::new(
         token_info::Model::get_token_hashmap(db.as_ref())
             .await
             .expect("token info fetch failed"),
     ));
+    metrics::set_gauge(&metrics::TOKEN_MAP_SIZE, token_map.read().await.len());
     let updated_token_map: Arc<RwLock<TokenMap>> = Arc::new(RwLock::new(AHashMap::new()));
     let (new_price_update_tx, new_price_update_rx) = mpsc::channel::<(u64, Vec<u8>)>(5000);
-    let current = current_block.read().await;
-    db_check(rpc_connection.clone(), &db, current.clone())
-        .await
-        .expect("DB check error");
-    drop(current);
-    let main_thread_count =
-        u8::from_str(&std::env::var("THREAD").expect("no THREAD in .env")).unwrap_or(3);
     tokio::join!(
         tg_loop,
-        handle_new_block_loop(
+        future::join_all(networks.iter().map(|network| network_loop(
             abortable.clone(),
+            network.clone(),
             db.clone(),
-            rpc_connection.clone(),
-            tx_client.clone(),
-            current_block.clone(),
-            bot.clone(),
-            main_thread_count,
+            bookkeeping.clone(),
+            notifier.clone(),
+            config.clone(),
             token_map.clone(),
             new_token.clone(),
-            new_price_update_tx
-        ),
+            new_price_update_tx.clone(),
+            liveness.clone(),
+        ))),
         // f_loop(
         //     abortable.clone(),
         //     db.clone(),
@@ -128,12 +154,22 @@ async fn main() {
         //     tx_tx.clone(),
         //     rpc_connection.clone()
         // ),
-        db_loop(abortable.clone(), db.clone()),
-        update_block_loop(
+        api_loop(
             abortable.clone(),
-            current_block.clone(),
-            back_up_rpc_connection.clone(),
+            db.clone(),
+            bookkeeping.clone(),
+            networks.clone(),
+            liveness.clone(),
+            config.clone()
         ),
+        token_refresh_loop(
+            abortable.clone(),
+            db.clone(),
+            token_map.clone(),
+            Duration::from_secs(config.spam.refresh_secs)
+        ),
+        quiet_hours_loop(abortable.clone(), db.clone(), notifier.clone()),
+        digest_loop(abortable.clone(), db.clone(), notifier.clone()),
         // update_token_list(
         //     abortable.clone(),
         //     db.clone(),
@@ -142,13 +178,6 @@ async fn main() {
         //     new_token.clone(),
         //     rpc_connection.clone(),
         // ),
-        health_check_loop(
-            abortable.clone(),
-            rpc_connection.clone(),
-            current_block.clone(),
-            db.clone(),
-            bot.clone()
-        ),
         // passive_update_price_feed(
         //     token_map.clone(),
         //     updated_token_map.clone(),
@@ -0,0 +1,135 @@
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use log::error;
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, Encoder, Histogram, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    TextEncoder,
+};
+
+pub static BLOCKS_PROCESSED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tracking_blocks_processed_total",
+        "Blocks processed by each worker",
+        &["network", "worker"]
+    )
+    .unwrap()
+});
+pub static CURRENT_BLOCK: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "tracking_current_block",
+        "Latest block height seen on RPC",
+        &["network"]
+    )
+    .unwrap()
+});
+pub static LAST_PROCESSED_BLOCK: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "tracking_last_processed_block",
+        "Highest block with every block below it processed",
+        &["network"]
+    )
+    .unwrap()
+});
+pub static HEAD_LAG: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "tracking_head_lag_blocks",
+        "Blocks between the chain head and the last processed block",
+        &["network"]
+    )
+    .unwrap()
+});
+pub static INDEXER_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "tracking_indexer_query_duration_seconds",
+        "Indexer graphql query latency"
+    )
+    .unwrap()
+});
+pub static INDEXER_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("tracking_indexer_errors_total", "Failed indexer queries").unwrap()
+});
+pub static RPC_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tracking_rpc_errors_total",
+        "Failed fullnode RPC calls",
+        &["network", "call"]
+    )
+    .unwrap()
+});
+pub static NOTIFICATIONS_SENT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("tracking_notifications_sent_total", "Alerts delivered").unwrap()
+});
+pub static NOTIFICATIONS_COALESCED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "tracking_notifications_coalesced_total",
+        "Alerts added to the previous message of a burst"
+    )
+    .unwrap()
+});
+pub static NOTIFICATIONS_FAILED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tracking_notifications_failed_total",
+        "Alerts that could not be delivered",
+        &["reason"]
+    )
+    .unwrap()
+});
+pub static NOTIFICATIONS_HELD: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tracking_notifications_held_total",
+        "Alerts dropped for a muted subscription, or held for a digest or quiet hours",
+        &["reason"]
+    )
+    .unwrap()
+});
+pub static CHANGES_SUPPRESSED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tracking_changes_suppressed_total",
+        "Balance changes left out of alerts as skipped, spam or dust tokens",
+        &["network"]
+    )
+    .unwrap()
+});
+pub static RULE_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tracking_rule_errors_total",
+        "Subscriptions left without alerts for a block because their stored rule does not parse",
+        &["network"]
+    )
+    .unwrap()
+});
+pub static TRACKED_WALLETS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!("tracking_wallets", "Unique wallets tracked", &["network"]).unwrap()
+});
+pub static TOKEN_MAP_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("tracking_token_map_size", "Tokens in the price map").unwrap()
+});
+
+pub fn set_gauge<T: TryInto<i64>>(gauge: &IntGauge, value: T) {
+    gauge.set(value.try_into().unwrap_or(i64::MAX));
+}
+
+/// Recomputed whenever either side of the lag moves.
+pub fn update_head_lag(network: &str) {
+    let current = CURRENT_BLOCK.with_label_values(&[network]).get();
+    let last = LAST_PROCESSED_BLOCK.with_label_values(&[network]).get();
+    HEAD_LAG
+        .with_label_values(&[network])
+        .set(current.saturating_sub(last).max(0));
+}
+
+pub async fn metrics_handler() -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        error!("metrics encode error: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+    (
+        [(header::CONTENT_TYPE, encoder.format_type().to_string())],
+        buffer,
+    )
+        .into_response()
+}
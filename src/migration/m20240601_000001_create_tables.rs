@@ -0,0 +1,303 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+/// The schema as it was kept in `db.sql`, tables that already exist are left alone.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres and SQLite get their tables from `m20241028_000012_portable_tables`.
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        manager
+            .create_table(
+                Table::create()
+                    .table(User::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(User::UserId)
+                            .big_unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(User::UserName).string_len(20).null())
+                    .col(
+                        ColumnDef::new(User::CreateTime)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WalletTracked::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WalletTracked::WalletId)
+                            .big_unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::ChatId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::UserId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::WalletAddress)
+                            .char_len(70)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::Nickname)
+                            .string_len(20)
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::TrackType)
+                            .enumeration(
+                                Alias::new("track_type"),
+                                ["full", "sent", "balance", "receive"].map(Alias::new),
+                            )
+                            .not_null()
+                            .default("full"),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::CreateTime)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(WalletTracked::MinimumValue)
+                            .double()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .index(
+                        Index::create()
+                            .name("chat_id")
+                            .unique()
+                            .col(WalletTracked::ChatId)
+                            .col(WalletTracked::WalletAddress),
+                    )
+                    .index(
+                        Index::create()
+                            .name("address")
+                            .col(WalletTracked::WalletAddress),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(TokenInfo::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TokenInfo::Mint)
+                            .string_len(70)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TokenInfo::Name).string_len(70).null())
+                    .col(
+                        ColumnDef::new(TokenInfo::Value)
+                            .double()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .col(
+                        ColumnDef::new(TokenInfo::Decimal)
+                            .tiny_unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(TokenInfo::IsSkipped)
+                            .tiny_unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(TokenInfo::UpdateTime)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp())
+                            .extra("ON UPDATE CURRENT_TIMESTAMP"),
+                    )
+                    .col(
+                        ColumnDef::new(TokenInfo::CreatTime)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(TokenInfo::LastUpdatedBlock)
+                            .big_unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .index(Index::create().name("is_skipped").col(TokenInfo::IsSkipped))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProcessedBlock::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProcessedBlock::Block)
+                            .big_unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::ProcessedTime)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::LastTxSig)
+                            .string_len(100)
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::Modulo)
+                            .tiny_unsigned()
+                            .not_null()
+                            .default(1),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::Remainder)
+                            .tiny_unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::Status)
+                            .enumeration(
+                                Alias::new("status"),
+                                ["complete", "processing", "error", "skipped"].map(Alias::new),
+                            )
+                            .not_null()
+                            .default("processing"),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::Place)
+                            .enumeration(
+                                Alias::new("place"),
+                                ["head", "bottom", "old"].map(Alias::new),
+                            )
+                            .not_null()
+                            .default("head"),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedBlock::TxCount)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .index(
+                        Index::create()
+                            .name("modulo_remainder_index")
+                            .col(ProcessedBlock::Modulo)
+                            .col(ProcessedBlock::Remainder),
+                    )
+                    .index(
+                        Index::create()
+                            .name("place_index")
+                            .col(ProcessedBlock::Place),
+                    )
+                    .index(
+                        Index::create()
+                            .name("place_modulo_remainder_index")
+                            .col(ProcessedBlock::Place)
+                            .col(ProcessedBlock::Modulo)
+                            .col(ProcessedBlock::Remainder),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres and SQLite get their tables from `m20241028_000012_portable_tables`.
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        for table in [
+            ProcessedBlock::Table.into_iden(),
+            TokenInfo::Table.into_iden(),
+            WalletTracked::Table.into_iden(),
+            User::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).if_exists().to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    UserId,
+    UserName,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    WalletId,
+    ChatId,
+    UserId,
+    WalletAddress,
+    Nickname,
+    TrackType,
+    CreateTime,
+    MinimumValue,
+}
+
+#[derive(DeriveIden)]
+enum TokenInfo {
+    Table,
+    Mint,
+    Name,
+    Value,
+    Decimal,
+    IsSkipped,
+    UpdateTime,
+    CreatTime,
+    LastUpdatedBlock,
+}
+
+#[derive(DeriveIden)]
+enum ProcessedBlock {
+    Table,
+    Block,
+    ProcessedTime,
+    LastTxSig,
+    Modulo,
+    Remainder,
+    Status,
+    Place,
+    TxCount,
+}
@@ -0,0 +1,170 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+/// Subscriptions and processed blocks belong to a network, existing rows are mainnet.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres and SQLite get their tables from `m20241028_000012_portable_tables`.
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        // Databases upgraded by hand from the old `db.sql` already have the column.
+        if !manager.has_column("wallet_tracked", "network").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WalletTracked::Table)
+                        .add_column(network_column())
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name("chat_id")
+                        .table(WalletTracked::Table)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name("chat_id")
+                        .table(WalletTracked::Table)
+                        .unique()
+                        .col(WalletTracked::ChatId)
+                        .col(WalletTracked::WalletAddress)
+                        .col(WalletTracked::Network)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name("address")
+                        .table(WalletTracked::Table)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name("address")
+                        .table(WalletTracked::Table)
+                        .col(WalletTracked::Network)
+                        .col(WalletTracked::WalletAddress)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_column("processed_block", "network").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ProcessedBlock::Table)
+                        .add_column(network_column())
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .get_connection()
+                .execute_unprepared(
+                    "ALTER TABLE `processed_block` DROP PRIMARY KEY, ADD PRIMARY KEY (`network`, `block`)",
+                )
+                .await?;
+            for (name, cols) in [
+                (
+                    "modulo_remainder_index",
+                    vec![ProcessedBlock::Modulo, ProcessedBlock::Remainder],
+                ),
+                ("place_index", vec![ProcessedBlock::Place]),
+                (
+                    "place_modulo_remainder_index",
+                    vec![
+                        ProcessedBlock::Place,
+                        ProcessedBlock::Modulo,
+                        ProcessedBlock::Remainder,
+                    ],
+                ),
+            ] {
+                manager
+                    .drop_index(
+                        Index::drop()
+                            .name(name)
+                            .table(ProcessedBlock::Table)
+                            .to_owned(),
+                    )
+                    .await?;
+                let mut index = Index::create();
+                index
+                    .name(name)
+                    .table(ProcessedBlock::Table)
+                    .col(ProcessedBlock::Network);
+                for col in cols {
+                    index.col(col);
+                }
+                manager.create_index(index).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres and SQLite get their tables from `m20241028_000012_portable_tables`.
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE `processed_block` DROP PRIMARY KEY, ADD PRIMARY KEY (`block`)",
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProcessedBlock::Table)
+                    .drop_column(ProcessedBlock::Network)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .drop_column(WalletTracked::Network)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+fn network_column() -> ColumnDef {
+    ColumnDef::new(Alias::new("network"))
+        .string_len(20)
+        .not_null()
+        .default("mainnet")
+        .to_owned()
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    ChatId,
+    WalletAddress,
+    Network,
+}
+
+#[derive(DeriveIden)]
+enum ProcessedBlock {
+    Table,
+    Network,
+    Modulo,
+    Remainder,
+    Place,
+}
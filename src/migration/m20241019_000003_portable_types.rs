@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+/// MySQL databases created before Postgres and SQLite support use unsigned
+/// integers, native enums and `ON UPDATE CURRENT_TIMESTAMP`. Converts them to
+/// the types `portable_tables` creates on Postgres and SQLite.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(());
+        }
+        modify(
+            manager,
+            Alias::new("user"),
+            vec![ColumnDef::new(Alias::new("user_id"))
+                .big_integer()
+                .not_null()
+                .to_owned()],
+        )
+        .await?;
+        modify(
+            manager,
+            Alias::new("wallet_tracked"),
+            vec![
+                ColumnDef::new(Alias::new("wallet_id"))
+                    .big_integer()
+                    .not_null()
+                    .auto_increment()
+                    .to_owned(),
+                ColumnDef::new(Alias::new("user_id"))
+                    .big_integer()
+                    .not_null()
+                    .to_owned(),
+                ColumnDef::new(Alias::new("wallet_address"))
+                    .string_len(70)
+                    .not_null()
+                    .to_owned(),
+                ColumnDef::new(Alias::new("track_type"))
+                    .string_len(20)
+                    .not_null()
+                    .default("full")
+                    .to_owned(),
+            ],
+        )
+        .await?;
+        modify(
+            manager,
+            Alias::new("token_info"),
+            vec![
+                ColumnDef::new(Alias::new("decimal"))
+                    .small_integer()
+                    .not_null()
+                    .default(0)
+                    .to_owned(),
+                ColumnDef::new(Alias::new("is_skipped"))
+                    .small_integer()
+                    .not_null()
+                    .default(0)
+                    .to_owned(),
+                ColumnDef::new(Alias::new("update_time"))
+                    .timestamp_with_time_zone()
+                    .not_null()
+                    .default(Expr::current_timestamp())
+                    .to_owned(),
+                ColumnDef::new(Alias::new("last_updated_block"))
+                    .big_integer()
+                    .not_null()
+                    .default(0)
+                    .to_owned(),
+            ],
+        )
+        .await?;
+        modify(
+            manager,
+            Alias::new("processed_block"),
+            vec![
+                ColumnDef::new(Alias::new("block"))
+                    .big_integer()
+                    .not_null()
+                    .to_owned(),
+                ColumnDef::new(Alias::new("modulo"))
+                    .small_integer()
+                    .not_null()
+                    .default(1)
+                    .to_owned(),
+                ColumnDef::new(Alias::new("remainder"))
+                    .small_integer()
+                    .not_null()
+                    .default(0)
+                    .to_owned(),
+                ColumnDef::new(Alias::new("status"))
+                    .string_len(20)
+                    .not_null()
+                    .default("processing")
+                    .to_owned(),
+                ColumnDef::new(Alias::new("place"))
+                    .string_len(20)
+                    .not_null()
+                    .default("head")
+                    .to_owned(),
+                ColumnDef::new(Alias::new("tx_count"))
+                    .integer()
+                    .not_null()
+                    .default(0)
+                    .to_owned(),
+            ],
+        )
+        .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // The portable types hold every value the old ones did.
+        Ok(())
+    }
+}
+
+async fn modify(
+    manager: &SchemaManager<'_>,
+    table: Alias,
+    columns: Vec<ColumnDef>,
+) -> Result<(), DbErr> {
+    let mut alter = Table::alter();
+    alter.table(table);
+    for mut column in columns {
+        alter.modify_column(&mut column);
+    }
+    manager.alter_table(alter).await
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+/// Per subscription allowlist or denylist of asset types.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only takes one column per ALTER TABLE.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .add_column(
+                        ColumnDef::new(WalletTracked::TokenMode)
+                            .string_len(20)
+                            .not_null()
+                            .default("all"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .add_column(ColumnDef::new(WalletTracked::Tokens).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .drop_column(WalletTracked::Tokens)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .drop_column(WalletTracked::TokenMode)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    TokenMode,
+    Tokens,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+/// Independent received and sent thresholds, in USD or in units of one token.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only takes one column per ALTER TABLE.
+        for mut column in [
+            ColumnDef::new(WalletTracked::MinReceived)
+                .double()
+                .null()
+                .to_owned(),
+            ColumnDef::new(WalletTracked::MinReceivedToken)
+                .string_len(70)
+                .null()
+                .to_owned(),
+            ColumnDef::new(WalletTracked::MinSent)
+                .double()
+                .null()
+                .to_owned(),
+            ColumnDef::new(WalletTracked::MinSentToken)
+                .string_len(70)
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WalletTracked::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            WalletTracked::MinSentToken,
+            WalletTracked::MinSent,
+            WalletTracked::MinReceivedToken,
+            WalletTracked::MinReceived,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WalletTracked::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    MinReceived,
+    MinReceivedToken,
+    MinSent,
+    MinSentToken,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+/// Per subscription rule in the filter expression language.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .add_column(ColumnDef::new(WalletTracked::FilterExpr).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .drop_column(WalletTracked::FilterExpr)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    FilterExpr,
+}
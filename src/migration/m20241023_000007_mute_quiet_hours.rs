@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+
+/// Muted subscriptions, per chat quiet hours and the alerts held during them.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .add_column(
+                        ColumnDef::new(WalletTracked::MutedUntil)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChatSetting::Table)
+                    .col(
+                        ColumnDef::new(ChatSetting::ChatId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ChatSetting::UtcOffset)
+                            .small_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ChatSetting::QuietStart)
+                            .small_integer()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ChatSetting::QuietEnd).small_integer().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(QueuedAlert::Table)
+                    .col(
+                        ColumnDef::new(QueuedAlert::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(QueuedAlert::ChatId).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(QueuedAlert::WalletId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(QueuedAlert::Label).string_len(70).not_null())
+                    .col(
+                        ColumnDef::new(QueuedAlert::Network)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QueuedAlert::Version)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(QueuedAlert::Text).text().not_null())
+                    .col(ColumnDef::new(QueuedAlert::TxUrl).text().not_null())
+                    .col(
+                        ColumnDef::new(QueuedAlert::CreateTime)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("queued_alert_chat_id")
+                    .table(QueuedAlert::Table)
+                    .col(QueuedAlert::ChatId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(QueuedAlert::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ChatSetting::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .drop_column(WalletTracked::MutedUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    MutedUntil,
+}
+
+#[derive(DeriveIden)]
+enum ChatSetting {
+    Table,
+    ChatId,
+    UtcOffset,
+    QuietStart,
+    QuietEnd,
+}
+
+#[derive(DeriveIden)]
+enum QueuedAlert {
+    Table,
+    Id,
+    ChatId,
+    WalletId,
+    Label,
+    Network,
+    Version,
+    Text,
+    TxUrl,
+    CreateTime,
+}
@@ -0,0 +1,155 @@
+use sea_orm_migration::prelude::*;
+
+/// Daily and weekly digests: the mode of a subscription, the schedule of a chat and
+/// the activity collected until the next digest.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only takes one column per ALTER TABLE.
+        for mut column in [
+            ColumnDef::new(WalletTracked::Digest)
+                .string_len(20)
+                .not_null()
+                .default("off")
+                .to_owned(),
+            ColumnDef::new(WalletTracked::LastDigest)
+                .timestamp_with_time_zone()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WalletTracked::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        for mut column in [
+            ColumnDef::new(ChatSetting::DigestTime)
+                .small_integer()
+                .not_null()
+                .default(9 * 60)
+                .to_owned(),
+            ColumnDef::new(ChatSetting::DigestWeekday)
+                .small_integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ChatSetting::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .create_table(
+                Table::create()
+                    .table(DigestEntry::Table)
+                    .col(
+                        ColumnDef::new(DigestEntry::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DigestEntry::WalletId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DigestEntry::Version)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DigestEntry::AssetType).text().not_null())
+                    .col(
+                        ColumnDef::new(DigestEntry::Symbol)
+                            .string_len(70)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DigestEntry::Amount).double().not_null())
+                    .col(ColumnDef::new(DigestEntry::Usd).double().null())
+                    .col(
+                        ColumnDef::new(DigestEntry::CreateTime)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("digest_entry_wallet_id")
+                    .table(DigestEntry::Table)
+                    .col(DigestEntry::WalletId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DigestEntry::Table).to_owned())
+            .await?;
+        for column in [ChatSetting::DigestWeekday, ChatSetting::DigestTime] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ChatSetting::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        for column in [WalletTracked::LastDigest, WalletTracked::Digest] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WalletTracked::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    Digest,
+    LastDigest,
+}
+
+#[derive(DeriveIden)]
+enum ChatSetting {
+    Table,
+    DigestTime,
+    DigestWeekday,
+}
+
+#[derive(DeriveIden)]
+enum DigestEntry {
+    Table,
+    Id,
+    WalletId,
+    Version,
+    AssetType,
+    Symbol,
+    Amount,
+    Usd,
+    CreateTime,
+}
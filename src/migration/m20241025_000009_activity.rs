@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+/// Balance changes of tracked wallets, kept for `/history`.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Activity::Table)
+                    .col(
+                        ColumnDef::new(Activity::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Activity::Network).string_len(20).not_null())
+                    .col(
+                        ColumnDef::new(Activity::WalletAddress)
+                            .string_len(70)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Activity::Block).big_integer().not_null())
+                    .col(ColumnDef::new(Activity::Version).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Activity::Timestamp)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Activity::AssetType).text().not_null())
+                    .col(ColumnDef::new(Activity::Symbol).string_len(70).not_null())
+                    .col(ColumnDef::new(Activity::Amount).double().not_null())
+                    .col(ColumnDef::new(Activity::Usd).double().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("activity_wallet_version")
+                    .table(Activity::Table)
+                    .col(Activity::Network)
+                    .col(Activity::WalletAddress)
+                    .col(Activity::Version)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("activity_block")
+                    .table(Activity::Table)
+                    .col(Activity::Network)
+                    .col(Activity::Block)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Activity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Activity {
+    Table,
+    Id,
+    Network,
+    WalletAddress,
+    Block,
+    Version,
+    Timestamp,
+    AssetType,
+    Symbol,
+    Amount,
+    Usd,
+}
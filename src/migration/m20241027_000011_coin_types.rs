@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+/// Coins other than APT are keyed by their Move type, which is longer than an
+/// address. Widens the columns holding asset keys, SQLite doesn't enforce lengths.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return Ok(());
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TokenInfo::Table)
+                    .modify_column(ColumnDef::new(TokenInfo::Mint).string_len(255).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletTracked::Table)
+                    .modify_column(
+                        ColumnDef::new(WalletTracked::MinReceivedToken)
+                            .string_len(255)
+                            .null(),
+                    )
+                    .modify_column(
+                        ColumnDef::new(WalletTracked::MinSentToken)
+                            .string_len(255)
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Coin keys stored since would not fit the old length.
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TokenInfo {
+    Table,
+    Mint,
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    MinReceivedToken,
+    MinSentToken,
+}
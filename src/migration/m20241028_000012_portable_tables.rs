@@ -0,0 +1,337 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+/// The tables of the first two migrations in types every backend has, for Postgres
+/// and SQLite. MySQL got them from those migrations and `portable_types`.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::MySql {
+            return Ok(());
+        }
+        if !manager.has_table("user").await? {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(User::Table)
+                        .col(
+                            ColumnDef::new(User::UserId)
+                                .big_integer()
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(User::UserName).string_len(20).null())
+                        .col(
+                            ColumnDef::new(User::CreateTime)
+                                .timestamp_with_time_zone()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_table("wallet_tracked").await? {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(WalletTracked::Table)
+                        .col(
+                            ColumnDef::new(WalletTracked::WalletId)
+                                .big_integer()
+                                .not_null()
+                                .auto_increment()
+                                .primary_key(),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::ChatId)
+                                .big_integer()
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::UserId)
+                                .big_integer()
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::WalletAddress)
+                                .string_len(70)
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::Nickname)
+                                .string_len(20)
+                                .null(),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::TrackType)
+                                .string_len(20)
+                                .not_null()
+                                .default("full"),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::CreateTime)
+                                .timestamp_with_time_zone()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .col(
+                            ColumnDef::new(WalletTracked::MinimumValue)
+                                .double()
+                                .not_null()
+                                .default(0.0),
+                        )
+                        .col(network_column())
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name("chat_id")
+                        .table(WalletTracked::Table)
+                        .unique()
+                        .col(WalletTracked::ChatId)
+                        .col(WalletTracked::WalletAddress)
+                        .col(WalletTracked::Network)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name("address")
+                        .table(WalletTracked::Table)
+                        .col(WalletTracked::Network)
+                        .col(WalletTracked::WalletAddress)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_table("token_info").await? {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(TokenInfo::Table)
+                        .col(
+                            ColumnDef::new(TokenInfo::Mint)
+                                .string_len(70)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(TokenInfo::Name).string_len(70).null())
+                        .col(
+                            ColumnDef::new(TokenInfo::Value)
+                                .double()
+                                .not_null()
+                                .default(0.0),
+                        )
+                        .col(
+                            ColumnDef::new(TokenInfo::Decimal)
+                                .small_integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .col(
+                            ColumnDef::new(TokenInfo::IsSkipped)
+                                .small_integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .col(
+                            ColumnDef::new(TokenInfo::UpdateTime)
+                                .timestamp_with_time_zone()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .col(
+                            ColumnDef::new(TokenInfo::CreatTime)
+                                .date_time()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .col(
+                            ColumnDef::new(TokenInfo::LastUpdatedBlock)
+                                .big_integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name("is_skipped")
+                        .table(TokenInfo::Table)
+                        .col(TokenInfo::IsSkipped)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_table("processed_block").await? {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(ProcessedBlock::Table)
+                        .col(network_column())
+                        .col(
+                            ColumnDef::new(ProcessedBlock::Block)
+                                .big_integer()
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::ProcessedTime)
+                                .timestamp_with_time_zone()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::LastTxSig)
+                                .string_len(100)
+                                .null(),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::Modulo)
+                                .small_integer()
+                                .not_null()
+                                .default(1),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::Remainder)
+                                .small_integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::Status)
+                                .string_len(20)
+                                .not_null()
+                                .default("processing"),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::Place)
+                                .string_len(20)
+                                .not_null()
+                                .default("head"),
+                        )
+                        .col(
+                            ColumnDef::new(ProcessedBlock::TxCount)
+                                .integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .primary_key(
+                            Index::create()
+                                .col(ProcessedBlock::Network)
+                                .col(ProcessedBlock::Block),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            for (name, cols) in [
+                (
+                    "modulo_remainder_index",
+                    vec![ProcessedBlock::Modulo, ProcessedBlock::Remainder],
+                ),
+                ("place_index", vec![ProcessedBlock::Place]),
+                (
+                    "place_modulo_remainder_index",
+                    vec![
+                        ProcessedBlock::Place,
+                        ProcessedBlock::Modulo,
+                        ProcessedBlock::Remainder,
+                    ],
+                ),
+            ] {
+                let mut index = Index::create();
+                index
+                    .name(name)
+                    .table(ProcessedBlock::Table)
+                    .col(ProcessedBlock::Network);
+                for col in cols {
+                    index.col(col);
+                }
+                manager.create_index(index).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::MySql {
+            return Ok(());
+        }
+        for table in [
+            ProcessedBlock::Table.into_iden(),
+            TokenInfo::Table.into_iden(),
+            WalletTracked::Table.into_iden(),
+            User::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).if_exists().to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn network_column() -> ColumnDef {
+    ColumnDef::new(Alias::new("network"))
+        .string_len(20)
+        .not_null()
+        .default("mainnet")
+        .to_owned()
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    UserId,
+    UserName,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum WalletTracked {
+    Table,
+    WalletId,
+    ChatId,
+    UserId,
+    WalletAddress,
+    Nickname,
+    TrackType,
+    CreateTime,
+    MinimumValue,
+    Network,
+}
+
+#[derive(DeriveIden)]
+enum TokenInfo {
+    Table,
+    Mint,
+    Name,
+    Value,
+    Decimal,
+    IsSkipped,
+    UpdateTime,
+    CreatTime,
+    LastUpdatedBlock,
+}
+
+#[derive(DeriveIden)]
+enum ProcessedBlock {
+    Table,
+    Network,
+    Block,
+    ProcessedTime,
+    LastTxSig,
+    Modulo,
+    Remainder,
+    Status,
+    Place,
+    TxCount,
+}
@@ -0,0 +1,95 @@
+use log::info;
+use sea_orm::{DatabaseConnection, EntityName, EntityTrait, IdenStatic, Iterable};
+use sea_orm_migration::prelude::*;
+
+use crate::entities::{
+    activity, chat_setting, digest_entry, processed_block, queued_alert, token_info, user,
+    wallet_tracked,
+};
+use crate::error::Err;
+
+mod m20240601_000001_create_tables;
+mod m20241019_000002_add_network;
+mod m20241019_000003_portable_types;
+mod m20241020_000004_token_filter;
+mod m20241021_000005_thresholds;
+mod m20241022_000006_filter_expr;
+mod m20241023_000007_mute_quiet_hours;
+mod m20241024_000008_digest;
+mod m20241025_000009_activity;
+mod m20241026_000010_activity_counterparty;
+mod m20241027_000011_coin_types;
+mod m20241028_000012_portable_tables;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20240601_000001_create_tables::Migration),
+            Box::new(m20241019_000002_add_network::Migration),
+            // Added later, runs here so the following migrations find its tables.
+            Box::new(m20241028_000012_portable_tables::Migration),
+            Box::new(m20241019_000003_portable_types::Migration),
+            Box::new(m20241020_000004_token_filter::Migration),
+            Box::new(m20241021_000005_thresholds::Migration),
+            Box::new(m20241022_000006_filter_expr::Migration),
+            Box::new(m20241023_000007_mute_quiet_hours::Migration),
+            Box::new(m20241024_000008_digest::Migration),
+            Box::new(m20241025_000009_activity::Migration),
+            Box::new(m20241026_000010_activity_counterparty::Migration),
+            Box::new(m20241027_000011_coin_types::Migration),
+        ]
+    }
+}
+
+/// Applies pending migrations when `migrate` is set, then checks the entities
+/// against the live schema either way.
+pub async fn prepare(db: &DatabaseConnection, migrate: bool) -> Result<(), Err> {
+    if migrate {
+        Migrator::up(db, None).await?;
+    }
+    let pending = Migrator::get_pending_migrations(db).await?;
+    if !pending.is_empty() {
+        info!("{} migrations pending", pending.len());
+    }
+    verify_schema(db).await
+}
+
+/// Every table and column the entities use must exist in the database.
+pub async fn verify_schema(db: &DatabaseConnection) -> Result<(), Err> {
+    let manager = SchemaManager::new(db);
+    let mut missing = vec![];
+    check_entity(&manager, user::Entity, &mut missing).await?;
+    check_entity(&manager, wallet_tracked::Entity, &mut missing).await?;
+    check_entity(&manager, token_info::Entity, &mut missing).await?;
+    check_entity(&manager, processed_block::Entity, &mut missing).await?;
+    check_entity(&manager, chat_setting::Entity, &mut missing).await?;
+    check_entity(&manager, queued_alert::Entity, &mut missing).await?;
+    check_entity(&manager, digest_entry::Entity, &mut missing).await?;
+    check_entity(&manager, activity::Entity, &mut missing).await?;
+    match missing.is_empty() {
+        true => Ok(()),
+        false => Err(Err::SchemaMismatch(missing)),
+    }
+}
+
+async fn check_entity<E: EntityTrait>(
+    manager: &SchemaManager<'_>,
+    entity: E,
+    missing: &mut Vec<String>,
+) -> Result<(), DbErr> {
+    let table = entity.table_name();
+    if !manager.has_table(table).await? {
+        missing.push(format!("table {table}"));
+        return Ok(());
+    }
+    for column in E::Column::iter() {
+        let name = column.as_str();
+        if !manager.has_column(table, name).await? {
+            missing.push(format!("column {table}.{name}"));
+        }
+    }
+    Ok(())
+}
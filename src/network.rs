@@ -0,0 +1,146 @@
+use ahash::AHashSet;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use futures::{future::Pending, stream::Abortable};
+use reqwest::Client as rqwClient;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::RwLock;
+
+use crate::block::update_block_loop;
+use crate::config::{Config, ExplorerConfig, NetworkConfig, SpamConfig};
+use crate::db::TokenMap;
+use crate::db_update_loop::db_loop;
+use crate::error::Err;
+use crate::health_check_loop::{db_check, health_check_loop};
+use crate::notifier::Notifier;
+use crate::probe::Liveness;
+use crate::process_new_block::handle_new_block_loop;
+use crate::replay::Recorder;
+use crate::rpc::{rpc_pool_loop, RpcPool};
+use crate::tx::TxQueryClient;
+
+/// One chain followed by the tracker, with its own fullnodes, indexer and head.
+pub struct Network {
+    pub name: String,
+    pub rpc: Arc<RpcPool>,
+    pub tx_client: TxQueryClient,
+    pub explorer: ExplorerConfig,
+    pub spam: SpamConfig,
+    pub current_block: Arc<RwLock<u64>>,
+    /// Set when `record.dir` is configured.
+    pub recorder: Option<Recorder>,
+}
+
+/// Every configured network, the first one is the default.
+#[derive(Clone)]
+pub struct Networks(Arc<Vec<Arc<Network>>>);
+
+impl Network {
+    pub async fn connect(
+        config: &NetworkConfig,
+        client: Arc<rqwClient>,
+        recorder: Option<Recorder>,
+        spam: SpamConfig,
+    ) -> Result<Self, Err> {
+        let tx_client = TxQueryClient::from_config(client, &config.indexer)?;
+        let rpc = Arc::new(RpcPool::from_config(&config.name, &config.rpc));
+        rpc.refresh().await;
+        let now_block = rpc.get_ledger_information().await?.inner().block_height;
+        Ok(Network {
+            name: config.name.clone(),
+            rpc,
+            tx_client,
+            explorer: config.explorer(),
+            spam,
+            current_block: Arc::new(RwLock::new(now_block)),
+            recorder,
+        })
+    }
+    /// Liveness name of a loop running for this network.
+    pub fn worker(&self, name: &str) -> String {
+        format!("{}/{}", self.name, name)
+    }
+}
+
+impl Networks {
+    pub async fn connect(config: &Config, client: Arc<rqwClient>) -> Result<Self, Err> {
+        let recorder = config.record.dir.as_ref().map(Recorder::new);
+        let mut networks = vec![];
+        for network in config.networks.iter() {
+            networks.push(Arc::new(
+                Network::connect(
+                    network,
+                    client.clone(),
+                    recorder.clone(),
+                    config.spam.clone(),
+                )
+                .await?,
+            ));
+        }
+        Ok(networks.into())
+    }
+    pub fn default_network(&self) -> &Arc<Network> {
+        &self.0[0]
+    }
+    pub fn get(&self, name: &str) -> Option<&Arc<Network>> {
+        self.0.iter().find(|network| network.name == name)
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Arc<Network>> {
+        self.0.iter()
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn names(&self) -> String {
+        self.0
+            .iter()
+            .map(|network| network.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+impl From<Vec<Arc<Network>>> for Networks {
+    fn from(networks: Vec<Arc<Network>>) -> Self {
+        Networks(Arc::new(networks))
+    }
+}
+
+/// Runs every per-network loop until aborted. `processed_block` lives in
+/// `bookkeeping`, which is `db` unless a shadow deployment separates them.
+pub async fn network_loop(
+    abort: Abortable<Pending<()>>,
+    network: Arc<Network>,
+    db: Arc<DatabaseConnection>,
+    bookkeeping: Arc<DatabaseConnection>,
+    notifier: Notifier,
+    config: Arc<Config>,
+    token: Arc<RwLock<TokenMap>>,
+    new_token: Arc<RwLock<AHashSet<AccountAddress>>>,
+    new_price_update_tx: Sender<(u64, Vec<u8>)>,
+    liveness: Arc<Liveness>,
+) {
+    let current = *network.current_block.read().await;
+    db_check(&bookkeeping, &network.name, current)
+        .await
+        .expect("DB check error");
+    tokio::join!(
+        handle_new_block_loop(
+            abort.clone(),
+            db.clone(),
+            bookkeeping.clone(),
+            network.clone(),
+            notifier.clone(),
+            config.worker.threads,
+            token,
+            new_token,
+            new_price_update_tx,
+            liveness,
+        ),
+        db_loop(abort.clone(), bookkeeping.clone(), network.name.clone()),
+        update_block_loop(abort.clone(), network.clone()),
+        rpc_pool_loop(abort.clone(), network.rpc.clone()),
+        health_check_loop(abort, network.clone(), bookkeeping, notifier, config),
+    );
+}
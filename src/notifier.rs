@@ -0,0 +1,436 @@
+use log::error;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+#[cfg(feature = "telegram")]
+use std::time::Duration;
+#[cfg(feature = "telegram")]
+use teloxide::Bot;
+
+use crate::error::Err;
+use crate::metrics::NOTIFICATIONS_FAILED;
+use crate::process_new_block::Notification;
+use crate::shadow::{LoggedNotification, NotificationLog};
+use crate::webhook::{Webhook, WebhookEvent};
+
+/// Longest message Telegram accepts.
+pub const MESSAGE_LIMIT: usize = 4096;
+
+/// Where alerts and health reports go. Production sends to Telegram, a headless
+/// deployment to a webhook, and either may also log every alert. A shadow deployment
+/// only logs them.
+#[derive(Clone, Default)]
+pub struct Notifier {
+    #[cfg(feature = "telegram")]
+    bot: Option<Bot>,
+    #[cfg(feature = "telegram")]
+    report_chat: Option<i64>,
+    #[cfg(feature = "telegram")]
+    bursts: Option<Arc<telegram::Bursts>>,
+    webhook: Option<Arc<Webhook>>,
+    log: Option<Arc<NotificationLog>>,
+    shadow: bool,
+}
+
+impl Notifier {
+    pub fn new(log: Option<Arc<NotificationLog>>) -> Self {
+        Notifier {
+            log,
+            ..Default::default()
+        }
+    }
+    #[cfg(feature = "telegram")]
+    pub fn telegram(bot: Bot) -> Self {
+        Self::new(None).with_telegram(bot, None)
+    }
+    /// Alerts go to the subscription's chat and health reports to `report_chat`.
+    #[cfg(feature = "telegram")]
+    pub fn with_telegram(mut self, bot: Bot, report_chat: Option<i64>) -> Self {
+        self.bot = Some(bot);
+        self.report_chat = report_chat;
+        self
+    }
+    /// Edits the alerts of a subscription within `window` of the message starting a
+    /// burst into that message, a zero window sends every alert.
+    #[cfg(feature = "telegram")]
+    pub fn with_burst_window(mut self, window: Duration) -> Self {
+        self.bursts = (!window.is_zero()).then(|| Arc::new(telegram::Bursts::new(window)));
+        self
+    }
+    pub fn with_webhook(mut self, webhook: Webhook) -> Self {
+        self.webhook = Some(Arc::new(webhook));
+        self
+    }
+    /// Marks a shadow deployment, which shares production's database and must not
+    /// write what production reads or consume what production queued.
+    pub fn in_shadow_mode(mut self) -> Self {
+        self.shadow = true;
+        self
+    }
+    pub fn is_shadow(&self) -> bool {
+        self.shadow
+    }
+    #[cfg_attr(not(feature = "telegram"), allow(unused_variables))]
+    pub async fn send(
+        &self,
+        db: &DatabaseConnection,
+        network: &str,
+        notification: Notification,
+    ) -> Result<(), Err> {
+        if self.log.is_some() || self.webhook.is_some() {
+            let logged = LoggedNotification::new(network, &notification);
+            if let Some(log) = &self.log {
+                if let Err(err) = log.write(&logged) {
+                    error!("notification log error: {:?}", err);
+                }
+            }
+            if let Some(webhook) = &self.webhook {
+                if let Err(err) = webhook.post(&WebhookEvent::Alert(&logged)).await {
+                    NOTIFICATIONS_FAILED.with_label_values(&["webhook"]).inc();
+                    error!("webhook error: {:?}", err);
+                }
+            }
+        }
+        #[cfg(feature = "telegram")]
+        if let Some(bot) = &self.bot {
+            return match &self.bursts {
+                Some(bursts) => bursts.send(db, bot, notification).await,
+                None => telegram::send(db, bot, notification).await.map(|_| ()),
+            };
+        }
+        Ok(())
+    }
+    /// Sends a chat a message about several alerts. Fails when any target did, so the
+    /// caller keeps what it summarized for the next attempt.
+    pub async fn summary(&self, chat_id: i64, text: String) -> Result<(), Err> {
+        let mut result = Ok(());
+        if let Some(webhook) = &self.webhook {
+            let event = WebhookEvent::Summary {
+                chat_id,
+                text: &text,
+            };
+            if let Err(err) = webhook.post(&event).await {
+                NOTIFICATIONS_FAILED.with_label_values(&["webhook"]).inc();
+                error!("webhook summary error: {:?}", err);
+                result = Err(err);
+            }
+        }
+        #[cfg(feature = "telegram")]
+        if let Some(bot) = &self.bot {
+            if let Err(err) = telegram::send_text(bot, chat_id, text).await {
+                result = Err(err);
+            }
+        }
+        result
+    }
+    /// Whether health reports reach anyone.
+    pub fn reports(&self) -> bool {
+        #[cfg(feature = "telegram")]
+        if self.bot.is_some() && self.report_chat.is_some() {
+            return true;
+        }
+        self.webhook.is_some()
+    }
+    /// Sends an operator facing health report, failures are only logged.
+    pub async fn report(&self, text: String) {
+        #[cfg(feature = "telegram")]
+        if let (Some(bot), Some(chat)) = (&self.bot, self.report_chat) {
+            let _ = telegram::send_text(bot, chat, text.clone()).await;
+        }
+        if let Some(webhook) = &self.webhook {
+            if let Err(err) = webhook.post(&WebhookEvent::Report { text: &text }).await {
+                error!("webhook report error: {:?}", err);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "telegram")]
+mod telegram {
+    use ahash::AHashMap;
+    use log::{error, warn};
+    use reqwest::Url;
+    use sea_orm::ActiveValue::Set;
+    use sea_orm::{
+        ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+        QueryFilter,
+    };
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    use teloxide::payloads::{EditMessageTextSetters, SendMessageSetters};
+    use teloxide::requests::Requester;
+    use teloxide::types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup, MessageId};
+    use teloxide::{ApiError, Bot, RequestError};
+
+    use super::MESSAGE_LIMIT;
+    use crate::entities::{prelude::*, wallet_tracked};
+    use crate::error::Err;
+    use crate::metrics::{NOTIFICATIONS_COALESCED, NOTIFICATIONS_FAILED, NOTIFICATIONS_SENT};
+    use crate::process_new_block::Notification;
+
+    fn keyboard(chat: &wallet_tracked::Model, tx_url: Url) -> InlineKeyboardMarkup {
+        InlineKeyboardMarkup::new([vec![
+            InlineKeyboardButton::url("TX detail", tx_url),
+            InlineKeyboardButton::callback("Unsubscribe", chat.to_unsub_callback_data()),
+            InlineKeyboardButton::callback("Mute 1h", chat.to_mute_callback_data()),
+        ]])
+    }
+
+    /// Sends one alert, dropping the chat's subscriptions when the bot was blocked and
+    /// following group to supergroup migrations. Returns the message, `None` when it
+    /// was not delivered.
+    pub async fn send(
+        db: &DatabaseConnection,
+        bot: &Bot,
+        notification: Notification,
+    ) -> Result<Option<MessageId>, Err> {
+        let chat = notification.subscription;
+        match bot
+            .send_message(ChatId(chat.chat_id), notification.text)
+            .reply_markup(keyboard(&chat, notification.tx_url))
+            .await
+        {
+            Ok(message) => {
+                NOTIFICATIONS_SENT.inc();
+                Ok(Some(message.id))
+            }
+            Err(err) => {
+                failed(db, chat, err).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn failed(
+        db: &DatabaseConnection,
+        chat: wallet_tracked::Model,
+        err: RequestError,
+    ) -> Result<(), Err> {
+        match err {
+            RequestError::RetryAfter(_) => {
+                NOTIFICATIONS_FAILED
+                    .with_label_values(&["retry_after"])
+                    .inc();
+            }
+            RequestError::Api(err) => {
+                NOTIFICATIONS_FAILED
+                    .with_label_values(&[match err {
+                        ApiError::BotBlocked => "bot_blocked",
+                        _ => "api",
+                    }])
+                    .inc();
+                if let ApiError::BotBlocked = err {
+                    let _wallet_blocked = WalletTracked::delete_many()
+                        .filter(wallet_tracked::Column::ChatId.eq(chat.chat_id))
+                        .exec(db)
+                        .await?;
+                    warn!("{:?} is blocked deleted all ", chat.chat_id);
+                }
+            }
+            RequestError::MigrateToChatId(id) => {
+                NOTIFICATIONS_FAILED
+                    .with_label_values(&["chat_migrated"])
+                    .inc();
+                let mut update = chat.into_active_model();
+                update.chat_id = Set(id);
+                if let Err(err) = update.update(db).await {
+                    error!("Error Migrating chat: {:?}", err);
+                };
+            }
+            _ => {
+                NOTIFICATIONS_FAILED.with_label_values(&["other"]).inc();
+                error!("{:?}", err);
+            }
+        };
+        Ok(())
+    }
+
+    struct Burst {
+        /// `None` while the message starting the burst is being sent.
+        message_id: Option<MessageId>,
+        started: Instant,
+        /// Alert texts with their explorer links, oldest first.
+        entries: Vec<String>,
+        /// Explorer link of the newest alert, behind the TX detail button.
+        tx_url: Url,
+    }
+
+    /// What one alert does to the burst of its subscription.
+    enum Plan {
+        Edit(MessageId, String),
+        /// Queued behind the message starting the burst, which edits it in once sent.
+        Queued,
+        /// Sent as the start of the burst reserved under the lock.
+        Start,
+        /// Sent on its own, the burst being sent is full.
+        Alone,
+    }
+
+    /// The last message of every subscription alerted within the window, keyed by
+    /// `wallet_id`.
+    pub struct Bursts {
+        window: Duration,
+        open: Mutex<AHashMap<i64, Burst>>,
+    }
+
+    impl Bursts {
+        pub fn new(window: Duration) -> Self {
+            Bursts {
+                window,
+                open: Mutex::new(AHashMap::new()),
+            }
+        }
+
+        /// Edits `notification` into the open burst of its subscription, or sends it
+        /// as the start of a new one when there is none, it is full or the edit failed.
+        /// A new burst is reserved before its message is sent, so alerts arriving
+        /// meanwhile join it instead of starting their own.
+        pub async fn send(
+            &self,
+            db: &DatabaseConnection,
+            bot: &Bot,
+            notification: Notification,
+        ) -> Result<(), Err> {
+            let chat = notification.subscription.clone();
+            let entry = format!("{}{}\n", notification.text, notification.tx_url);
+            let plan = self.plan(&chat, &entry, &notification.tx_url);
+            if let Plan::Edit(message_id, text) = &plan {
+                match bot
+                    .edit_message_text(ChatId(chat.chat_id), *message_id, text)
+                    .reply_markup(keyboard(&chat, notification.tx_url.clone()))
+                    .await
+                {
+                    Ok(_) => {
+                        NOTIFICATIONS_COALESCED.inc();
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        warn!("burst edit in {} failed: {:?}", chat.chat_id, err);
+                        self.open.lock().unwrap().insert(
+                            chat.wallet_id,
+                            Burst {
+                                message_id: None,
+                                started: Instant::now(),
+                                entries: vec![entry],
+                                tx_url: notification.tx_url.clone(),
+                            },
+                        );
+                    }
+                }
+            }
+            match plan {
+                Plan::Queued => Ok(()),
+                Plan::Alone => send(db, bot, notification).await.map(|_| ()),
+                Plan::Edit(..) | Plan::Start => {
+                    let sent = send(db, bot, notification).await;
+                    self.started(bot, &chat, sent.as_ref().ok().copied().flatten())
+                        .await;
+                    sent.map(|_| ())
+                }
+            }
+        }
+
+        fn plan(&self, chat: &wallet_tracked::Model, entry: &str, tx_url: &Url) -> Plan {
+            let mut open = self.open.lock().unwrap();
+            open.retain(|_, burst| burst.started.elapsed() < self.window);
+            if let Some(burst) = open.get_mut(&chat.wallet_id) {
+                if let Some(text) = burst_text(chat, &burst.entries, entry) {
+                    burst.entries.push(entry.to_string());
+                    burst.tx_url = tx_url.clone();
+                    return match burst.message_id {
+                        Some(message_id) => Plan::Edit(message_id, text),
+                        None => Plan::Queued,
+                    };
+                }
+                if burst.message_id.is_none() {
+                    return Plan::Alone;
+                }
+            }
+            open.insert(
+                chat.wallet_id,
+                Burst {
+                    message_id: None,
+                    started: Instant::now(),
+                    entries: vec![entry.to_string()],
+                    tx_url: tx_url.clone(),
+                },
+            );
+            Plan::Start
+        }
+
+        /// Settles the burst reserved for `chat` once its first message went out as
+        /// `message_id`, editing in the alerts queued meanwhile. When it was not
+        /// delivered, the queued alerts are sent together on their own.
+        async fn started(
+            &self,
+            bot: &Bot,
+            chat: &wallet_tracked::Model,
+            message_id: Option<MessageId>,
+        ) {
+            let (queued, tx_url) = {
+                let mut open = self.open.lock().unwrap();
+                let burst = match open.get_mut(&chat.wallet_id) {
+                    Some(burst) if burst.message_id.is_none() => burst,
+                    _ => return,
+                };
+                match message_id {
+                    Some(message_id) => {
+                        burst.message_id = Some(message_id);
+                        (burst.entries.clone(), burst.tx_url.clone())
+                    }
+                    None => match open.remove(&chat.wallet_id) {
+                        Some(burst) => (burst.entries, burst.tx_url),
+                        None => return,
+                    },
+                }
+            };
+            if queued.len() < 2 {
+                return;
+            }
+            match message_id {
+                Some(message_id) => {
+                    let (entries, last) = queued.split_at(queued.len() - 1);
+                    let text = match burst_text(chat, entries, &last[0]) {
+                        Some(text) => text,
+                        None => return,
+                    };
+                    match bot
+                        .edit_message_text(ChatId(chat.chat_id), message_id, text)
+                        .reply_markup(keyboard(chat, tx_url))
+                        .await
+                    {
+                        Ok(_) => NOTIFICATIONS_COALESCED.inc_by(entries.len() as u64),
+                        Err(err) => warn!("burst edit in {} failed: {:?}", chat.chat_id, err),
+                    }
+                }
+                None => {
+                    let _ = send_text(bot, chat.chat_id, queued[1..].concat()).await;
+                }
+            }
+        }
+    }
+
+    /// The burst message once `next` is added, `None` when it would be too long.
+    fn burst_text(chat: &wallet_tracked::Model, entries: &[String], next: &str) -> Option<String> {
+        let mut text = format!(
+            "{} transactions for {}\n",
+            entries.len() + 1,
+            chat.clone().display_name()
+        );
+        for entry in entries.iter().map(String::as_str).chain([next]) {
+            text.push('\n');
+            text.push_str(entry);
+        }
+        (text.len() <= MESSAGE_LIMIT).then_some(text)
+    }
+
+    /// Sends plain text, failures are logged and returned.
+    pub async fn send_text(bot: &Bot, chat: i64, text: String) -> Result<(), Err> {
+        match bot.send_message(ChatId(chat), text).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!("send to {} error: {:?}", chat, err);
+                Err(err.into())
+            }
+        }
+    }
+}
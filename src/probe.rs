@@ -0,0 +1,227 @@
+use ahash::AHashMap;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::config::HealthConfig;
+use crate::entities::processed_block;
+use crate::network::Networks;
+use crate::tx::{u64_to_i128, TxQueryClient};
+
+/// Heartbeats of the long running loops, shared with the probe endpoints.
+#[derive(Default)]
+pub struct Liveness {
+    heartbeats: RwLock<AHashMap<String, Instant>>,
+    stopped: RwLock<Vec<String>>,
+}
+
+impl Liveness {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+    pub async fn beat(&self, name: &str) {
+        let mut update = self.heartbeats.write().await;
+        update.insert(name.to_string(), Instant::now());
+        drop(update);
+    }
+    pub async fn stop(&self, name: &str) {
+        let mut update = self.heartbeats.write().await;
+        update.remove(name);
+        drop(update);
+        let mut update = self.stopped.write().await;
+        update.push(name.to_string());
+        drop(update);
+    }
+    /// A worker that has not reported for `stall` is considered stalled.
+    async fn check(&self, stall: Duration) -> Check {
+        let stopped = self.stopped.read().await;
+        if !stopped.is_empty() {
+            return Check::fail(format!("stopped: {}", stopped.join(", ")));
+        }
+        drop(stopped);
+        let heartbeats = self.heartbeats.read().await;
+        if heartbeats.is_empty() {
+            return Check::fail("no worker started".to_string());
+        }
+        let mut stalled = heartbeats
+            .iter()
+            .filter(|(_, last)| last.elapsed() > stall)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<String>>();
+        match stalled.is_empty() {
+            true => Check::ok(format!("{} workers alive", heartbeats.len())),
+            false => {
+                stalled.sort();
+                Check::fail(format!("stalled: {}", stalled.join(", ")))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProbeState {
+    pub db: Arc<DatabaseConnection>,
+    /// Holds `processed_block`, `db` unless a shadow keeps its own.
+    pub bookkeeping: Arc<DatabaseConnection>,
+    pub networks: Networks,
+    pub liveness: Arc<Liveness>,
+    pub config: HealthConfig,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    ok: bool,
+    detail: String,
+}
+
+impl Check {
+    fn ok(detail: String) -> Self {
+        Check { ok: true, detail }
+    }
+    fn fail(detail: String) -> Self {
+        Check { ok: false, detail }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProbeResponse {
+    ok: bool,
+    checks: AHashMap<String, Check>,
+}
+
+impl ProbeResponse {
+    fn into_reply(checks: AHashMap<String, Check>) -> (StatusCode, Json<Self>) {
+        let ok = checks.values().all(|check| check.ok);
+        let status = match ok {
+            true => StatusCode::OK,
+            false => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        (status, Json(ProbeResponse { ok, checks }))
+    }
+}
+
+pub fn probe_router(state: ProbeState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(state)
+}
+
+async fn healthz(State(state): State<ProbeState>) -> (StatusCode, Json<ProbeResponse>) {
+    let mut checks = AHashMap::new();
+    checks.insert(
+        "workers".to_string(),
+        state.liveness.check(state.config.worker_stall()).await,
+    );
+    ProbeResponse::into_reply(checks)
+}
+
+async fn readyz(State(state): State<ProbeState>) -> (StatusCode, Json<ProbeResponse>) {
+    let mut checks = AHashMap::new();
+    checks.insert(
+        "workers".to_string(),
+        state.liveness.check(state.config.worker_stall()).await,
+    );
+    checks.insert("db".to_string(), ping_check(state.db.as_ref()).await);
+    if !Arc::ptr_eq(&state.db, &state.bookkeeping) {
+        checks.insert(
+            "bookkeeping_db".to_string(),
+            ping_check(state.bookkeeping.as_ref()).await,
+        );
+    }
+    for network in state.networks.iter() {
+        let name = &network.name;
+        let ledger_version = match network.rpc.get_ledger_information().await {
+            Ok(info) => {
+                checks.insert(
+                    format!("{name}/rpc"),
+                    Check::ok(format!("block {}", info.inner().block_height)),
+                );
+                Some(info.inner().version)
+            }
+            Err(e) => {
+                checks.insert(format!("{name}/rpc"), Check::fail(e.to_string()));
+                None
+            }
+        };
+        checks.insert(
+            format!("{name}/indexer"),
+            indexer_check(
+                &network.tx_client,
+                ledger_version,
+                i128::from(state.config.ready_max_indexer_lag),
+            )
+            .await,
+        );
+        checks.insert(
+            format!("{name}/last_block"),
+            last_block_check(
+                state.bookkeeping.as_ref(),
+                name,
+                state.config.ready_max_block_age_secs,
+            )
+            .await,
+        );
+    }
+    ProbeResponse::into_reply(checks)
+}
+
+async fn ping_check(db: &DatabaseConnection) -> Check {
+    match db.ping().await {
+        Ok(_) => Check::ok("reachable".to_string()),
+        Err(e) => Check::fail(e.to_string()),
+    }
+}
+
+async fn indexer_check(
+    tx_client: &TxQueryClient,
+    ledger_version: Option<u64>,
+    max_lag: i128,
+) -> Check {
+    let status = match tx_client.get_indexer_status().await {
+        Ok(res) => match res.data {
+            Some(data) => data.processor_status.into_iter().next(),
+            None => None,
+        },
+        Err(e) => return Check::fail(e.to_string()),
+    };
+    match (status, ledger_version) {
+        (Some(status), Some(version)) => {
+            let lag = u64_to_i128(version) - status.last_success_version;
+            match lag > max_lag {
+                true => Check::fail(format!("{} versions behind", lag)),
+                false => Check::ok(format!("{} versions behind", lag)),
+            }
+        }
+        (Some(status), None) => Check::ok(format!(
+            "at version {}, ledger unknown",
+            status.last_success_version
+        )),
+        (None, _) => Check::fail("processor status not found".to_string()),
+    }
+}
+
+async fn last_block_check(db: &DatabaseConnection, network: &str, max_age: i64) -> Check {
+    let last = match processed_block::Model::get_last_block(db, network, None, None, false).await {
+        Ok(Some(block)) => processed_block::Model::get_block(db, network, block).await,
+        Ok(None) => return Check::fail("no block processed".to_string()),
+        Err(e) => return Check::fail(e.to_string()),
+    };
+    match last {
+        Ok(Some(entry)) => {
+            let age = (chrono::Utc::now() - entry.processed_time).num_seconds();
+            match age > max_age {
+                true => Check::fail(format!("block {} claimed {}s ago", entry.block, age)),
+                false => Check::ok(format!("block {} claimed {}s ago", entry.block, age)),
+            }
+        }
+        Ok(None) => Check::fail("no block processed".to_string()),
+        Err(e) => Check::fail(e.to_string()),
+    }
+}
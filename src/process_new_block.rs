@@ -1,8 +1,8 @@
 use ahash::{AHashMap, AHashSet};
 use aptos_sdk::move_types::account_address::AccountAddress;
-use aptos_sdk::rest_client::Client;
 use aptos_sdk::types::account_config::new_block;
 use base58::FromBase58;
+use chrono::{DateTime, Utc};
 use futures::{future::Pending, stream::Abortable};
 use graphql_client::GraphQLQuery;
 use log::{error, info, warn};
@@ -14,22 +14,31 @@ use sea_orm::{DatabaseConnection, EntityTrait, QuerySelect};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use teloxide::payloads::SendMessageSetters;
-use teloxide::requests::Requester;
-use teloxide::types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup};
-use teloxide::{ApiError, Bot, RequestError};
 use tokio::sync::mpsc::Sender;
 use tokio::sync::RwLock;
 
 use tokio::task;
 
+use crate::config::{ExplorerConfig, SpamConfig};
 use crate::db::{LastBlock, TokenMap};
-use crate::entities::sea_orm_active_enums::TrackType;
+use crate::entities::sea_orm_active_enums::{DigestMode, TrackType};
 use crate::entities::{prelude::*, sea_orm_active_enums::Status, *};
-use crate::query::transaction_query::ResponseData;
-use crate::query::transactions_query::{TransactionsQueryFungibleAssetActivities, Variables};
+use crate::filter_expr::{FilterExpr, Subject};
+use crate::history;
+use crate::metrics::{
+    set_gauge, BLOCKS_PROCESSED, CHANGES_SUPPRESSED, RULE_ERRORS, TOKEN_MAP_SIZE, TRACKED_WALLETS,
+};
+use crate::network::Network;
+use crate::notifier::Notifier;
+use crate::probe::Liveness;
+use crate::query::transactions_query::{
+    ResponseData, TransactionsQueryFungibleAssetActivities, Variables,
+};
 use crate::query::TransactionsQuery;
-use crate::tx::{u64_to_i128, BalanceChange, Filter, TxQueryClient};
+use crate::quiet;
+use crate::replay::BlockFixture;
+use crate::spam::{self, SpamToken, Verdict};
+use crate::tx::{u64_to_i128, BalanceChange, Filter};
 use crate::{
     db::UniqueWallet,
     entities::wallet_tracked::{self},
@@ -38,14 +47,14 @@ use crate::{
 pub async fn handle_new_block_loop(
     abort: Abortable<Pending<()>>,
     db: Arc<DatabaseConnection>,
-    rpc: Arc<Client>,
-    tx_client: TxQueryClient,
-    current_block: Arc<RwLock<u64>>,
-    bot: Bot,
+    bookkeeping: Arc<DatabaseConnection>,
+    network: Arc<Network>,
+    notifier: Notifier,
     modulo: u8,
     token: Arc<RwLock<TokenMap>>,
     new_token: Arc<RwLock<AHashSet<AccountAddress>>>,
     new_price_update_tx: Sender<(u64, Vec<u8>)>,
+    liveness: Arc<Liveness>,
 ) {
     let mut futures = Vec::new();
     let all_wallet: Arc<RwLock<AHashMap<String, ()>>> = Arc::new(RwLock::new(AHashMap::new()));
@@ -53,22 +62,24 @@ pub async fn handle_new_block_loop(
         futures.push(tokio::spawn(handle_new_block_loop_inner(
             abort.clone(),
             db.clone(),
-            rpc.clone(),
-            tx_client.clone(),
-            current_block.clone(),
-            bot.clone(),
+            bookkeeping.clone(),
+            network.clone(),
+            notifier.clone(),
             Arc::new(modulo),
             Arc::new(remainder),
             all_wallet.clone(),
             token.clone(),
             new_token.clone(),
             new_price_update_tx.clone(),
+            liveness.clone(),
         )))
     }
     futures.push(tokio::spawn(update_unique_loop(
         abort.clone(),
         all_wallet,
         db.clone(),
+        network.clone(),
+        liveness.clone(),
     )));
     futures::future::join_all(futures).await;
 }
@@ -76,9 +87,17 @@ pub async fn update_unique_loop(
     abort: Abortable<Pending<()>>,
     all_wallet: Arc<RwLock<AHashMap<String, ()>>>,
     db: Arc<DatabaseConnection>,
+    network: Arc<Network>,
+    liveness: Arc<Liveness>,
 ) {
+    let worker = network.worker("unique_wallet");
     'outer: loop {
-        if let Ok(new) = UniqueWallet::fetch_all_hash_map(db.as_ref()).await {
+        liveness.beat(&worker).await;
+        if let Ok(new) = UniqueWallet::fetch_all_hash_map(db.as_ref(), &network.name).await {
+            set_gauge(
+                &TRACKED_WALLETS.with_label_values(&[&network.name]),
+                new.len(),
+            );
             let mut update = all_wallet.write().await;
             *update = new;
             drop(update);
@@ -97,38 +116,38 @@ pub async fn update_unique_loop(
 pub async fn handle_new_block_loop_inner(
     abort: Abortable<Pending<()>>,
     db: Arc<DatabaseConnection>,
-    rpc: Arc<Client>,
-    tx_client: TxQueryClient,
-    current_block: Arc<RwLock<u64>>,
-    bot: Bot,
+    bookkeeping: Arc<DatabaseConnection>,
+    network: Arc<Network>,
+    notifier: Notifier,
     modulo: Arc<u8>,
     remainder: Arc<u8>,
     all_wallet: Arc<RwLock<AHashMap<String, ()>>>,
     token: Arc<RwLock<TokenMap>>,
     new_token: Arc<RwLock<AHashSet<AccountAddress>>>,
     new_price_update_tx: Sender<(u64, Vec<u8>)>,
+    liveness: Arc<Liveness>,
 ) {
+    let worker = network.worker(&format!("block_worker_{}", remainder));
     loop {
+        liveness.beat(&worker).await;
         let db_new = db.clone();
-        let rpc_new = rpc.clone();
-        let bot_new = bot.clone();
+        let bookkeeping_new = bookkeeping.clone();
+        let network_new = network.clone();
+        let notifier_new = notifier.clone();
         let modulo_new = modulo.clone();
         let remainder_new = remainder.clone();
-        let current_block_new = current_block.clone();
         let all_wallet_new = all_wallet.clone();
         let token_new = token.clone();
         let new_token_new = new_token.clone();
         let new_price_update_tx = new_price_update_tx.clone();
-        let tx_client_new = tx_client.clone();
         if let Err(err) = task::spawn(async move {
             match handle_new_block(
                 &db_new.clone(),
-                rpc_new.clone(),
-                tx_client_new.clone(),
-                bot_new.clone(),
+                &bookkeeping_new.clone(),
+                &network_new,
+                notifier_new.clone(),
                 &modulo_new.clone(),
                 &remainder_new.clone(),
-                &current_block_new.clone(),
                 &all_wallet_new.clone(),
                 &token_new.clone(),
                 &new_token_new.clone(),
@@ -141,7 +160,10 @@ pub async fn handle_new_block_loop_inner(
                     if let Err::SlotDatabaseEmpty = e {
                         tokio::time::sleep(Duration::from_millis(300)).await;
                     }
-                    error!("({}, {}) {:?}", modulo_new, remainder_new, e)
+                    error!(
+                        "{} ({}, {}) {:?}",
+                        network_new.name, modulo_new, remainder_new, e
+                    )
                 }
             };
             // tokio::time::sleep(Duration::from_secs(2)).await;
@@ -157,21 +179,22 @@ pub async fn handle_new_block_loop_inner(
     }
 }
 
-async fn handle_new_block(
+/// Claims the next block of this worker in `bookkeeping` and alerts the chats
+/// subscribed in `db` to the wallets it touches.
+pub async fn handle_new_block(
     db: &DatabaseConnection,
-    rpc: Arc<Client>,
-    tx_client: TxQueryClient,
-    bot: Bot,
+    bookkeeping: &DatabaseConnection,
+    network: &Network,
+    notifier: Notifier,
     modulo: &u8,
     remainder: &u8,
-    current_block: &RwLock<u64>,
     all_wallet: &RwLock<AHashMap<String, ()>>,
     token: &RwLock<TokenMap>,
     new_token: &RwLock<AHashSet<AccountAddress>>,
     new_price_update_tx: Sender<(u64, Vec<u8>)>,
 ) -> Result<(), Err> {
-    let this_block = get_block_to_process(db, modulo, remainder).await?;
-    let block_now = current_block.read().await;
+    let this_block = get_block_to_process(bookkeeping, &network.name, modulo, remainder).await?;
+    let block_now = network.current_block.read().await;
     if this_block > *block_now {
         let time: u64 = this_block.saturating_sub(*block_now) * 250;
         warn!("Too fast, {} > {}", this_block, *block_now);
@@ -180,7 +203,7 @@ async fn handle_new_block(
         return Ok(());
     }
 
-    let new_block = match rpc.get_block_by_height_bcs(this_block, false).await {
+    let new_block = match network.rpc.get_block_by_height_bcs(this_block, false).await {
         Ok(s) => s.inner().clone(),
         Err(err) => match err {
             _ => {
@@ -190,280 +213,439 @@ async fn handle_new_block(
     };
     let all_tracked = all_wallet.read().await;
     let new_entry = processed_block::ActiveModel {
-        block: Set(this_block),
-        modulo: Set(*modulo),
-        remainder: Set(*remainder),
+        network: Set(network.name.clone()),
+        block: Set(i64::try_from(this_block)?),
+        modulo: Set(i16::from(*modulo)),
+        remainder: Set(i16::from(*remainder)),
         ..Default::default()
     };
-    let result = new_entry.insert(db).await?;
+    let result = new_entry.insert(bookkeeping).await?;
     if all_tracked.len() == 0 {
         return Ok(());
     }
+    let timestamp = i64::try_from(new_block.block_timestamp)
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .unwrap_or_else(Utc::now);
     let new_block_txs_query = Variables {
         gte: Some(u64_to_i128(new_block.first_version)),
         lte: Some(u64_to_i128(new_block.last_version)),
     };
-    let txs = tx_client.get_tx_in_range(new_block_txs_query).await?;
+    let txs = network
+        .tx_client
+        .get_tx_in_range(new_block_txs_query)
+        .await?;
 
-    match txs.data {
+    let tx_count = match txs.data {
         Some(txs) => {
-            let mut txs_sorted = txs.clone();
-            txs_sorted
-                .fungible_asset_activities
-                .sort_by(|a, b| a.transaction_version.cmp(&b.transaction_version));
-            let mut txs_by_version: Vec<(i128, Vec<TransactionsQueryFungibleAssetActivities>)> =
-                vec![];
-            txs_sorted
-                .fungible_asset_activities
-                .into_iter()
-                .for_each(|event| {
-                    match txs_by_version
-                        .binary_search_by(|(version, _)| version.cmp(&event.transaction_version))
-                    {
-                        Ok(index) => {
-                            if let Some((version, events)) = txs_by_version.get_mut(index) {
-                                events.push(event);
-                            };
-                        }
-                        Err(_) => {
-                            txs_by_version.push((event.transaction_version, vec![event]));
-                        }
-                    };
-                });
-            let len = txs_by_version.len();
-
-            for (version, tx) in txs_by_version.into_iter() {
-                match tx.get(0) {
-                    Some(info) => {
-                        if !info.is_transaction_success {
-                            continue;
-                        }
-                    }
-                    None => {
-                        continue;
+            let subscriptions = wallet_tracked::Model::get_by_addresses(
+                db,
+                &network.name,
+                tracked_owners(&txs, &all_tracked),
+            )
+            .await?;
+            drop(all_tracked);
+            let token = token.read().await;
+            set_gauge(&TOKEN_MAP_SIZE, token.len());
+            if let Some(recorder) = &network.recorder {
+                if let Err(err) = recorder.record(&BlockFixture::new(
+                    network,
+                    new_block,
+                    &txs,
+                    &token,
+                    &subscriptions,
+                )) {
+                    error!("{} record {} error: {:?}", network.name, this_block, err);
+                }
+            }
+            let built = build_notifications(
+                txs,
+                &subscriptions,
+                &token,
+                &network.explorer,
+                &network.spam,
+            )?;
+            drop(token);
+            CHANGES_SUPPRESSED
+                .with_label_values(&[&network.name])
+                .inc_by(built.suppressed as u64);
+            RULE_ERRORS
+                .with_label_values(&[&network.name])
+                .inc_by(built.rule_errors as u64);
+            // A shadow shares production's database, its activity would be stored twice.
+            if !notifier.is_shadow() {
+                history::record(db, &network.name, this_block, timestamp, &built.activities)
+                    .await?;
+            }
+            if network.spam.auto_flag && !notifier.is_shadow() && !built.spam_tokens.is_empty() {
+                let flagged = token_info::Model::flag_spam(db, &built.spam_tokens).await?;
+                let mut update = token.write().await;
+                for entry in flagged.into_iter() {
+                    info!("{} flagged spam token {}", network.name, entry.mint);
+                    update.insert(entry.mint.clone(), entry);
+                }
+                drop(update)
+            }
+            if !built.unknown_tokens.is_empty() {
+                let mut update = new_token.write().await;
+                for address in built.unknown_tokens.iter() {
+                    if let Ok(address) = AccountAddress::from_str(address) {
+                        update.insert(address);
                     }
                 }
+                drop(update)
+            }
+            quiet::deliver(
+                db,
+                &notifier,
+                &network.name,
+                built.notifications,
+                built.digests,
+                Utc::now(),
+            )
+            .await?;
+            built.tx_count
+        }
+        None => {
+            warn!("None");
+            0
+        }
+    };
 
-                let mut keys: Vec<_> = tx
-                    .clone()
-                    .into_par_iter()
-                    .filter_map(|info| info.owner_address)
-                    .collect();
-                keys.dedup();
-                for key in keys.clone() {
-                    if all_tracked.contains_key(&key) {
-                        if let Some(balance_changes) = BalanceChange::from_events(tx.clone()) {
-                            let token = token.read().await;
-                            for change in balance_changes.clone() {
-                                if !token.contains_key(&change.to_token_address()) {
-                                    if let Ok(address) =
-                                        AccountAddress::from_str(&change.to_token_address())
-                                    {
-                                        let mut update = new_token.write().await;
-                                        update.insert(address);
-                                        drop(update)
-                                    }
-                                }
-                            }
-                            let pubkey = AccountAddress::from_str(&key)?;
-                            let filtered = balance_changes.clone().get_by_key(&pubkey);
-                            if filtered.is_empty() {
-                                continue;
-                            }
+    let update = processed_block::ActiveModel {
+        network: Set(result.network.clone()),
+        block: Set(result.block),
+        status: Set(Status::Complete),
+        tx_count: Set(tx_count.try_into().unwrap_or(i32::MAX)),
+        ..Default::default()
+    };
+    update.update(bookkeeping).await?;
+    BLOCKS_PROCESSED
+        .with_label_values(&[&network.name, &remainder.to_string()])
+        .inc();
 
-                            let all_chat =
-                                wallet_tracked::Model::get_filtered(db, Some(key)).await?;
-                            let response = filtered
-                                .clone()
-                                .into_iter()
-                                .map(|s| {
-                                    format!(
-                                        "{} $({:.3})\n",
-                                        s.to_priced_string(&(token)),
-                                        s.to_usd_change(&(token)).unwrap_or(0.0)
-                                    )
-                                })
-                                .collect::<Vec<String>>()
-                                .concat();
-                            let value_changes = filtered
-                                .clone()
-                                .into_iter()
-                                .map(|s| {
-                                    let net = s.to_usd_change(&(token)).unwrap_or(0.0);
-                                    (net.is_sign_negative(), net.abs())
-                                })
-                                .collect::<Vec<(bool, f64)>>();
-                            let full_change =
-                                value_changes.clone().into_iter().map(|c| c.1).sum::<f64>();
-                            let sent_value = value_changes
-                                .clone()
-                                .into_iter()
-                                .filter_map(|c| match c.0 {
-                                    true => Some(c.1),
-                                    false => None,
-                                })
-                                .sum::<f64>();
-                            let received_value = value_changes
-                                .clone()
-                                .into_iter()
-                                .filter_map(|c| match c.0 {
-                                    false => Some(c.1),
-                                    true => None,
-                                })
-                                .sum::<f64>();
-                            let tx_id = match tx.get(0) {
-                                Some(s) => s.transaction_version,
-                                None => {
-                                    return Err(Err::TxHasNoSig);
-                                }
-                            };
-                            let url = match reqwest::Url::parse(&format!(
-                                "https://explorer.aptoslabs.com/txn/{}?network=mainnet",
-                                tx_id
-                            )) {
-                                Ok(s) => s,
-                                Err(_) => {
-                                    return Err(Err::UrlParseError);
-                                }
-                            };
+    Ok(())
+}
 
-                            for chat in all_chat.into_iter() {
-                                if match chat.track_type {
-                                    TrackType::Full => true,
-                                    TrackType::Balance => full_change >= chat.minimum_value,
-                                    TrackType::Receive => received_value >= chat.minimum_value,
-                                    TrackType::Sent => sent_value >= chat.minimum_value,
-                                } {
-                                    let item = vec![
-                                        InlineKeyboardButton::url("TX detail", url.clone()),
-                                        InlineKeyboardButton::callback(
-                                            "Unsubscribe",
-                                            chat.to_unsub_callback_data(),
-                                        ),
-                                    ];
-                                    match bot
-                                        .send_message(ChatId(chat.chat_id), response.clone())
-                                        .reply_markup(InlineKeyboardMarkup::new([item.clone()]))
-                                        .await
-                                    {
-                                        Ok(_s) => {}
-                                        Err(err) => match err {
-                                            RequestError::RetryAfter(_) => {}
-                                            RequestError::Api(err) => {
-                                                if let ApiError::BotBlocked = err {
-                                                    let _wallet_blocked =
-                                                        WalletTracked::delete_many()
-                                                            .filter(
-                                                                wallet_tracked::Column::ChatId
-                                                                    .eq(chat.chat_id),
-                                                            )
-                                                            .exec(db)
-                                                            .await?;
-                                                    warn!(
-                                                        "{:?} is blocked deleted all ",
-                                                        chat.chat_id
-                                                    );
-                                                }
-                                            }
-                                            RequestError::MigrateToChatId(id) => {
-                                                let mut update = chat.into_active_model();
-                                                update.chat_id = Set(id);
-                                                if let Err(err) = update.update(db).await {
-                                                    error!("Error Migrating chat: {:?}", err);
-                                                };
-                                            }
-                                            _ => {
-                                                error!("{:?}", err);
-                                            }
-                                        },
-                                    };
-                                };
-                            }
-                        }
-                    }
+/// A message for one subscription about one transaction.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub subscription: wallet_tracked::Model,
+    pub version: i128,
+    pub text: String,
+    pub tx_url: reqwest::Url,
+    /// The balance changes `text` lists.
+    pub changes: Vec<Subject>,
+}
+
+/// Everything a block produces, independent of the database, RPC and Telegram.
+#[derive(Debug, Default)]
+pub struct BlockNotifications {
+    /// Transactions in the block with fungible asset activity.
+    pub tx_count: usize,
+    pub notifications: Vec<Notification>,
+    /// Tokens moved by tracked wallets that have no `token_info` row.
+    pub unknown_tokens: AHashSet<String>,
+    /// Tokens the spam heuristics caught.
+    pub spam_tokens: AHashMap<String, SpamToken>,
+    /// Balance changes left out of alerts as skipped, spam or dust.
+    pub suppressed: usize,
+    /// Subscriptions whose stored rule does not parse, they alert on nothing.
+    pub rule_errors: usize,
+    /// Balance changes of tracked wallets, whether or not they alerted.
+    pub activities: Vec<WalletActivity>,
+    /// Balance changes for subscriptions in digest mode, which get no alerts.
+    pub digests: Vec<DigestActivity>,
+}
+
+/// The balance changes of one transaction for a subscription in digest mode, whether
+/// or not they pass its thresholds, so the digest totals are complete.
+#[derive(Debug, Clone)]
+pub struct DigestActivity {
+    pub subscription: wallet_tracked::Model,
+    pub version: i128,
+    pub changes: Vec<Subject>,
+}
+
+/// A balance change of a tracked wallet in one transaction.
+#[derive(Debug, Clone)]
+pub struct WalletActivity {
+    pub version: i128,
+    pub wallet: String,
+    pub change: Subject,
+    pub counterparty: Option<String>,
+}
+
+/// The symbol the indexer reports for `mint` in one transaction's rows.
+fn symbol_of(tx: &[TransactionsQueryFungibleAssetActivities], mint: &str) -> Option<String> {
+    tx.iter()
+        .filter_map(|info| info.metadata.as_ref())
+        .find(|metadata| {
+            metadata.asset_type == mint
+                || AccountAddress::from_str(&metadata.asset_type)
+                    .map_or(false, |address| address.to_string() == mint)
+        })
+        .map(|metadata| metadata.symbol.clone())
+}
+
+/// Owners in `txs` that at least one chat tracks.
+pub fn tracked_owners(txs: &ResponseData, all_tracked: &AHashMap<String, ()>) -> Vec<String> {
+    let mut owners: Vec<String> = txs
+        .fungible_asset_activities
+        .iter()
+        .filter_map(|info| info.owner_address.clone())
+        .filter(|owner| all_tracked.contains_key(owner))
+        .collect();
+    owners.sort();
+    owners.dedup();
+    owners
+}
+
+/// Groups the indexer rows of a block by transaction and builds the messages the
+/// `subscriptions` of each owner ask for. Pure, so recorded blocks replay exactly.
+pub fn build_notifications(
+    txs: ResponseData,
+    subscriptions: &AHashMap<String, Vec<wallet_tracked::Model>>,
+    token: &TokenMap,
+    explorer: &ExplorerConfig,
+    spam: &SpamConfig,
+) -> Result<BlockNotifications, Err> {
+    let mut built = BlockNotifications::default();
+    let mut txs_sorted = txs;
+    txs_sorted
+        .fungible_asset_activities
+        .sort_by(|a, b| a.transaction_version.cmp(&b.transaction_version));
+    let mut txs_by_version: Vec<(i128, Vec<TransactionsQueryFungibleAssetActivities>)> = vec![];
+    txs_sorted
+        .fungible_asset_activities
+        .into_iter()
+        .for_each(|event| {
+            match txs_by_version
+                .binary_search_by(|(version, _)| version.cmp(&event.transaction_version))
+            {
+                Ok(index) => {
+                    if let Some((_version, events)) = txs_by_version.get_mut(index) {
+                        events.push(event);
+                    };
                 }
-                // const JUP_ROUTER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
-                // const JUP_EVENT: &str = "D8cy77BBepLMngZx6ZukaTff5hCt1HrWyKk3Hnd9oitf";
-                // let router_index = keys.clone().into_iter().position(|key| key == JUP_ROUTER);
-                // let event_index = keys.into_iter().position(|key| key == JUP_EVENT);
-                // if router_index.is_some() && event_index.is_some() {
-                //     if let Some(meta) = tx.meta {
-                //         let inner_ixs = match meta.inner_instructions {
-                //             OptionSerializer::Some(ixs) => ixs,
-                //             _ => vec![],
-                //         };
-                //         for ixs in inner_ixs {
-                //             for ix in ixs.instructions {
-                //                 if let UiInstruction::Compiled(ix_detail) = ix {
-                //                     let program_id_index: usize = ix_detail.program_id_index.into();
-                //                     let first = match ix_detail.accounts.first() {
-                //                         Some(index) => {
-                //                             let index: usize = (*index).into();
-                //                             Some(index)
-                //                         }
-                //                         None => None,
-                //                     };
+                Err(_) => {
+                    txs_by_version.push((event.transaction_version, vec![event]));
+                }
+            };
+        });
+    built.tx_count = txs_by_version.len();
+
+    // Parsed once per block. A rule that no longer parses silences its subscription
+    // instead of falling back to the track type the rule replaced.
+    let mut rules: AHashMap<i64, Option<FilterExpr>> = AHashMap::new();
+    for chat in subscriptions.values().flatten() {
+        let text = match chat.filter_expr.as_deref() {
+            Some(text) => text,
+            None => continue,
+        };
+        let rule = match FilterExpr::parse(text) {
+            Ok(rule) => Some(rule),
+            Err(err) => {
+                error!(
+                    "subscription {} rule {:?} does not parse, no alerts: {}",
+                    chat.wallet_id, text, err
+                );
+                built.rule_errors += 1;
+                None
+            }
+        };
+        rules.insert(chat.wallet_id, rule);
+    }
 
-                //                     if Some(program_id_index) == router_index
-                //                         && event_index == first
-                //                     {
-                //                         match ix_detail.data.from_base58() {
-                //                             Ok(data_byte) => {
-                //                                 if let Err(result) = new_price_update_tx
-                //                                     .send((this_block, data_byte))
-                //                                     .await
-                //                                 {
-                //                                     error!(
-                //                                         "Error sending event data: {:?}",
-                //                                         result
-                //                                     );
-                //                                 }
-                //                             }
-                //                             Err(e) => {
-                //                                 error!(
-                //                                     "base58 decode error: {:?}, data: {}",
-                //                                     e, ix_detail.data
-                //                                 );
-                //                             }
-                //                         }
-                //                     }
-                //                 }
-                //             }
-                //         }
-                //     }
-                // }
+    for (version, tx) in txs_by_version.into_iter() {
+        match tx.get(0) {
+            Some(info) => {
+                if !info.is_transaction_success {
+                    continue;
+                }
             }
+            None => {
+                continue;
+            }
+        }
 
-            let update = processed_block::ActiveModel {
-                block: Set(result.block),
-                status: Set(Status::Complete),
-                tx_count: Set(len.try_into().unwrap_or(u32::MAX)),
-                ..Default::default()
+        let mut keys: Vec<_> = tx
+            .clone()
+            .into_par_iter()
+            .filter_map(|info| info.owner_address)
+            .collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let all_chat = match subscriptions.get(&key) {
+                Some(all_chat) => all_chat,
+                None => continue,
             };
-            update.update(db).await?;
-        }
-        None => {
-            warn!("None");
-            let update = processed_block::ActiveModel {
-                block: Set(result.block),
-                status: Set(Status::Complete),
-                tx_count: Set(0),
-                ..Default::default()
+            let balance_changes = match BalanceChange::from_events(tx.clone()) {
+                Some(balance_changes) => balance_changes,
+                None => continue,
+            };
+            for change in balance_changes.iter() {
+                if !token.contains_key(&change.to_token_address()) {
+                    built.unknown_tokens.insert(change.to_token_address());
+                }
+            }
+            let pubkey = AccountAddress::from_str(&key)?;
+            let filtered = balance_changes.clone().get_by_key(&pubkey);
+            let unsolicited = filtered.iter().all(|change| change.amount() > 0);
+            let filtered = filtered
+                .into_iter()
+                .filter(|change| {
+                    let known_sender = change
+                        .counterparty(&balance_changes)
+                        .map_or(false, |sender| {
+                            subscriptions.contains_key(&format!("{sender:#x}"))
+                        });
+                    match spam::classify(change, unsolicited, known_sender, token, spam) {
+                        Verdict::Keep => true,
+                        Verdict::Spam => {
+                            let mint = change.to_token_address();
+                            let symbol = symbol_of(&tx, &mint);
+                            built.spam_tokens.insert(
+                                mint,
+                                SpamToken {
+                                    symbol,
+                                    decimals: change.decimals(),
+                                },
+                            );
+                            built.suppressed += 1;
+                            false
+                        }
+                        Verdict::Skipped | Verdict::Dust => {
+                            built.suppressed += 1;
+                            false
+                        }
+                    }
+                })
+                .collect::<Vec<BalanceChange>>();
+            if filtered.is_empty() {
+                continue;
+            }
+
+            built.activities.extend(filtered.iter().map(|change| {
+                WalletActivity {
+                    version,
+                    wallet: key.clone(),
+                    change: Subject::from_change(change, token),
+                    counterparty: change
+                        .counterparty(&balance_changes)
+                        .map(|owner| format!("{owner:#x}")),
+                }
+            }));
+
+            let url = match reqwest::Url::parse(&explorer.tx_url(version)) {
+                Ok(s) => s,
+                Err(_) => {
+                    return Err(Err::UrlParseError);
+                }
             };
-            update.update(db).await?;
+
+            for chat in all_chat.iter() {
+                let changes = filtered
+                    .iter()
+                    .filter(|change| chat.allows_token(&change.to_token_address()))
+                    .collect::<Vec<&BalanceChange>>();
+                if changes.is_empty() {
+                    continue;
+                }
+                if chat.digest != DigestMode::Off {
+                    built.digests.push(DigestActivity {
+                        subscription: chat.clone(),
+                        version,
+                        changes: changes
+                            .iter()
+                            .map(|change| Subject::from_change(change, token))
+                            .collect(),
+                    });
+                    continue;
+                }
+                let response = changes
+                    .iter()
+                    .map(|s| {
+                        format!(
+                            "{} $({:.3})\n",
+                            s.to_priced_string(token),
+                            s.to_usd_change(token).unwrap_or(0.0)
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .concat();
+                let value_changes = changes
+                    .iter()
+                    .map(|s| {
+                        let net = s.to_usd_change(token).unwrap_or(0.0);
+                        (net.is_sign_negative(), net.abs())
+                    })
+                    .collect::<Vec<(bool, f64)>>();
+                let full_change = value_changes.iter().map(|c| c.1).sum::<f64>();
+                let sent_value = value_changes
+                    .iter()
+                    .filter_map(|c| match c.0 {
+                        true => Some(c.1),
+                        false => None,
+                    })
+                    .sum::<f64>();
+                let received_value = value_changes
+                    .iter()
+                    .filter_map(|c| match c.0 {
+                        false => Some(c.1),
+                        true => None,
+                    })
+                    .sum::<f64>();
+                let rule = rules.get(&chat.wallet_id);
+                let alert = match (rule, chat.received_threshold(), chat.sent_threshold()) {
+                    (Some(Some(rule)), _, _) => rule.matches_any(&changes, token),
+                    (Some(None), _, _) => false,
+                    (None, None, None) => match chat.track_type {
+                        TrackType::Full => true,
+                        TrackType::Balance => full_change >= chat.minimum_value,
+                        TrackType::Receive => received_value >= chat.minimum_value,
+                        TrackType::Sent => sent_value >= chat.minimum_value,
+                    },
+                    (None, received, sent) => {
+                        let (sent_changes, received_changes): (Vec<&BalanceChange>, _) = changes
+                            .iter()
+                            .copied()
+                            .partition(|change| change.amount() < 0);
+                        received.map_or(false, |t| t.met(&received_changes, token))
+                            || sent.map_or(false, |t| t.met(&sent_changes, token))
+                    }
+                };
+                if alert {
+                    built.notifications.push(Notification {
+                        subscription: chat.clone(),
+                        version,
+                        text: response,
+                        tx_url: url.clone(),
+                        changes: changes
+                            .iter()
+                            .map(|change| Subject::from_change(change, token))
+                            .collect(),
+                    });
+                }
+            }
         }
     }
-
-    Ok(())
+    Ok(built)
 }
 
-async fn get_block_to_process(
+/// Next height for the worker `remainder` of `modulo` workers: the lowest height above
+/// the bottom pointer that has this remainder and no `processed_block` row yet. The
+/// caller claims it by inserting the row, the primary key keeps two claimers apart.
+pub async fn get_block_to_process(
     db: &DatabaseConnection,
+    network: &str,
     modulo: &u8,
     remainder: &u8,
 ) -> Result<u64, Err> {
     let bottom_last = match processed_block::Model::get_last_block(
         db,
+        network,
         None,
         Some(sea_orm_active_enums::Place::Bottom),
         true,
@@ -474,6 +656,7 @@ async fn get_block_to_process(
         None => {
             match processed_block::Model::get_last_block(
                 db,
+                network,
                 None,
                 Some(sea_orm_active_enums::Place::Old),
                 false,
@@ -490,6 +673,7 @@ async fn get_block_to_process(
     let new_blocks = ProcessedBlock::find()
         .select_only()
         .column(processed_block::Column::Block)
+        .filter(processed_block::Column::Network.eq(network))
         .filter(processed_block::Column::Block.gte(bottom_last))
         .order_by_asc(processed_block::Column::Block)
         .limit(Some(modulo + 1))
@@ -497,8 +681,8 @@ async fn get_block_to_process(
         .all(db)
         .await?
         .into_iter()
-        .map(|s| s.block)
-        .collect::<Vec<u64>>();
+        .map(|s| u64::try_from(s.block))
+        .collect::<Result<Vec<u64>, _>>()?;
     match new_blocks.first() {
         Some(s) => {
             if *s != bottom_last {
@@ -548,7 +732,7 @@ async fn get_block_to_process(
 
     for index in 0..20 * modulo {
         let to_check = to_check + index * modulo;
-        if processed_block::Model::get_block(db, to_check)
+        if processed_block::Model::get_block(db, network, to_check)
             .await?
             .is_none()
         {
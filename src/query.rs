@@ -4,7 +4,7 @@ pub mod transactions_query {
     #![allow(dead_code)]
     use std::result::Result;
     pub const OPERATION_NAME: &str = "TransactionsQuery";
-    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n" ;
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
     use super::*;
     use serde::{Deserialize, Serialize};
     #[allow(dead_code)]
@@ -25,11 +25,11 @@ pub mod transactions_query {
         pub lte: Option<bigint>,
     }
     impl Variables {}
-    #[derive(Deserialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug)]
     pub struct ResponseData {
         pub fungible_asset_activities: Vec<TransactionsQueryFungibleAssetActivities>,
     }
-    #[derive(Deserialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug)]
     pub struct TransactionsQueryFungibleAssetActivities {
         pub amount: Option<numeric>,
         pub asset_type: Option<String>,
@@ -42,7 +42,7 @@ pub mod transactions_query {
         pub event_index: bigint,
         pub token_standard: String,
     }
-    #[derive(Deserialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug)]
     pub struct TransactionsQueryFungibleAssetActivitiesMetadata {
         pub decimals: Int,
         pub name: String,
@@ -67,7 +67,7 @@ pub mod transaction_query {
     #![allow(dead_code)]
     use std::result::Result;
     pub const OPERATION_NAME: &str = "TransactionQuery";
-    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n" ;
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
     use super::*;
     use serde::{Deserialize, Serialize};
     #[allow(dead_code)]
@@ -86,11 +86,11 @@ pub mod transaction_query {
         pub eq: Option<bigint>,
     }
     impl Variables {}
-    #[derive(Deserialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug)]
     pub struct ResponseData {
         pub fungible_asset_activities: Vec<TransactionQueryFungibleAssetActivities>,
     }
-    #[derive(Deserialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug)]
     pub struct TransactionQueryFungibleAssetActivities {
         pub amount: Option<numeric>,
         pub asset_type: Option<String>,
@@ -103,7 +103,7 @@ pub mod transaction_query {
         pub event_index: bigint,
         pub token_standard: String,
     }
-    #[derive(Deserialize, Clone, Debug)]
+    #[derive(Deserialize, Serialize, Clone, Debug)]
     pub struct TransactionQueryFungibleAssetActivitiesMetadata {
         pub decimals: Int,
         pub name: String,
@@ -123,3 +123,268 @@ impl graphql_client::GraphQLQuery for TransactionQuery {
         }
     }
 }
+pub struct IndexerStatusQuery;
+pub mod indexer_status_query {
+    #![allow(dead_code)]
+    use std::result::Result;
+    pub const OPERATION_NAME: &str = "IndexerStatusQuery";
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    #[allow(dead_code)]
+    type Boolean = bool;
+    #[allow(dead_code)]
+    type Float = f64;
+    #[allow(dead_code)]
+    type Int = u8;
+    #[allow(dead_code)]
+    type ID = String;
+    type bigint = i128;
+    type timestamp = String;
+    #[derive(Serialize, Clone, Debug)]
+    pub struct Variables {
+        pub processor: Option<String>,
+    }
+    impl Variables {}
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct ResponseData {
+        pub processor_status: Vec<IndexerStatusQueryProcessorStatus>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct IndexerStatusQueryProcessorStatus {
+        pub processor: String,
+        pub last_success_version: bigint,
+        pub last_updated: timestamp,
+    }
+}
+impl graphql_client::GraphQLQuery for IndexerStatusQuery {
+    type Variables = indexer_status_query::Variables;
+    type ResponseData = indexer_status_query::ResponseData;
+    fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
+        graphql_client::QueryBody {
+            variables,
+            query: indexer_status_query::QUERY,
+            operation_name: indexer_status_query::OPERATION_NAME,
+        }
+    }
+}
+pub struct TokenMetadataQuery;
+pub mod token_metadata_query {
+    #![allow(dead_code)]
+    use std::result::Result;
+    pub const OPERATION_NAME: &str = "TokenMetadataQuery";
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    #[allow(dead_code)]
+    type Boolean = bool;
+    #[allow(dead_code)]
+    type Float = f64;
+    #[allow(dead_code)]
+    type Int = u8;
+    #[allow(dead_code)]
+    type ID = String;
+    #[derive(Serialize, Clone, Debug)]
+    pub struct Variables {
+        pub assets: Option<Vec<String>>,
+    }
+    impl Variables {}
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct ResponseData {
+        pub fungible_asset_metadata: Vec<TokenMetadataQueryFungibleAssetMetadata>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct TokenMetadataQueryFungibleAssetMetadata {
+        pub asset_type: String,
+        pub decimals: Int,
+        pub name: String,
+        pub symbol: String,
+    }
+}
+impl graphql_client::GraphQLQuery for TokenMetadataQuery {
+    type Variables = token_metadata_query::Variables;
+    type ResponseData = token_metadata_query::ResponseData;
+    fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
+        graphql_client::QueryBody {
+            variables,
+            query: token_metadata_query::QUERY,
+            operation_name: token_metadata_query::OPERATION_NAME,
+        }
+    }
+}
+pub struct WalletActivityQuery;
+pub mod wallet_activity_query {
+    #![allow(dead_code)]
+    use std::result::Result;
+    pub const OPERATION_NAME: &str = "WalletActivityQuery";
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    #[allow(dead_code)]
+    type Boolean = bool;
+    #[allow(dead_code)]
+    type Float = f64;
+    #[allow(dead_code)]
+    type Int = u8;
+    #[allow(dead_code)]
+    type ID = String;
+    type bigint = i128;
+    type numeric = i128;
+    type timestamp = String;
+    #[derive(Serialize, Clone, Debug)]
+    pub struct Variables {
+        pub owner: Option<String>,
+        pub from: Option<timestamp>,
+        pub to: Option<timestamp>,
+        pub after: Option<bigint>,
+    }
+    impl Variables {}
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct ResponseData {
+        pub fungible_asset_activities: Vec<WalletActivityQueryFungibleAssetActivities>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct WalletActivityQueryFungibleAssetActivities {
+        pub amount: Option<numeric>,
+        pub asset_type: Option<String>,
+        pub metadata: Option<WalletActivityQueryFungibleAssetActivitiesMetadata>,
+        pub transaction_version: bigint,
+        pub transaction_timestamp: timestamp,
+        pub owner_address: Option<String>,
+        #[serde(rename = "type")]
+        pub type_: String,
+        pub event_index: bigint,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct WalletActivityQueryFungibleAssetActivitiesMetadata {
+        pub decimals: Int,
+        pub symbol: String,
+        pub asset_type: String,
+    }
+}
+impl graphql_client::GraphQLQuery for WalletActivityQuery {
+    type Variables = wallet_activity_query::Variables;
+    type ResponseData = wallet_activity_query::ResponseData;
+    fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
+        graphql_client::QueryBody {
+            variables,
+            query: wallet_activity_query::QUERY,
+            operation_name: wallet_activity_query::OPERATION_NAME,
+        }
+    }
+}
+pub struct VersionsActivityQuery;
+pub mod versions_activity_query {
+    #![allow(dead_code)]
+    use std::result::Result;
+    pub const OPERATION_NAME: &str = "VersionsActivityQuery";
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    #[allow(dead_code)]
+    type Boolean = bool;
+    #[allow(dead_code)]
+    type Float = f64;
+    #[allow(dead_code)]
+    type Int = u8;
+    #[allow(dead_code)]
+    type ID = String;
+    type bigint = i128;
+    type numeric = i128;
+    #[derive(Serialize, Clone, Debug)]
+    pub struct Variables {
+        pub versions: Option<Vec<bigint>>,
+    }
+    impl Variables {}
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct ResponseData {
+        pub fungible_asset_activities: Vec<VersionsActivityQueryFungibleAssetActivities>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct VersionsActivityQueryFungibleAssetActivities {
+        pub amount: Option<numeric>,
+        pub asset_type: Option<String>,
+        pub metadata: Option<VersionsActivityQueryFungibleAssetActivitiesMetadata>,
+        pub transaction_version: bigint,
+        pub owner_address: Option<String>,
+        #[serde(rename = "type")]
+        pub type_: String,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct VersionsActivityQueryFungibleAssetActivitiesMetadata {
+        pub decimals: Int,
+        pub asset_type: String,
+    }
+}
+impl graphql_client::GraphQLQuery for VersionsActivityQuery {
+    type Variables = versions_activity_query::Variables;
+    type ResponseData = versions_activity_query::ResponseData;
+    fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
+        graphql_client::QueryBody {
+            variables,
+            query: versions_activity_query::QUERY,
+            operation_name: versions_activity_query::OPERATION_NAME,
+        }
+    }
+}
+pub struct BalancesQuery;
+pub mod balances_query {
+    #![allow(dead_code)]
+    use std::result::Result;
+    pub const OPERATION_NAME: &str = "BalancesQuery";
+    pub const QUERY : & str = "query TransactionsQuery($_gte: bigint, $_lte: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _gte: $_gte, _lte: $_lte } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery TransactionQuery($_eq: bigint) {\n  fungible_asset_activities(\n    where: { transaction_version: { _eq: $_eq } }\n    order_by: { event_index: asc }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      name\n      symbol\n      token_standard\n      asset_type\n    }\n    transaction_version\n    is_transaction_success\n    owner_address\n    type\n    event_index\n    token_standard\n  }\n}\n\nquery IndexerStatusQuery($processor: String) {\n  processor_status(where: { processor: { _eq: $processor } }) {\n    processor\n    last_success_version\n    last_updated\n  }\n}\n\nquery TokenMetadataQuery($assets: [String!]) {\n  fungible_asset_metadata(where: { asset_type: { _in: $assets } }) {\n    asset_type\n    decimals\n    name\n    symbol\n  }\n}\n\nquery WalletActivityQuery(\n  $owner: String\n  $from: timestamp\n  $to: timestamp\n  $after: bigint\n) {\n  fungible_asset_activities(\n    where: {\n      owner_address: { _eq: $owner }\n      transaction_timestamp: { _gte: $from, _lt: $to }\n      transaction_version: { _gt: $after }\n      is_transaction_success: { _eq: true }\n    }\n    order_by: [{ transaction_version: asc }, { event_index: asc }]\n    limit: 500\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n      asset_type\n    }\n    transaction_version\n    transaction_timestamp\n    owner_address\n    type\n    event_index\n  }\n}\n\nquery VersionsActivityQuery($versions: [bigint!]) {\n  fungible_asset_activities(\n    where: {\n      transaction_version: { _in: $versions }\n      is_transaction_success: { _eq: true }\n    }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      asset_type\n    }\n    transaction_version\n    owner_address\n    type\n  }\n}\n\nquery BalancesQuery($owner: String) {\n  current_fungible_asset_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    asset_type\n    metadata {\n      decimals\n      symbol\n    }\n  }\n  current_coin_balances(\n    where: { owner_address: { _eq: $owner }, amount: { _gt: 0 } }\n  ) {\n    amount\n    coin_type\n    coin_info {\n      decimals\n      symbol\n    }\n  }\n}\n" ;
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    #[allow(dead_code)]
+    type Boolean = bool;
+    #[allow(dead_code)]
+    type Float = f64;
+    #[allow(dead_code)]
+    type Int = u8;
+    #[allow(dead_code)]
+    type ID = String;
+    type numeric = i128;
+    #[derive(Serialize, Clone, Debug)]
+    pub struct Variables {
+        pub owner: Option<String>,
+    }
+    impl Variables {}
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct ResponseData {
+        pub current_fungible_asset_balances: Vec<BalancesQueryCurrentFungibleAssetBalances>,
+        pub current_coin_balances: Vec<BalancesQueryCurrentCoinBalances>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct BalancesQueryCurrentFungibleAssetBalances {
+        pub amount: numeric,
+        pub asset_type: String,
+        pub metadata: Option<BalancesQueryCurrentFungibleAssetBalancesMetadata>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct BalancesQueryCurrentFungibleAssetBalancesMetadata {
+        pub decimals: Int,
+        pub symbol: String,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct BalancesQueryCurrentCoinBalances {
+        pub amount: Option<numeric>,
+        pub coin_type: Option<String>,
+        pub coin_info: Option<BalancesQueryCurrentCoinBalancesCoinInfo>,
+    }
+    #[derive(Deserialize, Serialize, Clone, Debug)]
+    pub struct BalancesQueryCurrentCoinBalancesCoinInfo {
+        pub decimals: Option<Int>,
+        pub symbol: Option<String>,
+    }
+}
+impl graphql_client::GraphQLQuery for BalancesQuery {
+    type Variables = balances_query::Variables;
+    type ResponseData = balances_query::ResponseData;
+    fn build_query(variables: Self::Variables) -> ::graphql_client::QueryBody<Self::Variables> {
+        graphql_client::QueryBody {
+            variables,
+            query: balances_query::QUERY,
+            operation_name: balances_query::OPERATION_NAME,
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Muted subscriptions and per chat quiet hours. Alerts of a muted subscription are
+//! dropped, alerts arriving during quiet hours are held in `queued_alert` and sent as
+//! one summary per chat once the window ends.
+
+use ahash::AHashMap;
+use chrono::{DateTime, TimeDelta, Timelike, Utc};
+use futures::{future::Pending, stream::Abortable};
+use log::{error, info};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::digest;
+use crate::entities::{chat_setting, prelude::*, queued_alert, wallet_tracked};
+use crate::error::Err;
+use crate::metrics::NOTIFICATIONS_HELD;
+use crate::notifier::{Notifier, MESSAGE_LIMIT};
+use crate::process_new_block::{DigestActivity, Notification};
+
+const DAY: i32 = 24 * 60;
+const FLUSH_EVERY: Duration = Duration::from_secs(60);
+
+/// A daily window in local minutes after midnight, wrapping past midnight when
+/// `start > end`. `start == end` is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: u16,
+    pub end: u16,
+    /// Minutes local time is ahead of UTC.
+    pub utc_offset: i16,
+}
+
+impl QuietHours {
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let minute = local_minute(now, self.utc_offset);
+        match self.start <= self.end {
+            true => self.start <= minute && minute < self.end,
+            false => minute >= self.start || minute < self.end,
+        }
+    }
+    /// Parses `22:00-07:00`, optionally followed by the UTC offset as `+2`, `UTC-5`
+    /// or `+05:30`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut words = text.split_whitespace();
+        let window = words.next().ok_or("window missing, ex: 22:00-07:00")?;
+        let (start, end) = window
+            .split_once('-')
+            .ok_or_else(|| format!("{window}: expected start-end, ex: 22:00-07:00"))?;
+        let utc_offset = match words.next() {
+            Some(offset) => parse_offset(offset)?,
+            None => 0,
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected {extra}"));
+        }
+        Ok(QuietHours {
+            start: parse_clock(start)?,
+            end: parse_clock(end)?,
+            utc_offset,
+        })
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} {}",
+            format_clock(self.start),
+            format_clock(self.end),
+            format_offset(self.utc_offset)
+        )
+    }
+}
+
+pub(crate) fn format_clock(minute: u16) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+pub(crate) fn format_offset(utc_offset: i16) -> String {
+    let sign = match utc_offset < 0 {
+        true => '-',
+        false => '+',
+    };
+    let offset = utc_offset.unsigned_abs();
+    format!("UTC{sign}{:02}:{:02}", offset / 60, offset % 60)
+}
+
+impl chat_setting::Model {
+    /// `None` when the chat has no quiet hours.
+    pub fn quiet_hours(&self) -> Option<QuietHours> {
+        match (self.quiet_start, self.quiet_end) {
+            (Some(start), Some(end)) => Some(QuietHours {
+                start: u16::try_from(start).ok()?,
+                end: u16::try_from(end).ok()?,
+                utc_offset: self.utc_offset,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl wallet_tracked::Model {
+    pub fn is_muted(&self, now: DateTime<Utc>) -> bool {
+        self.muted_until.map_or(false, |until| until > now)
+    }
+}
+
+fn local_minute(now: DateTime<Utc>, utc_offset: i16) -> u16 {
+    let minute = i32::try_from(now.hour() * 60 + now.minute()).unwrap_or(0) + i32::from(utc_offset);
+    u16::try_from(minute.rem_euclid(DAY)).unwrap_or(0)
+}
+
+pub(crate) fn parse_clock(text: &str) -> Result<u16, String> {
+    let (hour, minute) = text.split_once(':').unwrap_or((text, "0"));
+    match (u16::from_str(hour), u16::from_str(minute)) {
+        (Ok(hour), Ok(minute)) if hour < 24 && minute < 60 => Ok(hour * 60 + minute),
+        _ => Err(format!("{text} is not a time, ex: 07:30")),
+    }
+}
+
+pub(crate) fn parse_offset(text: &str) -> Result<i16, String> {
+    let invalid = || format!("{text} is not a UTC offset, ex: +2 or UTC-05:30");
+    let lower = text.to_lowercase();
+    let rest = lower.strip_prefix("utc").unwrap_or(&lower);
+    let (sign, rest) = match (rest.strip_prefix('+'), rest.strip_prefix('-')) {
+        (Some(rest), _) => (1, rest),
+        (_, Some(rest)) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let (hour, minute) = rest.split_once(':').unwrap_or((rest, "0"));
+    match (u8::from_str(hour), u8::from_str(minute)) {
+        (Ok(hour), Ok(minute)) if hour <= 14 && minute < 60 => {
+            Ok(sign * (i16::from(hour) * 60 + i16::from(minute)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses `30m`, `8h`, `2d` or `1w`.
+pub fn parse_duration(text: &str) -> Option<TimeDelta> {
+    let text = text.trim().to_lowercase();
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = text.split_at(split);
+    let amount = i64::from_str(amount).ok().filter(|amount| *amount > 0)?;
+    let minutes = match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => 1,
+        "h" | "hour" | "hours" => 60,
+        "d" | "day" | "days" => i64::from(DAY),
+        "w" | "week" | "weeks" => 7 * i64::from(DAY),
+        _ => return None,
+    };
+    TimeDelta::try_minutes(amount.checked_mul(minutes)?)
+}
+
+/// Sets or clears the quiet hours of `chat_id`.
+pub async fn set_quiet_hours(
+    db: &DatabaseConnection,
+    chat_id: i64,
+    quiet: Option<QuietHours>,
+) -> Result<(), Err> {
+    let to_column = |minute: u16| i16::try_from(minute).ok();
+    chat_setting::Model::upsert(db, chat_id, |model| {
+        if let Some(quiet) = quiet {
+            model.utc_offset = Set(quiet.utc_offset);
+        }
+        model.quiet_start = Set(quiet.and_then(|quiet| to_column(quiet.start)));
+        model.quiet_end = Set(quiet.and_then(|quiet| to_column(quiet.end)));
+    })
+    .await?;
+    Ok(())
+}
+
+async fn quiet_chats(
+    db: &DatabaseConnection,
+    chats: Vec<i64>,
+) -> Result<AHashMap<i64, QuietHours>, Err> {
+    Ok(ChatSetting::find()
+        .filter(chat_setting::Column::ChatId.is_in(chats))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|setting| Some((setting.chat_id, setting.quiet_hours()?)))
+        .collect())
+}
+
+/// Sends `notifications`, except those of muted subscriptions, which are dropped, and
+/// those of chats in quiet hours, which are held for the summary. `digests` are
+/// collected for the digests of their subscriptions. A shadow holds nothing, the alerts
+/// production holds are dropped.
+pub async fn deliver(
+    db: &DatabaseConnection,
+    notifier: &Notifier,
+    network: &str,
+    notifications: Vec<Notification>,
+    digests: Vec<DigestActivity>,
+    now: DateTime<Utc>,
+) -> Result<(), Err> {
+    for activity in digests.iter() {
+        if activity.subscription.is_muted(now) {
+            NOTIFICATIONS_HELD.with_label_values(&["muted"]).inc();
+            continue;
+        }
+        if !notifier.is_shadow() {
+            digest::record(db, activity, now).await?;
+        }
+        NOTIFICATIONS_HELD.with_label_values(&["digest"]).inc();
+    }
+    if notifications.is_empty() {
+        return Ok(());
+    }
+    let mut chats: Vec<i64> = notifications
+        .iter()
+        .map(|notification| notification.subscription.chat_id)
+        .collect();
+    chats.sort();
+    chats.dedup();
+    let quiet = quiet_chats(db, chats).await?;
+    for notification in notifications.into_iter() {
+        let subscription = &notification.subscription;
+        if subscription.is_muted(now) {
+            NOTIFICATIONS_HELD.with_label_values(&["muted"]).inc();
+            continue;
+        }
+        if quiet
+            .get(&subscription.chat_id)
+            .map_or(false, |quiet| quiet.contains(now))
+        {
+            NOTIFICATIONS_HELD.with_label_values(&["quiet"]).inc();
+            if notifier.is_shadow() {
+                continue;
+            }
+            queued_alert::ActiveModel {
+                chat_id: Set(subscription.chat_id),
+                wallet_id: Set(subscription.wallet_id),
+                label: Set(subscription.clone().display_name()),
+                network: Set(network.to_string()),
+                version: Set(i64::try_from(notification.version)?),
+                text: Set(notification.text),
+                tx_url: Set(notification.tx_url.to_string()),
+                create_time: Set(now),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            continue;
+        }
+        notifier.send(db, network, notification).await?;
+    }
+    Ok(())
+}
+
+/// Sends one summary to every chat holding alerts whose quiet hours are over, then
+/// drops the held alerts it delivered. Returns the number of summaries.
+pub async fn flush_queued(
+    db: &DatabaseConnection,
+    notifier: &Notifier,
+    now: DateTime<Utc>,
+) -> Result<usize, Err> {
+    let mut by_chat: BTreeMap<i64, Vec<queued_alert::Model>> = BTreeMap::new();
+    for alert in QueuedAlert::find()
+        .order_by_asc(queued_alert::Column::Id)
+        .all(db)
+        .await?
+    {
+        by_chat.entry(alert.chat_id).or_default().push(alert);
+    }
+    if by_chat.is_empty() {
+        return Ok(0);
+    }
+    let quiet = quiet_chats(db, by_chat.keys().copied().collect()).await?;
+    let mut sent = 0;
+    for (chat_id, alerts) in by_chat.into_iter() {
+        if quiet
+            .get(&chat_id)
+            .map_or(false, |quiet| quiet.contains(now))
+        {
+            continue;
+        }
+        // Kept for the next flush when the summary does not go out.
+        if let Err(err) = notifier.summary(chat_id, summary_text(&alerts)).await {
+            error!("quiet hours summary for {} failed: {:?}", chat_id, err);
+            continue;
+        }
+        QueuedAlert::delete_many()
+            .filter(queued_alert::Column::Id.is_in(alerts.iter().map(|alert| alert.id)))
+            .exec(db)
+            .await?;
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+fn summary_text(alerts: &[queued_alert::Model]) -> String {
+    let mut text = format!("Quiet hours are over, {} alerts were held\n", alerts.len());
+    for (index, alert) in alerts.iter().enumerate() {
+        let entry = format!(
+            "\n{} on {}\n{}{}\n",
+            alert.label, alert.network, alert.text, alert.tx_url
+        );
+        let more = format!("\n...and {} more", alerts.len() - index);
+        if text.len() + entry.len() + more.len() > MESSAGE_LIMIT {
+            text.push_str(&more);
+            break;
+        }
+        text.push_str(&entry);
+    }
+    text
+}
+
+/// Sends the summaries of chats whose quiet hours ended, once a minute. Not run by a
+/// shadow, the queue belongs to production.
+pub async fn quiet_hours_loop(
+    abort: Abortable<Pending<()>>,
+    db: Arc<DatabaseConnection>,
+    notifier: Notifier,
+) {
+    if notifier.is_shadow() {
+        info!("shadow mode, quiet hours are left to production");
+        return;
+    }
+    loop {
+        tokio::time::sleep(FLUSH_EVERY).await;
+        if abort.is_aborted() {
+            info!("abort");
+            break;
+        }
+        if let Err(e) = flush_queued(db.as_ref(), &notifier, Utc::now()).await {
+            error!("quiet hours flush {:?}", e);
+        }
+    }
+}
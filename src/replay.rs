@@ -0,0 +1,177 @@
+//! Recording of what each processed block read from the fullnode, the indexer and
+//! the database, and offline replay of the recordings through `build_notifications`.
+
+use ahash::AHashMap;
+use aptos_sdk::rest_client::aptos_api_types::BcsBlock;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{ExplorerConfig, SpamConfig};
+use crate::db::TokenMap;
+use crate::entities::{token_info, wallet_tracked};
+use crate::error::Err;
+use crate::network::Network;
+use crate::process_new_block::{build_notifications, BlockNotifications};
+use crate::query::transactions_query::ResponseData;
+use crate::tx::BalanceChange;
+
+const USAGE: &str = "usage: replay <dir> <network> <from> [to]";
+
+/// Everything `build_notifications` needs for one block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockFixture {
+    pub network: String,
+    pub block: BcsBlock,
+    pub explorer: ExplorerConfig,
+    /// Missing in fixtures recorded before spam suppression.
+    #[serde(default)]
+    pub spam: SpamConfig,
+    pub indexer: ResponseData,
+    /// `token_info` rows of the assets moved in the block.
+    pub tokens: Vec<token_info::Model>,
+    /// Subscriptions of the tracked owners in the block.
+    pub subscriptions: Vec<wallet_tracked::Model>,
+}
+
+impl BlockFixture {
+    pub fn new(
+        network: &Network,
+        block: BcsBlock,
+        indexer: &ResponseData,
+        token: &TokenMap,
+        subscriptions: &AHashMap<String, Vec<wallet_tracked::Model>>,
+    ) -> Self {
+        let mut tokens: Vec<token_info::Model> = vec![];
+        let native = BalanceChange::default().to_token_address();
+        let moved = indexer
+            .fungible_asset_activities
+            .iter()
+            .flat_map(|activity| {
+                [
+                    activity.asset_type.clone(),
+                    activity.metadata.as_ref().map(|m| m.asset_type.clone()),
+                ]
+            })
+            .flatten()
+            .chain([native]);
+        for mint in moved {
+            if let Some(entry) = token.get(&mint) {
+                if !tokens.iter().any(|t| t.mint == entry.mint) {
+                    tokens.push(entry.clone());
+                }
+            }
+        }
+        BlockFixture {
+            network: network.name.clone(),
+            block,
+            explorer: network.explorer.clone(),
+            spam: network.spam.clone(),
+            indexer: indexer.clone(),
+            tokens,
+            subscriptions: subscriptions.values().flatten().cloned().collect(),
+        }
+    }
+
+    pub fn notifications(&self) -> Result<BlockNotifications, Err> {
+        let token: TokenMap = self
+            .tokens
+            .iter()
+            .map(|entry| (entry.mint.clone(), entry.clone()))
+            .collect();
+        let mut subscriptions: AHashMap<String, Vec<wallet_tracked::Model>> = AHashMap::new();
+        for subscription in self.subscriptions.iter() {
+            subscriptions
+                .entry(subscription.wallet_address.clone())
+                .or_default()
+                .push(subscription.clone());
+        }
+        build_notifications(
+            self.indexer.clone(),
+            &subscriptions,
+            &token,
+            &self.explorer,
+            &self.spam,
+        )
+    }
+}
+
+/// Writes a fixture per processed block to `<dir>/<network>/<height>.json`.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    dir: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Recorder { dir: dir.into() }
+    }
+    pub fn record(&self, fixture: &BlockFixture) -> Result<(), Err> {
+        let path = fixture_path(&self.dir, &fixture.network, fixture.block.block_height);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(fixture)?)?;
+        Ok(())
+    }
+}
+
+pub fn fixture_path(dir: &Path, network: &str, height: u64) -> PathBuf {
+    dir.join(network).join(format!("{height}.json"))
+}
+
+/// Replays the recorded blocks `from..=to` of `network` and prints the messages
+/// they produce. Missing heights are skipped. Returns the number of messages.
+pub fn replay(dir: &Path, network: &str, from: u64, to: u64) -> Result<usize, Err> {
+    let mut count = 0;
+    for height in from..=to {
+        let path = fixture_path(dir, network, height);
+        let fixture: BlockFixture = match std::fs::read(&path) {
+            Ok(raw) => serde_json::from_slice(&raw)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let built = fixture.notifications()?;
+        println!(
+            "[{}] block {} ({} txs, {} messages)",
+            network,
+            height,
+            built.tx_count,
+            built.notifications.len()
+        );
+        for notification in built.notifications.iter() {
+            println!(
+                "  chat {} wallet {} ({:?}) version {}\n  {}\n  {}",
+                notification.subscription.chat_id,
+                notification.subscription.wallet_address,
+                notification.subscription.track_type,
+                notification.version,
+                notification.text.trim_end().replace('\n', "\n  "),
+                notification.tx_url
+            );
+        }
+        count += built.notifications.len();
+    }
+    Ok(count)
+}
+
+/// Entry point of `aptos-tracking replay <dir> <network> <from> [to]`.
+pub fn run(args: &[String]) -> Result<(), Err> {
+    let (dir, network, from) = match args {
+        [dir, network, from, ..] => (dir, network, from.parse::<u64>()?),
+        _ => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+    };
+    let to = match args.get(3) {
+        Some(to) => to.parse::<u64>()?,
+        None => from,
+    };
+    let count = replay(Path::new(dir), network, from, to)?;
+    info!(
+        "replayed {}..={} of {}, {} messages",
+        from, to, network, count
+    );
+    Ok(())
+}
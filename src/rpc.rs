@@ -1 +1,252 @@
+use aptos_sdk::rest_client::aptos_api_types::BcsBlock;
+use aptos_sdk::rest_client::error::RestError;
+use aptos_sdk::rest_client::{Client, Response, State};
+use futures::{future::Pending, stream::Abortable};
+use log::{info, warn};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use url::Url;
 
+use crate::config::RpcConfig;
+use crate::metrics::RPC_ERRORS;
+
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Default)]
+struct NodeHealth {
+    height: u64,
+    latency_ms: Option<f64>,
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+struct RpcNode {
+    url: Url,
+    client: Client,
+    health: RwLock<NodeHealth>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeStatus {
+    pub url: Url,
+    pub height: u64,
+    pub latency_ms: Option<f64>,
+    pub failures: u32,
+    pub open: bool,
+}
+
+/// A set of fullnodes that routes each call to the healthiest one and fails over
+/// to the next when it errors.
+pub struct RpcPool {
+    /// Network label for metrics.
+    network: String,
+    nodes: Vec<RpcNode>,
+    /// Consecutive failures before a node is taken out of rotation.
+    breaker_threshold: u32,
+    /// How long a tripped node stays out before it gets a trial request.
+    breaker_cooldown: Duration,
+    max_height_lag: u64,
+    refresh_interval: Duration,
+}
+
+impl RpcPool {
+    pub fn new(network: &str, urls: Vec<Url>, config: &RpcConfig) -> Self {
+        let mut nodes: Vec<RpcNode> = vec![];
+        for url in urls {
+            if nodes.iter().any(|node| node.url == url) {
+                continue;
+            }
+            nodes.push(RpcNode {
+                client: Client::new(url.clone()),
+                url,
+                health: RwLock::new(NodeHealth::default()),
+            });
+        }
+        assert!(!nodes.is_empty(), "RPC pool needs at least one url");
+        RpcPool {
+            network: network.to_string(),
+            nodes,
+            breaker_threshold: config.breaker_threshold,
+            breaker_cooldown: Duration::from_secs(config.breaker_cooldown_secs),
+            max_height_lag: config.max_height_lag,
+            refresh_interval: Duration::from_secs(config.refresh_secs),
+        }
+    }
+
+    pub fn from_config(network: &str, config: &RpcConfig) -> Self {
+        Self::new(
+            network,
+            config
+                .urls
+                .iter()
+                .map(|url| Url::parse(url).expect("RPC url parse error"))
+                .collect(),
+            config,
+        )
+    }
+
+    pub async fn get_ledger_information(&self) -> Result<Response<State>, RestError> {
+        self.call("get_ledger_information", 0, |client| async move {
+            client.get_ledger_information().await
+        })
+        .await
+    }
+
+    pub async fn get_block_by_height_bcs(
+        &self,
+        height: u64,
+        with_transactions: bool,
+    ) -> Result<Response<BcsBlock>, RestError> {
+        self.call("get_block_by_height", height, |client| async move {
+            client
+                .get_block_by_height_bcs(height, with_transactions)
+                .await
+        })
+        .await
+    }
+
+    pub async fn best_height(&self) -> u64 {
+        let mut best = 0;
+        for node in self.nodes.iter() {
+            best = best.max(node.health.read().await.height);
+        }
+        best
+    }
+
+    pub async fn status(&self) -> Vec<NodeStatus> {
+        let now = Instant::now();
+        let mut status = vec![];
+        for node in self.nodes.iter() {
+            let health = node.health.read().await;
+            status.push(NodeStatus {
+                url: node.url.clone(),
+                height: health.height,
+                latency_ms: health.latency_ms,
+                failures: health.failures,
+                open: health.open_until.map_or(false, |until| until > now),
+            });
+        }
+        status
+    }
+
+    /// Polls every node, closed breakers included, so heights and latency stay current.
+    pub async fn refresh(&self) {
+        for node in self.nodes.iter() {
+            let start = Instant::now();
+            match node.client.get_ledger_information().await {
+                Ok(info) => {
+                    let height = info.inner().block_height;
+                    node.record_success(start.elapsed(), Some(height)).await;
+                }
+                Err(e) => {
+                    warn!("RPC {} health check failed: {:?}", node.url, e);
+                    node.record_failure(self.breaker_threshold, self.breaker_cooldown)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Nodes ordered by preference: closed breaker, caught up to `min_height`
+    /// and the best known height, then lowest latency.
+    async fn ranked(&self, min_height: u64) -> Vec<&RpcNode> {
+        let now = Instant::now();
+        let best = self.best_height().await;
+        let mut scored = vec![];
+        for (index, node) in self.nodes.iter().enumerate() {
+            let health = node.health.read().await;
+            let open = health.open_until.map_or(false, |until| until > now);
+            let behind = health.height < min_height
+                || best.saturating_sub(health.height) > self.max_height_lag;
+            let latency = health.latency_ms.unwrap_or(f64::MAX);
+            scored.push((open, behind, latency, index, node));
+        }
+        scored.sort_by(|a, b| {
+            (a.0, a.1)
+                .cmp(&(b.0, b.1))
+                .then(a.2.total_cmp(&b.2))
+                .then(a.3.cmp(&b.3))
+        });
+        scored.into_iter().map(|(.., node)| node).collect()
+    }
+
+    async fn call<'a, T, F, Fut>(
+        &'a self,
+        name: &str,
+        min_height: u64,
+        f: F,
+    ) -> Result<Response<T>, RestError>
+    where
+        F: Fn(&'a Client) -> Fut,
+        Fut: Future<Output = Result<Response<T>, RestError>>,
+    {
+        let mut last_err = None;
+        for node in self.ranked(min_height).await {
+            let start = Instant::now();
+            match f(&node.client).await {
+                Ok(res) => {
+                    node.record_success(start.elapsed(), Some(res.state().block_height))
+                        .await;
+                    return Ok(res);
+                }
+                Err(e) => {
+                    RPC_ERRORS.with_label_values(&[&self.network, name]).inc();
+                    node.record_failure(self.breaker_threshold, self.breaker_cooldown)
+                        .await;
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.expect("RPC pool is never empty"))
+    }
+}
+
+impl RpcNode {
+    async fn record_success(&self, latency: Duration, height: Option<u64>) {
+        let latency = latency.as_secs_f64() * 1000.0;
+        let mut update = self.health.write().await;
+        update.latency_ms = Some(match update.latency_ms {
+            Some(avg) => avg * (1.0 - LATENCY_WEIGHT) + latency * LATENCY_WEIGHT,
+            None => latency,
+        });
+        if let Some(height) = height {
+            update.height = update.height.max(height);
+        }
+        if update.open_until.is_some() {
+            info!("RPC {} recovered", self.url);
+        }
+        update.failures = 0;
+        update.open_until = None;
+        drop(update);
+    }
+    async fn record_failure(&self, threshold: u32, cooldown: Duration) {
+        let mut update = self.health.write().await;
+        update.failures += 1;
+        if update.failures >= threshold {
+            if update.open_until.is_none() {
+                warn!(
+                    "RPC {} circuit open after {} failures",
+                    self.url, update.failures
+                );
+            }
+            update.open_until = Some(Instant::now() + cooldown);
+        }
+        drop(update);
+    }
+}
+
+pub async fn rpc_pool_loop(abort: Abortable<Pending<()>>, pool: Arc<RpcPool>) {
+    'outer: loop {
+        pool.refresh().await;
+        for _ in 0..pool.refresh_interval.as_secs().max(1) {
+            if abort.is_aborted() {
+                info!("aborted");
+                break 'outer;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! JSON lines log of outbound alerts and the diff between the logs of a production
+//! and a shadow deployment.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::error::Err;
+use crate::process_new_block::Notification;
+
+const USAGE: &str = "usage: diff <production.jsonl> <shadow.jsonl>";
+
+/// One line of the notification log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggedNotification {
+    pub time: DateTime<Utc>,
+    pub network: String,
+    pub version: i128,
+    pub chat_id: i64,
+    pub wallet_id: i64,
+    pub wallet_address: String,
+    pub text: String,
+    pub tx_url: String,
+}
+
+impl LoggedNotification {
+    pub fn new(network: &str, notification: &Notification) -> Self {
+        LoggedNotification {
+            time: Utc::now(),
+            network: network.to_string(),
+            version: notification.version,
+            chat_id: notification.subscription.chat_id,
+            wallet_id: notification.subscription.wallet_id,
+            wallet_address: notification.subscription.wallet_address.clone(),
+            text: notification.text.clone(),
+            tx_url: notification.tx_url.to_string(),
+        }
+    }
+}
+
+pub struct NotificationLog {
+    file: Mutex<File>,
+}
+
+impl NotificationLog {
+    /// Appends to `path`, creating it when missing.
+    pub fn open(path: &str) -> Result<Self, Err> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(NotificationLog {
+            file: Mutex::new(file),
+        })
+    }
+    pub fn write(&self, notification: &LoggedNotification) -> Result<(), Err> {
+        let mut line = serde_json::to_vec(notification)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&line)?;
+        Ok(())
+    }
+}
+
+fn read_log(path: &Path) -> Result<Vec<LoggedNotification>, Err> {
+    let mut entries = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    Ok(entries)
+}
+
+/// Alert identity across deployments, the time it was sent is not compared.
+type Key = (String, i128, i64, String);
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DiffSummary {
+    pub same: usize,
+    pub only_production: usize,
+    pub only_shadow: usize,
+    pub different: usize,
+}
+
+/// Compares two logs over the versions both of them cover on each network, since the
+/// shadow usually starts later and lags or leads production.
+pub fn diff(production: &Path, shadow: &Path) -> Result<DiffSummary, Err> {
+    let production = read_log(production)?;
+    let shadow = read_log(shadow)?;
+    let mut ranges: BTreeMap<&str, ((i128, i128), (i128, i128))> = BTreeMap::new();
+    for (entries, is_production) in [(&production, true), (&shadow, false)] {
+        let mut own: BTreeMap<&str, (i128, i128)> = BTreeMap::new();
+        for entry in entries.iter() {
+            let range = own
+                .entry(entry.network.as_str())
+                .or_insert((entry.version, entry.version));
+            range.0 = range.0.min(entry.version);
+            range.1 = range.1.max(entry.version);
+        }
+        for (network, range) in own {
+            let both = ranges
+                .entry(network)
+                .or_insert(((i128::MAX, i128::MIN), (i128::MAX, i128::MIN)));
+            match is_production {
+                true => both.0 = range,
+                false => both.1 = range,
+            }
+        }
+    }
+    let overlap = |entry: &LoggedNotification| {
+        ranges
+            .get(entry.network.as_str())
+            .map_or(false, |(production, shadow)| {
+                entry.version >= production.0.max(shadow.0)
+                    && entry.version <= production.1.min(shadow.1)
+            })
+    };
+    let collect = |entries: &Vec<LoggedNotification>| {
+        let mut map: BTreeMap<Key, Vec<String>> = BTreeMap::new();
+        for entry in entries.iter().filter(|entry| overlap(entry)) {
+            map.entry((
+                entry.network.clone(),
+                entry.version,
+                entry.chat_id,
+                entry.wallet_address.clone(),
+            ))
+            .or_default()
+            .push(entry.text.clone());
+        }
+        map
+    };
+    let production = collect(&production);
+    let shadow = collect(&shadow);
+
+    let mut summary = DiffSummary::default();
+    let keys: BTreeSet<&Key> = production.keys().chain(shadow.keys()).collect();
+    for key in keys {
+        let (network, version, chat_id, wallet) = key;
+        match (production.get(key), shadow.get(key)) {
+            (Some(a), Some(b)) if a == b => summary.same += 1,
+            (Some(a), Some(b)) => {
+                summary.different += 1;
+                println!("~ [{network}] {version} chat {chat_id} {wallet}");
+                println!("  production: {:?}\n  shadow:     {:?}", a, b);
+            }
+            (Some(a), None) => {
+                summary.only_production += 1;
+                println!("- [{network}] {version} chat {chat_id} {wallet}: {:?}", a);
+            }
+            (None, Some(b)) => {
+                summary.only_shadow += 1;
+                println!("+ [{network}] {version} chat {chat_id} {wallet}: {:?}", b);
+            }
+            (None, None) => {}
+        }
+    }
+    for (network, (production, shadow)) in ranges.iter() {
+        let (from, to) = (production.0.max(shadow.0), production.1.min(shadow.1));
+        match from <= to {
+            true => println!("[{network}] compared versions {from}..={to}"),
+            false => println!("[{network}] logs do not overlap"),
+        }
+    }
+    Ok(summary)
+}
+
+/// Entry point of `aptos-tracking diff <production.jsonl> <shadow.jsonl>`.
+pub fn run(args: &[String]) -> Result<(), Err> {
+    let (production, shadow) = match args {
+        [production, shadow, ..] => (production, shadow),
+        _ => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+    };
+    let summary = diff(Path::new(production), Path::new(shadow))?;
+    println!(
+        "{} same, {} only in production, {} only in shadow, {} different",
+        summary.same, summary.only_production, summary.only_shadow, summary.different
+    );
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Suppression of airdropped scam and dust tokens, backed by `token_info.is_skipped`.
+
+use crate::config::SpamConfig;
+use crate::db::TokenMap;
+use crate::tx::{BalanceChange, Token};
+
+/// `is_skipped` of a token an operator vetted, the heuristics never flag it.
+pub const ALLOWED: i16 = -1;
+/// `is_skipped` of a token nobody looked at.
+pub const UNFLAGGED: i16 = 0;
+/// `is_skipped` of a token an operator skipped.
+pub const SKIPPED: i16 = 1;
+/// `is_skipped` of a token the heuristics flagged. Advisory, it only holds while the
+/// token has no price.
+pub const FLAGGED: i16 = 2;
+
+/// A token the heuristics caught, with the metadata its `token_info` row gets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpamToken {
+    pub symbol: Option<String>,
+    pub decimals: u8,
+}
+
+/// What to do with one balance change of a tracked wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Keep,
+    /// The token is skipped in `token_info`.
+    Skipped,
+    /// Unsolicited deposit worth less than `spam.dust_usd`.
+    Dust,
+    /// Unsolicited deposit of an unpriced token from a sender nobody tracks, to be
+    /// flagged.
+    Spam,
+}
+
+/// Whether `token_info` says alerts and totals should ignore `mint`. A flag the
+/// heuristics set is dropped once the token gets a price.
+pub fn is_skipped(token: &TokenMap, mint: &str) -> bool {
+    token
+        .get(mint)
+        .map_or(false, |entry| match entry.is_skipped {
+            FLAGGED => entry.value <= 0.0,
+            is_skipped => is_skipped > UNFLAGGED,
+        })
+}
+
+/// Classifies `change`. `unsolicited` is true when the wallet neither paid gas nor
+/// sent anything in the transaction, which is how airdrops look to the recipient.
+/// `known_sender` is true when the other side of the transfer is a tracked wallet.
+pub fn classify(
+    change: &BalanceChange,
+    unsolicited: bool,
+    known_sender: bool,
+    token: &TokenMap,
+    config: &SpamConfig,
+) -> Verdict {
+    let mint = change.to_token_address();
+    if is_skipped(token, &mint) {
+        return Verdict::Skipped;
+    }
+    if !config.enabled || !unsolicited || change.amount() <= 0 {
+        return Verdict::Keep;
+    }
+    let entry = token.get(&mint);
+    if entry.map_or(false, |entry| entry.is_skipped == ALLOWED) {
+        return Verdict::Keep;
+    }
+    let priced = entry.map_or(false, |entry| entry.value > 0.0);
+    if !priced {
+        return match (change.token(), known_sender) {
+            (Token::TA(_) | Token::Coin(_), false) => Verdict::Spam,
+            _ => Verdict::Keep,
+        };
+    }
+    match change.to_usd_change(token) {
+        Some(value) if value < config.dust_usd => Verdict::Dust,
+        _ => Verdict::Keep,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, Verdict};
+    use crate::config::SpamConfig;
+    use crate::db::TokenMap;
+    use crate::tx::BalanceChange;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+    use std::str::FromStr;
+
+    const ALICE: &str = "0x00000000000000000000000000000000000000000000000000000000000a11ce";
+
+    /// A deposit of `asset_type` to Alice, as its indexer row decodes.
+    fn airdrop(event_type: &str, asset_type: &str) -> BalanceChange {
+        BalanceChange::from_event(
+            event_type.to_string(),
+            1_000_000,
+            6,
+            AccountAddress::from_str(ALICE).unwrap(),
+            asset_type.to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn only_unpriced_airdrops_from_unknown_senders_are_spam() {
+        let config = SpamConfig::default();
+        assert!(!config.auto_flag);
+        let token = TokenMap::new();
+        for change in [
+            airdrop(
+                "0x1::fungible_asset::Deposit",
+                "0x00000000000000000000000000000000000000000000000000000000000005dc",
+            ),
+            airdrop("0x1::coin::DepositEvent", "0xc1a1a::claim::Claim"),
+        ] {
+            assert_eq!(
+                classify(&change, true, false, &token, &config),
+                Verdict::Spam
+            );
+            assert_eq!(
+                classify(&change, true, true, &token, &config),
+                Verdict::Keep
+            );
+            assert_eq!(
+                classify(&change, false, false, &token, &config),
+                Verdict::Keep
+            );
+        }
+    }
+
+    #[test]
+    fn apt_is_never_spam() {
+        let change = airdrop("0x1::coin::DepositEvent", "0x1::aptos_coin::AptosCoin");
+        assert_eq!(
+            classify(
+                &change,
+                true,
+                false,
+                &TokenMap::new(),
+                &SpamConfig::default()
+            ),
+            Verdict::Keep
+        );
+    }
+}
@@ -1,49 +1,289 @@
+use crate::config::{IndexerConfig, IndexerEndpointConfig};
 use crate::db::TokenMap;
 use crate::error::Err;
+use crate::metrics::{INDEXER_ERRORS, INDEXER_LATENCY};
+use crate::query::indexer_status_query;
+use crate::query::token_metadata_query;
 use crate::query::transaction_query::{self, ResponseData};
 use crate::query::transactions_query::{self, TransactionsQueryFungibleAssetActivities, Variables};
-use crate::query::{TransactionQuery, TransactionsQuery};
+use crate::query::{balances_query, versions_activity_query, wallet_activity_query};
+use crate::query::{
+    BalancesQuery, IndexerStatusQuery, TokenMetadataQuery, TransactionQuery, TransactionsQuery,
+    VersionsActivityQuery, WalletActivityQuery,
+};
 use aptos_sdk::crypto::HashValue;
 use aptos_sdk::move_types::account_address::AccountAddress;
 use aptos_sdk::rest_client::aptos_api_types::{TransactionData, TransactionOnChainData};
 use aptos_sdk::rest_client::Client;
 use aptos_sdk::types::bytes;
-use graphql_client::{reqwest::post_graphql, GraphQLQuery};
+use graphql_client::GraphQLQuery;
 use log::warn;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use reqwest::Client as rqwClient;
 use sqlx::Transaction;
 use std::collections::HashMap;
 use std::ops::Sub;
 use std::option::Option;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+/// Coin type of APT, its key in `token_info`.
+pub const APT_COIN: &str = "0x1::aptos_coin::AptosCoin";
+/// Fungible asset metadata address of APT.
+const APT_FA: &str = "0xa";
+
+/// Indexer processor that fills `fungible_asset_activities`.
+pub const FUNGIBLE_ASSET_PROCESSOR: &str = "fungible_asset_processor";
+
+#[derive(Debug, Clone)]
+pub struct IndexerEndpoint {
+    pub url: String,
+    pub headers: HeaderMap,
+    pub timeout: Duration,
+}
+
+impl IndexerEndpoint {
+    /// Fails when the api key or a header is not a valid HTTP header.
+    pub fn from_config(config: &IndexerEndpointConfig) -> Result<Self, Err> {
+        let mut headers = HeaderMap::new();
+        if let Some(key) = &config.api_key {
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {key}"))?,
+            );
+        }
+        for (name, value) in config.headers.iter() {
+            headers.insert(HeaderName::from_str(name)?, HeaderValue::from_str(value)?);
+        }
+        Ok(IndexerEndpoint {
+            url: config.url.clone(),
+            headers,
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+}
+
+/// Indexer client that tries each endpoint in order, skipping the ones that error or
+/// have not indexed the requested version yet, and retries the round with backoff.
 #[derive(Debug, Clone)]
 pub struct TxQueryClient {
     pub client: Arc<rqwClient>,
-    pub url: String,
+    pub endpoints: Arc<Vec<IndexerEndpoint>>,
+    pub retries: u32,
+    pub backoff: Duration,
+    indexed_version: Arc<Vec<AtomicU64>>,
 }
 impl TxQueryClient {
+    pub fn new(
+        client: Arc<rqwClient>,
+        endpoints: Vec<IndexerEndpoint>,
+        retries: u32,
+        backoff: Duration,
+    ) -> Self {
+        assert!(!endpoints.is_empty(), "Graphql url needed");
+        TxQueryClient {
+            client,
+            indexed_version: Arc::new(endpoints.iter().map(|_| AtomicU64::new(0)).collect()),
+            endpoints: Arc::new(endpoints),
+            retries,
+            backoff,
+        }
+    }
+    /// Endpoints are tried in the order they are configured.
+    pub fn from_config(client: Arc<rqwClient>, config: &IndexerConfig) -> Result<Self, Err> {
+        Ok(Self::new(
+            client,
+            config
+                .endpoints
+                .iter()
+                .map(IndexerEndpoint::from_config)
+                .collect::<Result<Vec<IndexerEndpoint>, Err>>()?,
+            config.retries,
+            Duration::from_millis(config.backoff_ms),
+        ))
+    }
     pub async fn get_tx_by_version(
         &self,
         version: u64,
-    ) -> Result<graphql_client::Response<transaction_query::ResponseData>, reqwest::Error> {
+    ) -> Result<graphql_client::Response<transaction_query::ResponseData>, Err> {
         let version_bytes = version.to_le_bytes();
 
         let version = i128::from_le_bytes(concat_arrays(version_bytes, 0u64.to_le_bytes()));
-        post_graphql::<TransactionQuery, _>(
-            self.client.as_ref(),
-            self.url.clone(),
+        self.query::<TransactionQuery>(
             transaction_query::Variables { eq: Some(version) },
+            Some(version),
         )
         .await
     }
     pub async fn get_tx_in_range(
         &self,
         variables: Variables,
-    ) -> Result<graphql_client::Response<transactions_query::ResponseData>, reqwest::Error> {
-        post_graphql::<TransactionsQuery, _>(self.client.as_ref(), self.url.clone(), variables)
+    ) -> Result<graphql_client::Response<transactions_query::ResponseData>, Err> {
+        let min_version = variables.lte;
+        self.query::<TransactionsQuery>(variables, min_version)
+            .await
+    }
+    pub async fn get_indexer_status(
+        &self,
+    ) -> Result<graphql_client::Response<indexer_status_query::ResponseData>, Err> {
+        self.query::<IndexerStatusQuery>(status_variables(), None)
+            .await
+    }
+
+    /// Name, symbol and decimals of each asset type the indexer knows.
+    pub async fn get_token_metadata(
+        &self,
+        assets: Vec<String>,
+    ) -> Result<graphql_client::Response<token_metadata_query::ResponseData>, Err> {
+        self.query::<TokenMetadataQuery>(
+            token_metadata_query::Variables {
+                assets: Some(assets),
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Up to 500 successful activity rows of `owner` between `from` and `to`, after
+    /// version `after`, oldest first.
+    pub async fn get_wallet_activity(
+        &self,
+        variables: wallet_activity_query::Variables,
+    ) -> Result<graphql_client::Response<wallet_activity_query::ResponseData>, Err> {
+        self.query::<WalletActivityQuery>(variables, None).await
+    }
+
+    /// Every activity row of the successful transactions at `versions`.
+    pub async fn get_versions_activity(
+        &self,
+        versions: Vec<i128>,
+    ) -> Result<graphql_client::Response<versions_activity_query::ResponseData>, Err> {
+        self.query::<VersionsActivityQuery>(
+            versions_activity_query::Variables {
+                versions: Some(versions),
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Current non-zero coin and fungible asset balances of `owner`.
+    pub async fn get_balances(
+        &self,
+        owner: &str,
+    ) -> Result<graphql_client::Response<balances_query::ResponseData>, Err> {
+        self.query::<BalancesQuery>(
+            balances_query::Variables {
+                owner: Some(owner.to_string()),
+            },
+            None,
+        )
+        .await
+    }
+
+    async fn query<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+        min_version: Option<i128>,
+    ) -> Result<graphql_client::Response<Q::ResponseData>, Err>
+    where
+        Q::Variables: Clone,
+    {
+        let mut last_err = Err::IndexerStale;
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                tokio::time::sleep(self.backoff * 2_u32.saturating_pow(attempt - 1)).await;
+            }
+            for (index, endpoint) in self.endpoints.iter().enumerate() {
+                if let Some(min_version) = min_version {
+                    match self.is_fresh(index, min_version).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            warn!("indexer {} has not reached {}", endpoint.url, min_version);
+                            last_err = Err::IndexerStale;
+                            continue;
+                        }
+                        Err(e) => {
+                            warn!("indexer {} status error: {:?}", endpoint.url, e);
+                            last_err = e;
+                            continue;
+                        }
+                    }
+                }
+                match self.post::<Q>(endpoint, variables.clone()).await {
+                    Ok(res) => return Ok(res),
+                    Err(e) => {
+                        warn!("indexer {} error: {:?}", endpoint.url, e);
+                        last_err = e;
+                    }
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Whether the endpoint has indexed `version`, the status is only fetched again
+    /// while the cached one is behind.
+    async fn is_fresh(&self, index: usize, version: i128) -> Result<bool, Err> {
+        let known = self.indexed_version[index].load(Ordering::Relaxed);
+        if u64_to_i128(known) >= version {
+            return Ok(true);
+        }
+        let status = self
+            .post::<IndexerStatusQuery>(&self.endpoints[index], status_variables())
+            .await?;
+        let last_success = status
+            .data
+            .and_then(|data| data.processor_status.into_iter().next())
+            .map_or(0, |status| status.last_success_version);
+        let last_success: u64 = last_success.try_into().unwrap_or(0);
+        self.indexed_version[index].fetch_max(last_success, Ordering::Relaxed);
+        Ok(u64_to_i128(last_success) >= version)
+    }
+
+    async fn post<Q: GraphQLQuery>(
+        &self,
+        endpoint: &IndexerEndpoint,
+        variables: Q::Variables,
+    ) -> Result<graphql_client::Response<Q::ResponseData>, Err> {
+        let timer = INDEXER_LATENCY.start_timer();
+        let result = match self
+            .client
+            .post(endpoint.url.as_str())
+            .headers(endpoint.headers.clone())
+            .timeout(endpoint.timeout)
+            .json(&Q::build_query(variables))
+            .send()
             .await
+            .and_then(|res| res.error_for_status())
+        {
+            Ok(res) => {
+                res.json::<graphql_client::Response<Q::ResponseData>>()
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        timer.observe_duration();
+        match result {
+            Ok(res) if res.errors.as_ref().map_or(false, |e| !e.is_empty()) => {
+                INDEXER_ERRORS.inc();
+                warn!("indexer {} returned {:?}", endpoint.url, res.errors);
+                Err(Err::IndexerQuery)
+            }
+            Ok(res) => Ok(res),
+            Err(e) => {
+                INDEXER_ERRORS.inc();
+                Err(Err::Rqw(e))
+            }
+        }
+    }
+}
+
+fn status_variables() -> indexer_status_query::Variables {
+    indexer_status_query::Variables {
+        processor: Some(FUNGIBLE_ASSET_PROCESSOR.to_string()),
     }
 }
 
@@ -84,6 +324,8 @@ impl TxQueryClient {
 //         .collect::<Vec<(String, u64)>>())
 // }
 
+/// Balance changes of the transaction at `version`, `None` when the indexer has no
+/// fungible asset activity for it.
 pub async fn get_tx_detail(
     conn: &TxQueryClient,
     version: u64,
@@ -97,7 +339,9 @@ pub async fn get_tx_detail(
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A signed change of one owner's balance of one asset, decoded from an indexer
+/// `fungible_asset_activities` row. Positive amounts are received.
+#[derive(Debug, Clone)]
 pub struct BalanceChange {
     change: i128,
     address: AccountAddress,
@@ -114,11 +358,13 @@ impl Default for BalanceChange {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+/// Asset moved by a [`BalanceChange`]: APT, a fungible asset or another coin.
+#[derive(Debug, Clone, Default)]
 pub enum Token {
     #[default]
     Native,
     TA(TokenAccount),
+    Coin(CoinType),
 }
 #[derive(Debug, Clone, Copy)]
 pub struct TokenAccount {
@@ -137,7 +383,41 @@ impl Default for TokenAccount {
     }
 }
 
+/// A coin other than APT, named by its Move type instead of an address.
+#[derive(Debug, Clone)]
+pub struct CoinType {
+    coin_type: String,
+    decimal: u8,
+}
+
 impl BalanceChange {
+    /// Raw signed amount in the asset's smallest unit.
+    pub fn amount(&self) -> i128 {
+        self.change
+    }
+    /// Account whose balance changed.
+    pub fn owner(&self) -> AccountAddress {
+        match &self.token {
+            Token::TA(s) => s.owner,
+            Token::Native | Token::Coin(_) => self.address,
+        }
+    }
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+    /// Decimals of the asset, 8 for APT.
+    pub fn decimals(&self) -> u8 {
+        match &self.token {
+            Token::Native => 8,
+            Token::TA(a) => a.decimal,
+            Token::Coin(c) => c.decimal,
+        }
+    }
+    /// Signed amount in whole tokens.
+    pub fn to_ui_f64(&self) -> f64 {
+        self.change as f64 / 10_f64.powi(i32::from(self.decimals()))
+    }
+    /// USD value of the change with the `token_info` price, `None` when unpriced.
     pub fn to_usd_change(&self, token_map: &TokenMap) -> Option<f64> {
         if let Some(entry) = token_map.get(&self.to_token_address()) {
             if let Ok(change) = f64::from_str(&self.change.to_string()) {
@@ -198,29 +478,29 @@ impl BalanceChange {
         }
     }
     pub fn to_ui_amount(&self) -> String {
-        match self.token {
-            Token::Native => Decimal {
-                amount: self.change.abs(),
-                decimal: 8,
-            },
-            Token::TA(a) => Decimal {
-                amount: self.change.abs(),
-                decimal: a.decimal,
-            },
+        Decimal {
+            amount: self.change.abs(),
+            decimal: self.decimals(),
         }
         .to_string()
     }
+    /// Key of the asset in `token_info` and [`TokenMap`].
     pub fn to_token_address(&self) -> String {
-        match self.token {
-            Token::Native => "0x1::aptos_coin::AptosCoin".to_string(),
+        match &self.token {
+            Token::Native => APT_COIN.to_string(),
             Token::TA(s) => {
                 format!("{}", s.token_address)
             }
+            Token::Coin(c) => c.coin_type.clone(),
         }
     }
     pub fn to_short_token_address(&self) -> String {
-        match self.token {
+        match &self.token {
             Token::Native => "APT".to_string(),
+            Token::Coin(c) => match c.coin_type.rsplit_once("::") {
+                Some((_, name)) => name.to_string(),
+                None => c.coin_type.clone(),
+            },
             Token::TA(s) => {
                 let address = s.token_address.to_string();
                 let first = address[..5].to_string();
@@ -230,29 +510,29 @@ impl BalanceChange {
         }
     }
     pub fn _to_owner(&self) -> String {
-        match self.token {
-            Token::Native => {
-                format!("{}", self.address)
-            }
-            Token::TA(s) => {
-                format!("{}", s.owner)
-            }
-        }
+        format!("{}", self.owner())
     }
     pub fn to_short_owner(&self) -> String {
-        let address = match self.token {
-            Token::Native => {
-                format!("{}", self.address)
-            }
-            Token::TA(s) => {
-                format!("{}", s.owner)
-            }
-        };
+        let address = format!("{}", self.owner());
         let first = address[..5].to_string();
         let last = address[address.len() - 5..].to_string();
         format!("{first}...{last}",)
     }
 
+    /// Another owner whose balance of the same asset moved the other way in the same
+    /// transaction, `all` being every change of that transaction.
+    pub fn counterparty(&self, all: &[BalanceChange]) -> Option<AccountAddress> {
+        let owner = self.owner();
+        let token = self.to_token_address();
+        all.iter()
+            .find(|other| {
+                other.owner() != owner
+                    && other.change.is_positive() != self.change.is_positive()
+                    && other.to_token_address() == token
+            })
+            .map(BalanceChange::owner)
+    }
+
     pub fn from_indexer_response(res: ResponseData) -> Option<Vec<BalanceChange>> {
         if res.fungible_asset_activities.len() == 0 {
             return None;
@@ -279,6 +559,8 @@ impl BalanceChange {
         }
         Some(result)
     }
+    /// Decodes the rows of one transaction, `None` when none of them moves a
+    /// supported asset.
     pub fn from_events(
         events: Vec<TransactionsQueryFungibleAssetActivities>,
     ) -> Option<Vec<BalanceChange>> {
@@ -303,6 +585,9 @@ impl BalanceChange {
         Some(result)
     }
 
+    /// Decodes one activity row: withdrawals, deposits and gas fees of coins and
+    /// fungible assets. Fungible assets are keyed by their metadata address and other
+    /// coins by their type, APT in either form is folded into native APT.
     pub fn from_event(
         event_type: String,
         amount: i128,
@@ -310,38 +595,52 @@ impl BalanceChange {
         owner: AccountAddress,
         asset_type: String,
     ) -> Option<Self> {
-        match event_type.as_str() {
-            "0x1::aptos_coin::GasFeeEvent" => Some(BalanceChange {
+        if event_type == "0x1::aptos_coin::GasFeeEvent" {
+            return Some(BalanceChange {
                 address: owner,
                 change: amount * -1,
                 token: Token::Native,
+            });
+        }
+        let token = match AccountAddress::from_str(&asset_type) {
+            Ok(address) if AccountAddress::from_str(APT_FA).map_or(false, |apt| apt == address) => {
+                Token::Native
+            }
+            Ok(address) => Token::TA(TokenAccount {
+                token_address: address,
+                decimal,
+                owner,
             }),
-            "0x1::coin::WithdrawEvent" => Some(BalanceChange {
-                address: owner,
-                change: amount * -1,
-                token: Token::Native,
+            Err(_) if asset_type == APT_COIN => Token::Native,
+            Err(_) if asset_type.contains("::") => Token::Coin(CoinType {
+                coin_type: asset_type,
+                decimal,
             }),
-            "0x1::coin::DepositEvent" => Some(BalanceChange {
-                address: owner,
-                change: amount,
-                token: Token::Native,
-            }),
-            // "0x1::fungible_asset::Deposit" => {}
-            // "0x1::fungible_asset::Withdraw" => {}
-            _ => None,
-        }
+            Err(_) => return None,
+        };
+        let change = match event_type.as_str() {
+            "0x1::coin::WithdrawEvent" | "0x1::fungible_asset::Withdraw" => amount * -1,
+            "0x1::coin::DepositEvent" | "0x1::fungible_asset::Deposit" => amount,
+            _ => return None,
+        };
+        Some(BalanceChange {
+            address: owner,
+            change,
+            token,
+        })
     }
 }
+/// Narrows balance changes down to one wallet.
 pub trait Filter {
     fn get_by_key(self, key: &AccountAddress) -> Self;
 }
 impl Filter for Vec<BalanceChange> {
     fn get_by_key(self, key: &AccountAddress) -> Self {
         self.into_iter()
-            .filter(|i| match (i.token, i.address.eq(key)) {
+            .filter(|i| match (&i.token, i.address.eq(key)) {
                 (_, true) => true,
                 (Token::TA(t), false) => t.owner.eq(key),
-                (Token::Native, false) => false,
+                (Token::Native | Token::Coin(_), false) => false,
             })
             .collect::<Self>()
     }
@@ -351,12 +650,16 @@ pub struct ReplyInfo {
     pub value: f64,
 }
 
+/// Fixed point amount printed with `decimal` digits after the point.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Decimal {
     amount: i128,
     decimal: u8,
 }
 impl Decimal {
+    pub fn new(amount: i128, decimal: u8) -> Self {
+        Decimal { amount, decimal }
+    }
     pub fn to_string(&self) -> String {
         let decimal: usize = self.decimal.into();
         //9
@@ -0,0 +1,66 @@
+//! JSON POST sink for alerts and health reports, for deployments that consume them
+//! outside Telegram.
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client as rqwClient;
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::WebhookConfig;
+use crate::error::Err;
+use crate::shadow::LoggedNotification;
+
+/// Body of a webhook POST, tagged by `kind`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WebhookEvent<'a> {
+    Alert(&'a LoggedNotification),
+    Report {
+        text: &'a str,
+    },
+    /// Alerts held during the quiet hours of a chat.
+    Summary {
+        chat_id: i64,
+        text: &'a str,
+    },
+}
+
+pub struct Webhook {
+    client: Arc<rqwClient>,
+    url: String,
+    headers: HeaderMap,
+    timeout: Duration,
+}
+
+impl Webhook {
+    /// `None` when `webhook.url` is not set.
+    pub fn from_config(client: Arc<rqwClient>, config: &WebhookConfig) -> Option<Self> {
+        let url = config.url.clone()?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in config.headers.iter() {
+            headers.insert(
+                HeaderName::from_str(name).expect("webhook header name invalid"),
+                HeaderValue::from_str(value).expect("webhook header value invalid"),
+            );
+        }
+        Some(Webhook {
+            client,
+            url,
+            headers,
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+    pub async fn post(&self, event: &WebhookEvent<'_>) -> Result<(), Err> {
+        self.client
+            .post(self.url.as_str())
+            .headers(self.headers.clone())
+            .timeout(self.timeout)
+            .json(event)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}